@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io;
@@ -91,10 +93,7 @@ static OPERATIONS: phf::Map<u32, Operation> = phf_map! {
     execute: |program: &mut Vec<i32>, counter: &mut usize, op_instance: &OperationInstance| -> usize {
       let size = validate_operation(program, *counter, op_instance);
       let dst = program[*counter + 1];
-      let mut input = String::new();
-      io::stdin().read_line(&mut input).unwrap();
-      input = input.trim().to_string();
-      program[dst as usize] = input.parse::<i32>().unwrap();
+      program[dst as usize] = read_input();
       *counter + size
     },
   },
@@ -104,7 +103,7 @@ static OPERATIONS: phf::Map<u32, Operation> = phf_map! {
     execute: |program: &mut Vec<i32>, counter: &mut usize, op_instance: &OperationInstance| -> usize {
       let size = validate_operation(program, *counter, op_instance);
       let dst = program[*counter + 1];
-      println!("{}", program[dst as usize]);
+      write_output(program[dst as usize]);
       *counter + size
     },
   },
@@ -179,6 +178,28 @@ static OPERATIONS: phf::Map<u32, Operation> = phf_map! {
   },
 };
 
+thread_local! {
+  // when set, INPUT and PRINT use these rather than stdin and stdout
+  static CAPTURED_IO: RefCell<Option<(VecDeque<i32>, Vec<i32>)>> = const { RefCell::new(None) };
+}
+
+fn read_input() -> i32 {
+  let captured = CAPTURED_IO.with(|io| io.borrow_mut().as_mut().map(|(inputs, _)| inputs.pop_front()));
+  if let Some(input) = captured {
+    return input.expect("no more captured inputs")
+  }
+  let mut input = String::new();
+  io::stdin().read_line(&mut input).unwrap();
+  input.trim().parse::<i32>().unwrap()
+}
+
+fn write_output(value: i32) {
+  let captured = CAPTURED_IO.with(|io| io.borrow_mut().as_mut().map(|(_, outputs)| outputs.push(value)));
+  if captured.is_none() {
+    println!("{}", value);
+  }
+}
+
 fn validate_jump_destination(jump_destination: i32, program: & Vec<i32>,) -> usize {
   if jump_destination < 0 {
     panic!("invalid jump operation, negative destination '{}'", jump_destination);
@@ -332,3 +353,49 @@ fn run_program(program: &mut Vec<i32>) {
     }
   }
 }
+
+#[cfg(test)]
+mod differential_tests {
+  use super::*;
+  use intcode_machine::Mismatch;
+  use intcode_machine::compare;
+  use intcode_machine::differential_test;
+
+  fn run_captured(program: &mut Vec<i32>, inputs: &[i32], max_steps: usize) -> Option<Vec<i32>> {
+    CAPTURED_IO.with(|io| *io.borrow_mut() = Some((inputs.iter().cloned().collect(), Vec::new())));
+    // run_program, stopping after max_steps
+    let mut counter: usize = 0;
+    let mut steps = 0;
+    let halted = loop {
+      if program[counter] == 99 {
+        break true
+      }
+      if steps == max_steps {
+        break false
+      }
+      steps += 1;
+      if !execute_instruction_at(program, &mut counter) {
+        break true
+      }
+    };
+    let outputs = CAPTURED_IO.with(|io| io.borrow_mut().take().unwrap().1);
+    if halted { Some(outputs) } else { None }
+  }
+
+  #[test]
+  fn differs_from_intcode_machine() {
+    // PRINT here always reads its parameter as an address, so an immediate
+    // value is printed from the wrong address, or faults if out of range
+    let found = differential_test(&run_captured, 1, 500);
+    assert!(found.iter().any(|x| x.mismatch == Mismatch::Outputs));
+    for counterexample in &found {
+      // each still shows its mismatch, and none of its shrinks do
+      let again = compare(&counterexample.generated, &run_captured).unwrap();
+      assert_eq!(again.mismatch, counterexample.mismatch);
+      for candidate in counterexample.generated.shrink() {
+        let mismatch = compare(&candidate, &run_captured).map(|x| x.mismatch);
+        assert_ne!(mismatch, Some(counterexample.mismatch), "{} shrinks to {}", counterexample, candidate);
+      }
+    }
+  }
+}
@@ -0,0 +1,38 @@
+use std::time::Instant;
+
+extern crate intcode_machine;
+
+use intcode_machine::Program;
+use intcode_machine::run_intcode_program;
+
+// the day 5 diagnostic program, which is self-modifying, and mostly runs each
+// instruction only once
+const DIAGNOSTIC_PROGRAM: &str = include_str!("../tests/data/day05.csv");
+
+// counts down from 10000, so the same two instructions run over and over
+const COUNTDOWN_PROGRAM: &str = "101,-1,10,10,1005,10,0,104,7,99,10000";
+
+fn bench(name: &str, program: &str, inputs: Vec<i32>, iterations: u32) {
+  let program = Program::from_string(program);
+  // warm up
+  for _ in 0..iterations / 10 {
+    run_intcode_program(&mut program.clone(), &Some(inputs.clone()));
+  }
+  let start = Instant::now();
+  for _ in 0..iterations {
+    run_intcode_program(&mut program.clone(), &Some(inputs.clone()));
+  }
+  let elapsed = start.elapsed();
+  println!(
+    "{}: {} iterations in {:?}, {:.2} us per run",
+    name,
+    iterations,
+    elapsed,
+    elapsed.as_secs_f64() * 1e6 / f64::from(iterations));
+}
+
+fn main() {
+  bench("day05 diagnostic, system id 1", DIAGNOSTIC_PROGRAM, vec![1], 20_000);
+  bench("day05 diagnostic, system id 5", DIAGNOSTIC_PROGRAM, vec![5], 20_000);
+  bench("countdown loop", COUNTDOWN_PROGRAM, vec![], 200);
+}
@@ -0,0 +1,522 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::ascii::AsciiMachine;
+use super::ascii::AsciiStatus;
+use super::program::Program;
+use super::program::Snapshot;
+
+/// A room as the game describes it, e.g.
+///
+/// ```text
+/// == Hull Breach ==
+/// You got in through a hole in the floor here.
+///
+/// Doors here lead:
+/// - north
+///
+/// Items here:
+/// - mug
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct Room {
+  pub name: String,
+  pub description: String,
+  pub doors: Vec<String>,
+  pub items: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+  Description,
+  Doors,
+  Items,
+  Other,
+}
+
+/// The rooms described in the text, in order, so the last one is where the
+/// player ended up, even if they were sent back from another.
+pub
+fn parse_rooms(text: &str) -> Vec<Room> {
+  let mut rooms: Vec<Room> = Vec::new();
+  let mut section = Section::Other;
+  for line in text.lines() {
+    let line = line.trim();
+    if line.len() > 6 && line.starts_with("== ") && line.ends_with(" ==") {
+      rooms.push(Room { name: line[3..line.len() - 3].to_string(), ..Default::default() });
+      section = Section::Description;
+      continue;
+    }
+    let room = match rooms.last_mut() {
+      Some(room) => room,
+      None => continue,
+    };
+    match (line, section) {
+      ("Doors here lead:", _) => section = Section::Doors,
+      ("Items here:", _) => section = Section::Items,
+      ("", Section::Description) if !room.description.is_empty() => section = Section::Other,
+      ("", _) => {},
+      (_, Section::Description) => {
+        if !room.description.is_empty() {
+          room.description.push('\n');
+        }
+        room.description += line;
+      },
+      (_, Section::Doors) | (_, Section::Items) => match line.strip_prefix("- ") {
+        Some(name) if section == Section::Doors => room.doors.push(name.to_string()),
+        Some(name) => room.items.push(name.to_string()),
+        None => section = Section::Other,
+      },
+      (_, Section::Other) => {},
+    }
+  }
+  rooms
+}
+
+/// What the game said in reply to a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+enum Reply {
+  /// the game waits for the next command
+  Waiting(String),
+  /// the game has ended
+  Over(String),
+  /// the game did not finish replying, e.g. it was stuck in a loop
+  Hung(String),
+}
+
+impl Reply {
+  pub
+  fn text(&self) -> &str {
+    match self {
+      Reply::Waiting(text) | Reply::Over(text) | Reply::Hung(text) => text,
+    }
+  }
+}
+
+/// A game played with text commands, which can be saved and loaded, so a
+/// command can be tried and then taken back.
+pub
+trait TextGame {
+  type Save;
+
+  /// The reply to no command, when the game starts.
+  fn start(&mut self) -> Reply;
+
+  fn send(&mut self, command: &str) -> Reply;
+
+  fn save(&self) -> Self::Save;
+
+  fn load(&mut self, save: &Self::Save);
+}
+
+/// A text game written in Intcode, talking in ASCII.
+#[derive(Debug)]
+pub
+struct IntcodeGame<'a> {
+  machine: AsciiMachine<'a>,
+  /// steps allowed per reply before the game counts as hung
+  pub max_steps: usize,
+}
+
+impl<'a> IntcodeGame<'a> {
+  pub
+  fn new(program: Program, max_steps: usize) -> IntcodeGame<'a> {
+    IntcodeGame { machine: AsciiMachine::new(program), max_steps }
+  }
+
+  fn reply(&mut self) -> Reply {
+    let context = self.machine.program_instance().context.get_mut();
+    context.limits.max_steps = Some(context.steps.get() + self.max_steps);
+    let (status, output) = self.machine.run();
+    let mut text = output.text;
+    for value in output.values {
+      text += &format!("{}\n", value);
+    }
+    match status {
+      AsciiStatus::NeedInput => Reply::Waiting(text),
+      AsciiStatus::Halted => Reply::Over(text),
+      AsciiStatus::Stopped(_) => Reply::Hung(text),
+    }
+  }
+}
+
+impl<'a> TextGame for IntcodeGame<'a> {
+  type Save = Snapshot;
+
+  fn start(&mut self) -> Reply {
+    self.reply()
+  }
+
+  fn send(&mut self, command: &str) -> Reply {
+    self.machine.send_line(command);
+    self.reply()
+  }
+
+  fn save(&self) -> Snapshot {
+    self.machine.snapshot()
+  }
+
+  fn load(&mut self, save: &Snapshot) {
+    self.machine.restore(save);
+  }
+}
+
+/// Everything found by `explore`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct Exploration {
+  pub rooms: BTreeMap<String, Room>,
+  /// the doors to take from the start to each room
+  pub paths: BTreeMap<String, Vec<String>>,
+  /// items which can be taken and carried, with the room they are in
+  pub safe_items: BTreeMap<String, String>,
+  /// items which end or hang the game when taken, or stop the player moving
+  pub dangerous_items: BTreeSet<String>,
+  /// the room with a door which sends the player back, and that door
+  pub checkpoint: Option<(String, String)>,
+}
+
+impl fmt::Display for Exploration {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (name, path) in &self.paths {
+      writeln!(f, "room '{}': {}", name, path.join(", "))?;
+    }
+    for (item, room) in &self.safe_items {
+      writeln!(f, "safe item '{}' in '{}'", item, room)?;
+    }
+    for item in &self.dangerous_items {
+      writeln!(f, "dangerous item '{}'", item)?;
+    }
+    match &self.checkpoint {
+      Some((room, door)) => writeln!(f, "checkpoint '{}' through '{}'", room, door),
+      None => writeln!(f, "no checkpoint"),
+    }
+  }
+}
+
+// the room the reply leaves the player in
+fn arrival(reply: &Reply) -> Option<Room> {
+  match reply {
+    Reply::Waiting(text) | Reply::Over(text) => parse_rooms(text).pop(),
+    Reply::Hung(_) => None,
+  }
+}
+
+fn can_move<G: TextGame>(game: &mut G, room: &Room) -> bool {
+  let save = game.save();
+  room.doors.iter().any(|door| {
+    let reply = game.send(door);
+    game.load(&save);
+    arrival(&reply).is_some_and(|x| x.name != room.name)
+  })
+}
+
+fn visit<G: TextGame>(game: &mut G, room: Room, path: Vec<String>, exploration: &mut Exploration) {
+  if exploration.rooms.contains_key(&room.name) {
+    return
+  }
+  let save = game.save();
+  for item in &room.items {
+    let safe = match game.send(&format!("take {}", item)) {
+      Reply::Waiting(_) => can_move(game, &room),
+      _ => false,
+    };
+    game.load(&save);
+    if safe {
+      exploration.safe_items.insert(item.clone(), room.name.clone());
+    } else {
+      exploration.dangerous_items.insert(item.clone());
+    }
+  }
+  exploration.rooms.insert(room.name.clone(), room.clone());
+  exploration.paths.insert(room.name.clone(), path.clone());
+  for door in &room.doors {
+    let reply = game.send(door);
+    let rooms = match &reply {
+      Reply::Waiting(text) => parse_rooms(text),
+      _ => Vec::new(),
+    };
+    match rooms.last() {
+      // sent back to where the player was, through another room
+      Some(next) if rooms.len() > 1 && next.name == room.name => {
+        exploration.checkpoint = Some((room.name.clone(), door.clone()));
+      },
+      Some(next) => {
+        let mut next_path = path.clone();
+        next_path.push(door.clone());
+        visit(game, next.clone(), next_path, exploration);
+      },
+      None => {},
+    }
+    game.load(&save);
+  }
+}
+
+/// Visit every room reachable from the room in `text`, the last reply of
+/// the game, trying every item on the way, then load the game back to
+/// where it was.
+pub
+fn explore<G: TextGame>(game: &mut G, text: &str) -> Exploration {
+  let mut exploration = Exploration::default();
+  if let Some(room) = parse_rooms(text).pop() {
+    let save = game.save();
+    visit(game, room, Vec::new(), &mut exploration);
+    game.load(&save);
+  }
+  exploration
+}
+
+fn opposite(door: &str) -> Option<&'static str> {
+  match door {
+    "north" => Some("south"),
+    "south" => Some("north"),
+    "east" => Some("west"),
+    "west" => Some("east"),
+    "up" => Some("down"),
+    "down" => Some("up"),
+    _ => None,
+  }
+}
+
+// the doors from one room to another, back to where their paths part
+fn route(from: &[String], to: &[String]) -> Result<Vec<String>, String> {
+  let shared = from.iter().zip(to).take_while(|(x, y)| x == y).count();
+  let mut doors = Vec::new();
+  for door in from[shared..].iter().rev() {
+    match opposite(door) {
+      Some(back) => doors.push(back.to_string()),
+      None => return Err(format!("can't find the way back through '{}'", door)),
+    }
+  }
+  doors.extend(to[shared..].iter().cloned());
+  Ok(doors)
+}
+
+fn walk<G: TextGame>(game: &mut G, exploration: &Exploration, from: &str, to: &str) -> Result<(), String> {
+  let doors = route(&exploration.paths[from], &exploration.paths[to])?;
+  for door in &doors {
+    match game.send(door) {
+      Reply::Waiting(_) => {},
+      _ => return Err(format!("the game ended on the way to '{}'", to)),
+    }
+  }
+  Ok(())
+}
+
+/// The items which got the player past the checkpoint, and what the game
+/// said then.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct Solution {
+  pub items: Vec<String>,
+  pub text: String,
+}
+
+/// From the room in `text`, where `explore` started, collect every safe item,
+/// then go through the checkpoint with each combination of them until one
+/// is not sent back. The game is left where the winning attempt ended.
+pub
+fn solve<G: TextGame>(game: &mut G, text: &str, exploration: &Exploration) -> Result<Solution, String> {
+  let (checkpoint, door) = exploration.checkpoint.clone().ok_or("no checkpoint was found")?;
+  let mut current = parse_rooms(text).pop().ok_or("no room to start from")?.name;
+  for (item, room) in &exploration.safe_items {
+    walk(game, exploration, &current, room)?;
+    current = room.clone();
+    if let Reply::Over(_) | Reply::Hung(_) = game.send(&format!("take {}", item)) {
+      return Err(format!("the game ended taking '{}'", item))
+    }
+  }
+  walk(game, exploration, &current, &checkpoint)?;
+  let items: Vec<&String> = exploration.safe_items.keys().collect();
+  if items.len() > 20 {
+    return Err(format!("too many items to try every combination of, found '{}'", items.len()))
+  }
+  let save = game.save();
+  for combination in 0..(1usize << items.len()) {
+    game.load(&save);
+    for (index, item) in items.iter().enumerate() {
+      if combination & (1 << index) == 0 {
+        game.send(&format!("drop {}", item));
+      }
+    }
+    let reply = game.send(&door);
+    let through = match &reply {
+      Reply::Hung(_) => false,
+      _ => arrival(&reply).is_none_or(|x| x.name != checkpoint),
+    };
+    if through {
+      let carried = items.iter().enumerate()
+        .filter(|(index, _)| combination & (1 << index) != 0)
+        .map(|(_, item)| item.to_string())
+        .collect();
+      return Ok(Solution { items: carried, text: reply.text().to_string() })
+    }
+  }
+  game.load(&save);
+  Err(format!("no combination of the '{}' safe items gets past the checkpoint", items.len()))
+}
+
+#[cfg(test)]
+mod adventure_tests {
+  use super::*;
+
+  const EJECTED: &str = "\
+\n\n\n== Pressure-Sensitive Floor ==
+Analyzing...
+
+Doors here lead:
+- south
+
+A loud voice says \"Alert! Droids on this ship are heavier than the detected value!\" and you are ejected back to the checkpoint.
+
+
+
+== Security Checkpoint ==
+In the next room, a pressure-sensitive floor will verify your identity.
+Be careful.
+
+Doors here lead:
+- north
+- west
+
+Items here:
+- coin
+
+Command?
+";
+
+  // the player can leave the breach for the kitchen and the checkpoint,
+  // which lets them through carrying the mug and nothing else
+  #[derive(Debug, Clone, Default)]
+  struct Game {
+    room: &'static str,
+    inventory: BTreeSet<&'static str>,
+    stuck: bool,
+  }
+
+  impl Game {
+    fn layout(room: &str) -> (Vec<&'static str>, Vec<&'static str>) {
+      match room {
+        "Hull Breach" => (vec!["north", "east"], vec![]),
+        "Kitchen" => (vec!["south"], vec!["mug", "lava", "magnet", "loop"]),
+        "Security Checkpoint" => (vec!["north", "west"], vec!["coin"]),
+        _ => panic!("no room '{}'", room),
+      }
+    }
+
+    fn describe(&self) -> String {
+      let (doors, items) = Game::layout(self.room);
+      let mut text = format!("\n\n\n== {} ==\nA room.\n\nDoors here lead:\n", self.room);
+      for door in doors {
+        text += &format!("- {}\n", door);
+      }
+      let items: Vec<_> = items.into_iter().filter(|x| !self.inventory.contains(x)).collect();
+      if !items.is_empty() {
+        text += "\nItems here:\n";
+        for item in items {
+          text += &format!("- {}\n", item);
+        }
+      }
+      text + "\nCommand?\n"
+    }
+  }
+
+  impl TextGame for Game {
+    type Save = Game;
+
+    fn start(&mut self) -> Reply {
+      self.room = "Hull Breach";
+      Reply::Waiting(self.describe())
+    }
+
+    fn send(&mut self, command: &str) -> Reply {
+      let (doors, items) = Game::layout(self.room);
+      if let Some(item) = command.strip_prefix("take ") {
+        let item = *items.iter().find(|x| **x == item).unwrap();
+        self.inventory.insert(item);
+        return match item {
+          "lava" => Reply::Over("You melt.\n".to_string()),
+          "loop" => Reply::Hung(String::new()),
+          "magnet" => {
+            self.stuck = true;
+            Reply::Waiting("It sticks to you.\n\nCommand?\n".to_string())
+          },
+          _ => Reply::Waiting("Taken.\n\nCommand?\n".to_string()),
+        }
+      }
+      if let Some(item) = command.strip_prefix("drop ") {
+        self.inventory.remove(item);
+        return Reply::Waiting("Dropped.\n\nCommand?\n".to_string())
+      }
+      assert!(doors.contains(&command), "no door '{}' in '{}'", command, self.room);
+      if self.stuck {
+        return Reply::Waiting("You can't move!!\n\nCommand?\n".to_string())
+      }
+      self.room = match (self.room, command) {
+        ("Hull Breach", "north") => "Kitchen",
+        ("Hull Breach", "east") => "Security Checkpoint",
+        ("Security Checkpoint", "north") if self.inventory.iter().eq(["mug"].iter()) => {
+          return Reply::Over("You are let through.\n".to_string())
+        },
+        ("Security Checkpoint", "north") => return Reply::Waiting(EJECTED.to_string()),
+        _ => "Hull Breach",
+      };
+      Reply::Waiting(self.describe())
+    }
+
+    fn save(&self) -> Game {
+      self.clone()
+    }
+
+    fn load(&mut self, save: &Game) {
+      *self = save.clone();
+    }
+  }
+
+  #[test]
+  fn parses_rooms() {
+    let rooms = parse_rooms(EJECTED);
+    assert_eq!(rooms.len(), 2);
+    assert_eq!(rooms[0].name, "Pressure-Sensitive Floor");
+    assert_eq!(rooms[0].doors, vec!["south"]);
+    assert_eq!(rooms[1], Room {
+      name: "Security Checkpoint".to_string(),
+      description: "In the next room, a pressure-sensitive floor will verify your identity.\nBe careful.".to_string(),
+      doors: vec!["north".to_string(), "west".to_string()],
+      items: vec!["coin".to_string()],
+    });
+    assert!(parse_rooms("Command?\n").is_empty());
+  }
+
+  #[test]
+  fn explores_every_room_and_item() {
+    let mut game = Game::default();
+    let text = game.start().text().to_string();
+    let exploration = explore(&mut game, &text);
+    assert_eq!(exploration.paths.keys().collect::<Vec<_>>(), vec!["Hull Breach", "Kitchen", "Security Checkpoint"]);
+    assert_eq!(exploration.paths["Security Checkpoint"], vec!["east"]);
+    assert_eq!(exploration.safe_items.iter().collect::<Vec<_>>(), vec![
+      (&"coin".to_string(), &"Security Checkpoint".to_string()),
+      (&"mug".to_string(), &"Kitchen".to_string()),
+    ]);
+    assert_eq!(exploration.dangerous_items.iter().collect::<Vec<_>>(), vec!["lava", "loop", "magnet"]);
+    assert_eq!(exploration.checkpoint, Some(("Security Checkpoint".to_string(), "north".to_string())));
+    // the game is back where it started
+    assert_eq!(game.room, "Hull Breach");
+    assert!(game.inventory.is_empty());
+  }
+
+  #[test]
+  fn finds_the_items_for_the_checkpoint() {
+    let mut game = Game::default();
+    let text = game.start().text().to_string();
+    let exploration = explore(&mut game, &text);
+    let solution = solve(&mut game, &text, &exploration).unwrap();
+    assert_eq!(solution.items, vec!["mug"]);
+    assert_eq!(solution.text, "You are let through.\n");
+    assert_eq!(route(&["north".to_string()], &["east".to_string()]), Ok(vec!["south".to_string(), "east".to_string()]));
+  }
+}
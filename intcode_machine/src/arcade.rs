@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use super::instruction_type::InstructionType;
+use super::program::Interrupt;
+use super::program::Program;
+use super::program::ProgramInstance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Tile {
+  Empty,
+  Wall,
+  Block,
+  Paddle,
+  Ball,
+}
+
+impl Tile {
+  pub
+  fn from_id(id: InstructionType) -> Tile {
+    match id {
+      0 => Tile::Empty,
+      1 => Tile::Wall,
+      2 => Tile::Block,
+      3 => Tile::Paddle,
+      4 => Tile::Ball,
+      _ => panic!("expected a tile id from 0 to 4, got '{}'", id),
+    }
+  }
+
+  pub
+  fn to_char(self) -> char {
+    match self {
+      Tile::Empty => ' ',
+      Tile::Wall => '#',
+      Tile::Block => '=',
+      Tile::Paddle => '-',
+      Tile::Ball => 'o',
+    }
+  }
+}
+
+/// The tiles drawn so far, and the score, which is drawn at (-1, 0).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct Screen {
+  pub tiles: BTreeMap<(InstructionType, InstructionType), Tile>,
+  pub score: InstructionType,
+}
+
+impl Screen {
+  pub
+  fn draw(&mut self, x: InstructionType, y: InstructionType, value: InstructionType) {
+    if (x, y) == (-1, 0) {
+      self.score = value;
+    } else {
+      self.tiles.insert((x, y), Tile::from_id(value));
+    }
+  }
+
+  pub
+  fn count(&self, tile: Tile) -> usize {
+    self.tiles.values().filter(|x| **x == tile).count()
+  }
+
+  /// The position of the first tile of this kind, by row then column.
+  pub
+  fn find(&self, tile: Tile) -> Option<(InstructionType, InstructionType)> {
+    self.tiles.iter()
+      .filter(|(_, x)| **x == tile)
+      .map(|((x, y), _)| (*x, *y))
+      .min_by_key(|(x, y)| (*y, *x))
+  }
+
+  /// The tiles as text, from (0, 0), followed by the score.
+  pub
+  fn render(&self) -> String {
+    let width = self.tiles.keys().map(|(x, _)| x + 1).max().unwrap_or(0);
+    let height = self.tiles.keys().map(|(_, y)| y + 1).max().unwrap_or(0);
+    let mut output = String::new();
+    for y in 0..height {
+      let row: String = (0..width)
+        .map(|x| self.tiles.get(&(x, y)).map_or(' ', |tile| tile.to_char()))
+        .collect();
+      output += row.trim_end();
+      output.push('\n');
+    }
+    output += &format!("score: {}\n", self.score);
+    output
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Joystick {
+  Left,
+  Neutral,
+  Right,
+}
+
+impl Joystick {
+  pub
+  fn value(self) -> InstructionType {
+    match self {
+      Joystick::Left => -1,
+      Joystick::Neutral => 0,
+      Joystick::Right => 1,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum ArcadeStatus {
+  WaitingForJoystick,
+  GameOver,
+}
+
+/// An arcade cabinet, which draws the (x, y, tile) triples output by the
+/// game onto its screen, and gives it the joystick position as input.
+#[derive(Debug)]
+pub
+struct Arcade<'a> {
+  program_instance: ProgramInstance<'a>,
+  pub screen: Screen,
+  pending: Vec<InstructionType>,
+}
+
+impl<'a> Arcade<'a> {
+  pub
+  fn new(program: Program) -> Arcade<'a> {
+    Arcade { program_instance: ProgramInstance::new(program), screen: Screen::default(), pending: Vec::new() }
+  }
+
+  /// Set the number of quarters at address 0, where 2 means free play.
+  pub
+  fn insert_quarters(&mut self, quarters: InstructionType) {
+    self.program_instance.get_program_mut().instructions[0] = quarters;
+  }
+
+  /// Run until the game needs the joystick or is over, drawing as it goes.
+  pub
+  fn run(&mut self) -> ArcadeStatus {
+    loop {
+      match self.program_instance.resume() {
+        Interrupt::Output(value) => {
+          self.pending.push(value);
+          if self.pending.len() == 3 {
+            self.screen.draw(self.pending[0], self.pending[1], self.pending[2]);
+            self.pending.clear();
+          }
+        },
+        Interrupt::NeedInput => return ArcadeStatus::WaitingForJoystick,
+        Interrupt::Halted => return ArcadeStatus::GameOver,
+        Interrupt::Stopped(limit) => panic!("unexpected stop by {}", limit),
+      }
+    }
+  }
+
+  pub
+  fn move_joystick(&mut self, joystick: Joystick) {
+    self.program_instance.push_input(joystick.value());
+  }
+
+  /// Move the paddle towards the ball.
+  pub
+  fn auto_joystick(&self) -> Joystick {
+    match (self.screen.find(Tile::Paddle), self.screen.find(Tile::Ball)) {
+      (Some((paddle, _)), Some((ball, _))) if ball < paddle => Joystick::Left,
+      (Some((paddle, _)), Some((ball, _))) if ball > paddle => Joystick::Right,
+      _ => Joystick::Neutral,
+    }
+  }
+
+  /// Play until the game is over with the auto-play policy, calling
+  /// `on_frame` whenever the game waits for the joystick and at the end,
+  /// and return the final score.
+  pub
+  fn auto_play<F: FnMut(&Screen)>(&mut self, mut on_frame: F) -> InstructionType {
+    loop {
+      let status = self.run();
+      on_frame(&self.screen);
+      match status {
+        ArcadeStatus::WaitingForJoystick => self.move_joystick(self.auto_joystick()),
+        ArcadeStatus::GameOver => return self.screen.score,
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod arcade_tests {
+  use super::*;
+
+  #[test]
+  fn draws_tiles_and_score() {
+    let mut screen = Screen::default();
+    for (x, y, tile) in &[(0, 0, 1), (1, 0, 1), (2, 0, 1), (1, 1, 2), (0, 2, 3), (2, 2, 4), (-1, 0, 12)] {
+      screen.draw(*x, *y, *tile);
+    }
+    assert_eq!(screen.count(Tile::Wall), 3);
+    assert_eq!(screen.find(Tile::Ball), Some((2, 2)));
+    assert_eq!(screen.score, 12);
+    assert_eq!(screen.render(), "###\n =\n- o\nscore: 12\n");
+  }
+
+  #[test]
+  fn auto_play_follows_the_ball() {
+    // draws a paddle left of the ball, then scores the joystick position
+    let mut program = Program::from_string("104,1,104,0,104,3,104,3,104,0,104,4,3,100,104,-1,104,0,4,100,99");
+    program.instructions.resize(101, 0);
+    let mut arcade = Arcade::new(program);
+    let mut frames = Vec::new();
+    assert_eq!(arcade.auto_play(|screen| frames.push(screen.render())), 1);
+    assert_eq!(frames, vec![" - o\nscore: 0\n", " - o\nscore: 1\n"]);
+  }
+}
@@ -0,0 +1,160 @@
+use super::instruction_type::InstructionType;
+use super::limits::Limit;
+use super::program::Interrupt;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::Snapshot;
+
+/// What a program printed, split into text and the values outside of ASCII.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct AsciiOutput {
+  pub text: String,
+  /// values outside of 0 to 127, which are usually a final result
+  pub values: Vec<InstructionType>,
+}
+
+impl AsciiOutput {
+  pub
+  fn push(&mut self, value: InstructionType) {
+    if (0..=127).contains(&value) {
+      self.text.push(value as u8 as char);
+    } else {
+      self.values.push(value);
+    }
+  }
+}
+
+impl Extend<InstructionType> for AsciiOutput {
+  fn extend<T: IntoIterator<Item = InstructionType>>(&mut self, values: T) {
+    for value in values {
+      self.push(value);
+    }
+  }
+}
+
+/// Why an ASCII program stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum AsciiStatus {
+  NeedInput,
+  Halted,
+  /// stopped by one of the limits of the program instance
+  Stopped(Limit),
+}
+
+/// The codes for a line of text, ending in a newline.
+pub
+fn encode_line(line: &str) -> Vec<InstructionType> {
+  let line = line.trim_end_matches(['\n', '\r']);
+  line.chars().chain(Some('\n')).map(|x| x as InstructionType).collect()
+}
+
+/// A program which talks in ASCII codes, given lines of text as input.
+#[derive(Debug)]
+pub
+struct AsciiMachine<'a> {
+  program_instance: ProgramInstance<'a>,
+  halted: bool,
+}
+
+impl<'a> AsciiMachine<'a> {
+  pub
+  fn new(program: Program) -> AsciiMachine<'a> {
+    AsciiMachine::from_instance(ProgramInstance::new(program))
+  }
+
+  pub
+  fn from_instance(program_instance: ProgramInstance<'a>) -> AsciiMachine<'a> {
+    AsciiMachine { program_instance, halted: false }
+  }
+
+  pub
+  fn program_instance(&mut self) -> &mut ProgramInstance<'a> {
+    &mut self.program_instance
+  }
+
+  pub
+  fn is_halted(&self) -> bool {
+    self.halted
+  }
+
+  pub
+  fn send_line(&mut self, line: &str) {
+    self.send_values(&encode_line(line));
+  }
+
+  pub
+  fn send_values(&mut self, values: &[InstructionType]) {
+    for value in values {
+      self.program_instance.push_input(*value);
+    }
+  }
+
+  pub
+  fn snapshot(&self) -> Snapshot {
+    self.program_instance.snapshot()
+  }
+
+  /// Go back to a snapshot, which may be from before the program halted.
+  pub
+  fn restore(&mut self, snapshot: &Snapshot) {
+    self.program_instance.restore(snapshot);
+    self.halted = false;
+  }
+
+  /// Run until the program needs input which has not been sent, or halts,
+  /// and return what it printed meanwhile.
+  pub
+  fn run(&mut self) -> (AsciiStatus, AsciiOutput) {
+    let mut output = AsciiOutput::default();
+    if self.halted {
+      return (AsciiStatus::Halted, output)
+    }
+    loop {
+      match self.program_instance.resume() {
+        Interrupt::Output(value) => output.push(value),
+        Interrupt::NeedInput => return (AsciiStatus::NeedInput, output),
+        Interrupt::Halted => {
+          self.halted = true;
+          return (AsciiStatus::Halted, output)
+        },
+        Interrupt::Stopped(limit) => return (AsciiStatus::Stopped(limit), output),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod ascii_tests {
+  use super::*;
+
+  // echoes a line without its newline, then prints 1000
+  const ECHO: &str = "3,17,1008,17,10,18,1005,18,14,4,17,1105,1,0,104,1000,99,0,0";
+
+  #[test]
+  fn encodes_lines_with_newline() {
+    assert_eq!(encode_line("NOT A J"), vec![78, 79, 84, 32, 65, 32, 74, 10]);
+    assert_eq!(encode_line("WALK\r\n"), vec![87, 65, 76, 75, 10]);
+  }
+
+  #[test]
+  fn separates_text_from_other_values() {
+    let mut output = AsciiOutput::default();
+    output.extend(vec![72, 105, 10, 128, -1, 33]);
+    assert_eq!(output.text, "Hi\n!");
+    assert_eq!(output.values, vec![128, -1]);
+  }
+
+  #[test]
+  fn waits_for_lines() {
+    let mut machine = AsciiMachine::new(Program::from_string(ECHO));
+    assert_eq!(machine.run(), (AsciiStatus::NeedInput, AsciiOutput::default()));
+    machine.send_line("hello");
+    let (status, output) = machine.run();
+    assert_eq!(status, AsciiStatus::Halted);
+    assert_eq!(output.text, "hello");
+    assert_eq!(output.values, vec![1000]);
+    assert!(machine.is_halted());
+  }
+}
@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::panic;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::ExecutionLimits;
+use intcode_machine::Program;
+use intcode_machine::ProgramInstance;
+use intcode_machine::StepError;
+use intcode_machine::TextTracer;
+use intcode_machine::panic_message;
+
+const USAGE: &str = "\
+usage: intcode PROGRAM [options]
+
+Run the program, printing each output on its own line. Exits with 0 if it
+halts, or 1 if it faults, needs more inputs than given, or hits the step
+limit.
+
+options:
+  --input N           give the program N as input, may be repeated
+  --inputs N,N,...    give the program these inputs
+  --input-file FILE   give the program the inputs in FILE, separated by
+                      commas or whitespace
+  --stdin             read inputs from stdin, a line each, when the program
+                      needs them, instead of the inputs given
+  --set ADDR=VALUE    set memory at ADDR to VALUE before running, may be
+                      repeated, e.g. --set 1=12 --set 2=2
+  --max-steps N       stop after N steps
+  --trace             write each step of the run to stderr
+  --dump              print the final memory, comma separated
+  --read ADDR         print the final value at ADDR, may be repeated, e.g.
+                      --read 0";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn parse_values(text: &str, source: &str) -> Vec<i32> {
+  text.split(|x: char| x == ',' || x.is_whitespace())
+    .filter(|x| !x.is_empty())
+    .map(|x| x.parse::<i32>().unwrap_or_else(|_| fail(&format!("invalid value '{}' in {}", x, source))))
+    .collect()
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut program_path = None;
+  let mut inputs = Vec::new();
+  let mut stdin = false;
+  let mut patches = Vec::new();
+  let mut max_steps = None;
+  let mut trace = false;
+  let mut dump = false;
+  let mut reads = Vec::new();
+  let mut i = 0;
+  while i < args.len() {
+    let value = || args.get(i + 1).cloned().unwrap_or_else(|| fail(&format!("missing value for '{}'", args[i])));
+    let number = |name: &str| value().parse::<usize>().unwrap_or_else(|_| fail(&format!("invalid value for '{}'", name)));
+    match args[i].as_str() {
+      "--input" => inputs.extend(parse_values(&value(), "'--input'")),
+      "--inputs" => inputs.extend(parse_values(&value(), "'--inputs'")),
+      "--input-file" => {
+        let path = value();
+        let text = fs::read_to_string(&path).unwrap_or_else(|error| fail(&format!("failed to read '{}': {}", path, error)));
+        inputs.extend(parse_values(&text, &format!("'{}'", path)));
+      },
+      "--set" => {
+        let patch = value();
+        let parsed = patch.split_once('=').and_then(|(address, value)| {
+          Some((address.trim().parse::<usize>().ok()?, value.trim().parse::<i32>().ok()?))
+        });
+        patches.push(parsed.unwrap_or_else(|| fail(&format!("expected ADDR=VALUE for '--set', got '{}'", patch))));
+      },
+      "--max-steps" => max_steps = Some(number("--max-steps")),
+      "--read" => reads.push(number("--read")),
+      "--stdin" => {
+        stdin = true;
+        i += 1;
+        continue;
+      },
+      "--trace" => {
+        trace = true;
+        i += 1;
+        continue;
+      },
+      "--dump" => {
+        dump = true;
+        i += 1;
+        continue;
+      },
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if program_path.is_none() && !x.starts_with("--") => {
+        program_path = Some(x.to_string());
+        i += 1;
+        continue;
+      },
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+    i += 2;
+  }
+  if stdin && !inputs.is_empty() {
+    fail("'--stdin' can't be used with inputs given as options");
+  }
+  let program_path = program_path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let text = fs::read_to_string(&program_path)
+    .unwrap_or_else(|error| fail(&format!("failed to read '{}': {}", program_path, error)));
+  let mut program = Program { instructions: parse_values(&text, &format!("'{}'", program_path)) };
+  for (address, value) in &patches {
+    if *address >= program.instructions.len() {
+      fail(&format!("address '{}' for '--set' is outside the program of length '{}'", address, program.instructions.len()));
+    }
+    program.instructions[*address] = *value;
+  }
+  let mut program_instance = ProgramInstance::from_ref(&mut program);
+  {
+    let mut context = program_instance.context.borrow_mut();
+    // with '--stdin' the inputs are read below, as the program needs them
+    *context.inputs.borrow_mut() = Some(VecDeque::from(inputs));
+    if trace {
+      *context.tracer.borrow_mut() = Some(Box::new(TextTracer { writer: io::stderr() }));
+    }
+    context.limits = ExecutionLimits { max_steps, ..Default::default() };
+  }
+  // faults are reported below rather than by the panic hook
+  panic::set_hook(Box::new(|_| {}));
+  let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+    loop {
+      match program_instance.step() {
+        Ok((next_counter, output)) => {
+          if let Some(output) = output {
+            println!("{}", output);
+          }
+          program_instance.context.borrow().counter.set(next_counter);
+        },
+        Err(StepError::EndOfProgram) => return Ok(()),
+        Err(StepError::NeedInput) if stdin => {
+          let mut line = String::new();
+          if io::stdin().lock().read_line(&mut line).map_err(|x| x.to_string())? == 0 {
+            return Err("stdin ended before the program's next input".to_string())
+          }
+          let input = line.trim().parse::<i32>().map_err(|_| format!("invalid input '{}' from stdin", line.trim()))?;
+          program_instance.push_input(input);
+        },
+        Err(StepError::NeedInput) => return Err("the program needs more inputs than given".to_string()),
+        Err(StepError::Error(message)) => return Err(message.to_string()),
+        Err(StepError::LimitExceeded(limit)) => return Err(format!("stopped by {}", limit)),
+      }
+    }
+  }));
+  let counter = program_instance.context.borrow().counter.get();
+  drop(program_instance);
+  let fault = match result {
+    Ok(Ok(())) => None,
+    Ok(Err(message)) => Some(message),
+    Err(payload) => Some(panic_message(&*payload)),
+  };
+  if dump {
+    println!("{}", program);
+  }
+  for address in reads {
+    match program.instructions.get(address) {
+      Some(value) => println!("{}: {}", address, value),
+      None => eprintln!("address '{}' is outside the memory of length '{}'", address, program.instructions.len()),
+    }
+  }
+  if let Some(message) = fault {
+    eprintln!("fault at '{}': {}", counter, message);
+    process::exit(1);
+  }
+}
@@ -0,0 +1,151 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::IntcodeGame;
+use intcode_machine::Program;
+use intcode_machine::Reply;
+use intcode_machine::Snapshot;
+use intcode_machine::TextGame;
+use intcode_machine::explore;
+use intcode_machine::parse_rooms;
+use intcode_machine::solve;
+
+const USAGE: &str = "\
+usage: intcode_adventure PROGRAM [options]
+
+Play the text adventure in PROGRAM interactively. Besides the commands of the
+game, these lines are handled here:
+
+  save FILE   write the state of the game and the last room to FILE
+  load FILE   go back to the state and room in FILE
+  explore     visit every room and try every item, then come back
+  solve       explore, collect the safe items and find the ones which get
+              past the checkpoint
+
+options:
+  --steps N   steps allowed per command before the game counts as hung
+              (default: 1000000)
+  --solve     solve from the start without asking, exit 1 if it fails";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+// a save is the snapshot followed by a 'room' line and the last room text
+fn write_save(snapshot: &Snapshot, room_text: &str) -> String {
+  format!("{}room\n{}", snapshot, room_text)
+}
+
+fn parse_save(text: &str) -> Result<(Snapshot, String), String> {
+  let (snapshot, room_text) = text.split_once("\nroom\n")
+    .ok_or_else(|| "expected a 'room' line in the save".to_string())?;
+  Ok((Snapshot::from_string(snapshot)?, room_text.to_string()))
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut path = None;
+  let mut max_steps = 1_000_000;
+  let mut solve_now = false;
+  let mut index = 0;
+  while index < args.len() {
+    match args[index].as_str() {
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      "--steps" => {
+        index += 1;
+        max_steps = args.get(index)
+          .and_then(|x| x.parse::<usize>().ok())
+          .unwrap_or_else(|| fail("expected a number after '--steps'"));
+      },
+      "--solve" => solve_now = true,
+      arg if arg.starts_with('-') => fail(&format!("unknown option '{}'", arg)),
+      arg if path.is_none() => path = Some(arg.to_string()),
+      arg => fail(&format!("unexpected argument '{}'", arg)),
+    }
+    index += 1;
+  }
+  let path = path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let mut game = IntcodeGame::new(Program::from_file(&path), max_steps);
+  let reply = game.start();
+  print!("{}", reply.text());
+  // explore and solve start from the last room described
+  let mut room_text = reply.text().to_string();
+  if solve_now {
+    let exploration = explore(&mut game, &room_text);
+    match solve(&mut game, &room_text, &exploration) {
+      Ok(solution) => {
+        println!("items: {}", solution.items.join(", "));
+        print!("{}", solution.text);
+      },
+      Err(error) => {
+        eprintln!("{}", error);
+        process::exit(1);
+      },
+    }
+    return
+  }
+  let stdin = io::stdin();
+  for line in stdin.lock().lines() {
+    let line = line.unwrap();
+    let command = line.trim();
+    let reply = if let Some(file) = command.strip_prefix("save ") {
+      match fs::write(file.trim(), write_save(&game.save(), &room_text)) {
+        Ok(()) => println!("saved to '{}'", file.trim()),
+        Err(error) => eprintln!("failed to write '{}': {}", file.trim(), error),
+      }
+      continue
+    } else if let Some(file) = command.strip_prefix("load ") {
+      let save = fs::read_to_string(file.trim())
+        .map_err(|error| error.to_string())
+        .and_then(|x| parse_save(&x));
+      match save {
+        Ok((snapshot, text)) => {
+          game.load(&snapshot);
+          room_text = text;
+          println!("loaded '{}'", file.trim());
+        },
+        Err(error) => eprintln!("failed to load '{}': {}", file.trim(), error),
+      }
+      continue
+    } else if command == "explore" {
+      print!("{}", explore(&mut game, &room_text));
+      continue
+    } else if command == "solve" {
+      // a failed solve leaves the game as it was
+      let before = game.save();
+      let exploration = explore(&mut game, &room_text);
+      match solve(&mut game, &room_text, &exploration) {
+        Ok(solution) => {
+          println!("items: {}", solution.items.join(", "));
+          Reply::Over(solution.text)
+        },
+        Err(error) => {
+          game.load(&before);
+          eprintln!("{}", error);
+          continue
+        },
+      }
+    } else {
+      game.send(command)
+    };
+    print!("{}", reply.text());
+    io::stdout().flush().unwrap();
+    if !parse_rooms(reply.text()).is_empty() {
+      room_text = reply.text().to_string();
+    }
+    match reply {
+      Reply::Waiting(_) => {},
+      Reply::Over(_) => return,
+      Reply::Hung(_) => eprintln!("the game did not reply within {} steps, load a save to go on", max_steps),
+    }
+  }
+}
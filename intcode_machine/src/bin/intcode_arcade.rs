@@ -0,0 +1,85 @@
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Arcade;
+use intcode_machine::ArcadeStatus;
+use intcode_machine::Joystick;
+use intcode_machine::Program;
+
+const USAGE: &str = "\
+usage: intcode_arcade PROGRAM [options]
+
+Run the program as an arcade game, drawing its screen in the terminal. Each
+line typed moves the joystick: 'a' for left, 'd' for right, and anything else
+leaves it in the middle.
+
+options:
+  --free-play         insert two quarters, so the game can be played
+  --auto              play by following the ball with the paddle
+  --headless          play automatically, printing each frame as plain text
+                      followed by a blank line, and then the final score";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut program_path = None;
+  let mut free_play = false;
+  let mut auto = false;
+  let mut headless = false;
+  for arg in &args {
+    match arg.as_str() {
+      "--free-play" => free_play = true,
+      "--auto" => auto = true,
+      "--headless" => headless = true,
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if program_path.is_none() && !x.starts_with("--") => program_path = Some(x.to_string()),
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+  }
+  let program_path = program_path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let mut arcade = Arcade::new(Program::from_file(&program_path));
+  if free_play {
+    arcade.insert_quarters(2);
+  }
+  if headless {
+    let score = arcade.auto_play(|screen| println!("{}", screen.render()));
+    println!("final score: {}", score);
+    return
+  }
+  let stdin = io::stdin();
+  let mut lines = stdin.lock().lines();
+  loop {
+    let status = arcade.run();
+    // clear the terminal before each frame
+    print!("\x1b[2J\x1b[H{}", arcade.screen.render());
+    io::stdout().flush().unwrap();
+    if status == ArcadeStatus::GameOver {
+      break;
+    }
+    let joystick = if auto {
+      arcade.auto_joystick()
+    } else {
+      match lines.next() {
+        Some(line) => match line.unwrap().trim() {
+          "a" => Joystick::Left,
+          "d" => Joystick::Right,
+          _ => Joystick::Neutral,
+        },
+        None => break,
+      }
+    };
+    arcade.move_joystick(joystick);
+  }
+  println!("game over, score: {}", arcade.screen.score);
+}
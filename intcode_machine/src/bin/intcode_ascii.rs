@@ -0,0 +1,49 @@
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::AsciiMachine;
+use intcode_machine::AsciiStatus;
+use intcode_machine::Program;
+
+const USAGE: &str = "\
+usage: intcode_ascii PROGRAM
+
+Run the PROGRAM interactively as an ASCII program: its outputs are printed as
+text, and each line typed is sent to it as codes ending in a newline. Outputs
+outside of ASCII are printed on their own lines, marked as results.";
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  if args.len() != 1 || args[0].starts_with('-') {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  }
+  let mut machine = AsciiMachine::new(Program::from_file(&args[0]));
+  let stdin = io::stdin();
+  let mut lines = stdin.lock().lines();
+  loop {
+    let (status, output) = machine.run();
+    print!("{}", output.text);
+    if !output.values.is_empty() && !output.text.is_empty() && !output.text.ends_with('\n') {
+      println!();
+    }
+    for value in output.values {
+      println!("[non-ASCII result: {}]", value);
+    }
+    io::stdout().flush().unwrap();
+    if status == AsciiStatus::Halted {
+      break;
+    }
+    match lines.next() {
+      Some(line) => machine.send_line(&line.unwrap()),
+      None => {
+        eprintln!("input ended while the program was waiting for a line");
+        process::exit(1);
+      },
+    }
+  }
+}
@@ -0,0 +1,103 @@
+use std::env;
+use std::panic;
+use std::path::Path;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Fuzzer;
+use intcode_machine::Program;
+
+const USAGE: &str = "\
+usage: intcode_fuzz PROGRAM [options]
+
+Run the program with mutated input sequences, keeping those which reach new
+addresses or branch directions, and report the faults found, each with
+minimized inputs which reproduce it.
+
+options:
+  --corpus DIR        load the corpus from DIR first, and save the corpus and
+                      fault reproducers (in DIR/faults) there at the end
+  --iterations N      number of mutated inputs to run (default: 100000)
+  --seed N            seed for the mutations (default: 1)
+  --max-steps N       stop each run after this many steps (default: 100000)
+  --max-inputs N      longest input sequence to try (default: 16)";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut program_path = None;
+  let mut corpus = None;
+  let mut iterations = 100_000;
+  let mut seed = 1;
+  let mut max_steps = None;
+  let mut max_inputs = None;
+  let mut i = 0;
+  while i < args.len() {
+    let value = || args.get(i + 1).cloned().unwrap_or_else(|| fail(&format!("missing value for '{}'", args[i])));
+    let number = |name: &str| value().parse::<u64>().unwrap_or_else(|_| fail(&format!("invalid value for '{}'", name)));
+    match args[i].as_str() {
+      "--corpus" => corpus = Some(value()),
+      "--iterations" => iterations = number("--iterations"),
+      "--seed" => seed = number("--seed"),
+      "--max-steps" => max_steps = Some(number("--max-steps") as usize),
+      "--max-inputs" => max_inputs = Some(number("--max-inputs") as usize),
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if program_path.is_none() && !x.starts_with("--") => {
+        program_path = Some(x.to_string());
+        i += 1;
+        continue;
+      },
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+    i += 2;
+  }
+  let program_path = program_path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let mut fuzzer = Fuzzer::new(Program::from_file(&program_path), seed);
+  if let Some(max_steps) = max_steps {
+    fuzzer.max_steps = max_steps;
+  }
+  if let Some(max_inputs) = max_inputs {
+    fuzzer.max_inputs = max_inputs;
+  }
+  // faults are expected, and reported below rather than by the panic hook
+  panic::set_hook(Box::new(|_| {}));
+  if let Some(corpus) = &corpus {
+    if Path::new(corpus).is_dir() {
+      match fuzzer.load_corpus(Path::new(corpus)) {
+        Ok(loaded) => println!("loaded {} inputs from '{}'", loaded, corpus),
+        Err(error) => fail(&format!("failed to load corpus '{}': {}", corpus, error)),
+      }
+    }
+  }
+  fuzzer.run(&[]);
+  let mut faults = fuzzer.faults.len();
+  for _ in 0..iterations {
+    fuzzer.fuzz_one();
+    for fault in &fuzzer.faults[faults..] {
+      println!("fault: {}", fault);
+    }
+    faults = fuzzer.faults.len();
+  }
+  println!(
+    "{} features covered by {} corpus inputs, {} faults",
+    fuzzer.features.len(),
+    fuzzer.corpus.len(),
+    fuzzer.faults.len());
+  if let Some(corpus) = &corpus {
+    if let Err(error) = fuzzer.save(Path::new(corpus)) {
+      eprintln!("failed to save corpus '{}': {}", corpus, error);
+      process::exit(2);
+    }
+  }
+  if !fuzzer.faults.is_empty() {
+    process::exit(1);
+  }
+}
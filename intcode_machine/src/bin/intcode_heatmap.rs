@@ -0,0 +1,85 @@
+use std::env;
+use std::fs;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::AccessCounts;
+use intcode_machine::HeatmapTracer;
+use intcode_machine::Program;
+use intcode_machine::run_intcode_program_with_tracer;
+
+const USAGE: &str = "\
+usage: intcode_heatmap PROGRAM IMAGE [options]
+
+Run the program and write a plain PPM IMAGE with a pixel for each address of
+memory: red for writes, green for reads and blue for executes, brighter for
+more frequent accesses.
+
+options:
+  --input N         give the program N as input, may be repeated
+  --width N         pixels per row (default: 32)
+  --frames STEPS    also write an image of the accesses in each STEPS steps,
+                    named after IMAGE with the frame number, e.g. for
+                    heatmap.ppm: heatmap-0000.ppm, heatmap-0001.ppm, ...
+                    on the scale of the busiest frame";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn write(path: &str, content: &str) {
+  if let Err(error) = fs::write(path, content) {
+    eprintln!("failed to write '{}': {}", path, error);
+    process::exit(2);
+  }
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut paths = Vec::new();
+  let mut inputs = Vec::new();
+  let mut width = 32;
+  let mut steps_per_frame = None;
+  let mut i = 0;
+  while i < args.len() {
+    let value = || args.get(i + 1).cloned().unwrap_or_else(|| fail(&format!("missing value for '{}'", args[i])));
+    let number = |name: &str| value().parse::<usize>().unwrap_or_else(|_| fail(&format!("invalid value for '{}'", name)));
+    match args[i].as_str() {
+      "--input" => inputs.push(value().parse::<i32>().unwrap_or_else(|_| fail("invalid value for '--input'"))),
+      "--width" => width = number("--width"),
+      "--frames" => steps_per_frame = Some(number("--frames")),
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if paths.len() < 2 && !x.starts_with("--") => {
+        paths.push(x.to_string());
+        i += 1;
+        continue;
+      },
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+    i += 2;
+  }
+  if paths.len() != 2 {
+    fail("missing PROGRAM or IMAGE");
+  }
+  let mut program = Program::from_file(&paths[0]);
+  let tracer = HeatmapTracer::new(steps_per_frame.unwrap_or(usize::MAX));
+  let outputs = run_intcode_program_with_tracer(&mut program, &Some(inputs), Box::new(tracer.clone()));
+  println!("outputs: {:?}", outputs);
+  let total = tracer.total();
+  let addresses = program.instructions.len().max(total.counts.len());
+  write(&paths[1], &total.render_ppm(width, addresses, total.peak()));
+  if steps_per_frame.is_some() {
+    let frames = tracer.frames();
+    let peak = frames.iter().fold(AccessCounts::default(), |peak, x| peak.max(x.peak()));
+    let stem = paths[1].strip_suffix(".ppm").unwrap_or(&paths[1]);
+    for (index, frame) in frames.iter().enumerate() {
+      write(&format!("{}-{:04}.ppm", stem, index), &frame.render_ppm(width, addresses, peak));
+    }
+    println!("wrote {} frames", frames.len());
+  }
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::fs;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Hull;
+use intcode_machine::HullRobot;
+use intcode_machine::Program;
+use intcode_machine::ProgramInstance;
+
+const USAGE: &str = "\
+usage: intcode_hull PROGRAM [options]
+
+Run the program as the brain of a hull-painting robot, then print how many
+panels it painted at least once, and the white panels as text.
+
+options:
+  --start-white       start the robot on a white panel
+  --pbm OUTPUT        write the white panels to OUTPUT as a PBM image instead";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut program_path = None;
+  let mut start_white = false;
+  let mut pbm = None;
+  let mut i = 0;
+  while i < args.len() {
+    match args[i].as_str() {
+      "--start-white" => start_white = true,
+      "--pbm" => {
+        pbm = Some(args.get(i + 1).cloned().unwrap_or_else(|| fail("missing value for '--pbm'")));
+        i += 1;
+      },
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if program_path.is_none() && !x.starts_with("--") => program_path = Some(x.to_string()),
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+    i += 1;
+  }
+  let program_path = program_path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let mut hull = Hull::default();
+  if start_white {
+    hull.white.insert((0, 0));
+  }
+  let mut robot = HullRobot::new(hull);
+  robot.run(&mut ProgramInstance::new(Program::from_file(&program_path)));
+  println!("painted {} panels", robot.hull.painted.len());
+  match pbm {
+    Some(output) => {
+      if let Err(error) = fs::write(&output, robot.hull.render_pbm()) {
+        eprintln!("failed to write '{}': {}", output, error);
+        process::exit(1);
+      }
+    },
+    None => print!("{}", robot.hull.render_text()),
+  }
+}
@@ -0,0 +1,170 @@
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::panic;
+use std::process;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+extern crate intcode_machine;
+
+use intcode_machine::Monitor;
+use intcode_machine::MonitorStatus;
+use intcode_machine::Program;
+
+const USAGE: &str = "\
+usage: intcode_monitor PROGRAM [options]
+
+Watch the program run, full screen: the instructions and memory around the
+counter, with the latest writes in brackets, the inputs not read yet and the
+outputs. Commands are read a line at a time:
+
+  s [N]          step N instructions (default: 1), also an empty line
+  r              run until paused, or the program needs input or stops
+  p              pause
+  i VALUE...     give the program inputs
+  x              switch memory between decimal and hexadecimal
+  q              quit
+
+options:
+  --size WxH     screen size (default: $COLUMNS x $LINES, or 80x24)
+  --speed N      steps per frame while running (default: 1000)
+  --delay MS     pause between frames while running (default: 50)
+  --headless     read every command from stdin without drawing, running
+                 until the program stops, then print the screen as text";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+// apply a command, returning whether to quit
+fn apply(monitor: &mut Monitor, line: &str) -> Result<bool, String> {
+  let words: Vec<&str> = line.split_whitespace().collect();
+  match words.first().copied() {
+    None | Some("s") => {
+      let count = match words.get(1) {
+        Some(x) => x.parse::<usize>().map_err(|_| format!("invalid step count '{}'", x))?,
+        None => 1,
+      };
+      monitor.pause();
+      for _ in 0..count {
+        if !monitor.step() {
+          break;
+        }
+      }
+    },
+    Some("r") => monitor.status = MonitorStatus::Running,
+    Some("p") => monitor.pause(),
+    Some("i") => {
+      for word in &words[1..] {
+        let value = word.parse::<i32>().map_err(|_| format!("invalid input '{}'", word))?;
+        monitor.push_input(value);
+      }
+    },
+    Some("x") => monitor.hex = !monitor.hex,
+    Some("q") => return Ok(true),
+    Some(x) => return Err(format!("unknown command '{}'", x)),
+  }
+  Ok(false)
+}
+
+fn screen_size() -> (usize, usize) {
+  let get = |name: &str, default: usize| env::var(name).ok().and_then(|x| x.parse::<usize>().ok()).unwrap_or(default);
+  (get("COLUMNS", 80), get("LINES", 24))
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut program_path = None;
+  let (mut width, mut height) = screen_size();
+  let mut speed = 1000;
+  let mut delay = 50;
+  let mut headless = false;
+  let mut i = 0;
+  while i < args.len() {
+    let value = || args.get(i + 1).cloned().unwrap_or_else(|| fail(&format!("missing value for '{}'", args[i])));
+    let number = |name: &str| value().parse::<u64>().unwrap_or_else(|_| fail(&format!("invalid value for '{}'", name)));
+    match args[i].as_str() {
+      "--size" => {
+        let size = value();
+        let parsed = size.split_once('x').and_then(|(w, h)| Some((w.parse::<usize>().ok()?, h.parse::<usize>().ok()?)));
+        (width, height) = parsed.unwrap_or_else(|| fail("invalid value for '--size'"));
+      },
+      "--speed" => speed = number("--speed") as usize,
+      "--delay" => delay = number("--delay"),
+      "--headless" => {
+        headless = true;
+        i += 1;
+        continue;
+      },
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if program_path.is_none() && !x.starts_with("--") => {
+        program_path = Some(x.to_string());
+        i += 1;
+        continue;
+      },
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+    i += 2;
+  }
+  let program_path = program_path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let mut monitor = Monitor::new(Program::from_file(&program_path));
+  // faults are shown in the status rather than by the panic hook
+  panic::set_hook(Box::new(|_| {}));
+  if headless {
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+      match apply(&mut monitor, &line.unwrap()) {
+        Ok(true) => break,
+        Ok(false) => {},
+        Err(message) => eprintln!("{}", message),
+      }
+      while monitor.status == MonitorStatus::Running {
+        monitor.run(speed.max(1));
+      }
+    }
+    print!("{}", monitor.render(width, height));
+    return
+  }
+  let (sender, receiver) = mpsc::channel();
+  thread::spawn(move || {
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+      if sender.send(line.unwrap()).is_err() {
+        break;
+      }
+    }
+  });
+  let mut message = String::new();
+  loop {
+    // the last line is left for the prompt
+    print!("\x1b[H\x1b[2J{}{}\n> ", monitor.render(width, height.saturating_sub(2)), message);
+    io::stdout().flush().unwrap();
+    let line = if monitor.status == MonitorStatus::Running {
+      receiver.try_recv().ok()
+    } else {
+      match receiver.recv() {
+        Ok(line) => Some(line),
+        Err(_) => break,
+      }
+    };
+    if let Some(line) = line {
+      message.clear();
+      match apply(&mut monitor, &line) {
+        Ok(true) => break,
+        Ok(false) => {},
+        Err(error) => message = error,
+      }
+    }
+    if monitor.status == MonitorStatus::Running {
+      monitor.run(speed.max(1));
+      thread::sleep(Duration::from_millis(delay));
+    }
+  }
+  println!();
+}
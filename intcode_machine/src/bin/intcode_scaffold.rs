@@ -0,0 +1,86 @@
+use std::env;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::MAX_FUNCTIONS;
+use intcode_machine::MAX_ROUTINE_LENGTH;
+use intcode_machine::Program;
+use intcode_machine::ScaffoldMap;
+use intcode_machine::camera_view;
+use intcode_machine::compress_path;
+use intcode_machine::join_moves;
+use intcode_machine::run_scaffold;
+
+const USAGE: &str = "\
+usage: intcode_scaffold PROGRAM [options]
+
+Run the camera PROGRAM and print the scaffold it sees, the sum of the
+alignment parameters of its intersections and the path along it. Then
+compress the path into movement routines, send them to the vacuum robot and
+print the dust it collects.
+
+options:
+  --max-length N   the most characters in a routine (default: 20)
+  --video          ask the robot for a video feed and print it";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut path = None;
+  let mut max_length = MAX_ROUTINE_LENGTH;
+  let mut video = false;
+  let mut index = 0;
+  while index < args.len() {
+    match args[index].as_str() {
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      "--max-length" => {
+        index += 1;
+        max_length = args.get(index)
+          .and_then(|x| x.parse::<usize>().ok())
+          .unwrap_or_else(|| fail("expected a number after '--max-length'"));
+      },
+      "--video" => video = true,
+      arg if arg.starts_with('-') => fail(&format!("unknown option '{}'", arg)),
+      arg if path.is_none() => path = Some(arg.to_string()),
+      arg => fail(&format!("unexpected argument '{}'", arg)),
+    }
+    index += 1;
+  }
+  let program = Program::from_file(&path.unwrap_or_else(|| fail("missing PROGRAM")));
+  let view = camera_view(program.clone());
+  let map = ScaffoldMap::parse(&view);
+  print!("{}", view);
+  println!("alignment: {}", map.alignment_sum());
+  let moves = map.trace_path();
+  println!("path: {}", join_moves(&moves));
+  let routines = match compress_path(&moves, MAX_FUNCTIONS, max_length) {
+    Some(routines) => routines,
+    None => {
+      eprintln!("the path does not fit in {} routines of at most {} characters", MAX_FUNCTIONS, max_length);
+      process::exit(1);
+    },
+  };
+  print!("{}", routines);
+  let output = run_scaffold(program, &routines, video);
+  if video {
+    print!("{}", output.text);
+  }
+  match output.values.last() {
+    Some(dust) => println!("dust: {}", dust),
+    None => {
+      if !video {
+        print!("{}", output.text);
+      }
+      eprintln!("the robot reported no dust collected");
+      process::exit(1);
+    },
+  }
+}
@@ -0,0 +1,46 @@
+use std::env;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Program;
+use intcode_machine::Session;
+use intcode_machine::record_intcode_program;
+use intcode_machine::replay_intcode_program;
+
+const USAGE: &str = "\
+usage: intcode_session record PROGRAM SESSION
+       intcode_session replay PROGRAM SESSION
+
+record: run the program interactively, reading inputs from stdin and
+        printing outputs as they happen, and log every input and output to
+        the SESSION file
+replay: run the program with the inputs from the SESSION file and check
+        that it produces the same inputs and outputs";
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  if args.len() != 3 {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  }
+  let mut program = Program::from_file(&args[1]);
+  match args[0].as_str() {
+    "record" => {
+      record_intcode_program(&mut program, &None, &args[2], true);
+    },
+    "replay" => {
+      match replay_intcode_program(&mut program, &Session::from_file(&args[2])) {
+        Ok(outputs) => println!("replay matches session, outputs: {:?}", outputs),
+        Err(mismatch) => {
+          eprintln!("{}", mismatch);
+          process::exit(1);
+        },
+      }
+    },
+    _ => {
+      eprintln!("{}", USAGE);
+      process::exit(2);
+    },
+  }
+}
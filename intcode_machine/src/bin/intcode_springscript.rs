@@ -0,0 +1,70 @@
+use std::env;
+use std::fs;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Program;
+use intcode_machine::SpringMode;
+use intcode_machine::SpringdroidOutcome;
+use intcode_machine::Springscript;
+use intcode_machine::run_springscript;
+use intcode_machine::synthesize_springscript;
+
+const USAGE: &str = "\
+usage: intcode_springscript PROGRAM SCRIPT
+       intcode_springscript PROGRAM --synthesize walk|run [MAX_INSTRUCTIONS]
+
+Check the springscript in the SCRIPT file and send it to the springdroid
+PROGRAM, then print the hull damage reported, or the frames of the droid
+falling. With --synthesize, search for the shortest script of at most
+MAX_INSTRUCTIONS (default: 4) which gets the droid across, learning from
+each fall.";
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  if args.len() < 2 || args[0].starts_with('-') {
+    fail("missing PROGRAM or SCRIPT");
+  }
+  let program = Program::from_file(&args[0]);
+  if args[1] == "--synthesize" {
+    let mode = match args.get(2).map(|x| x.as_str()) {
+      Some("walk") => SpringMode::Walk,
+      Some("run") => SpringMode::Run,
+      _ => fail("expected walk or run after '--synthesize'"),
+    };
+    let max_instructions = match args.get(3) {
+      Some(x) => x.parse::<usize>().unwrap_or_else(|_| fail("invalid value for MAX_INSTRUCTIONS")),
+      None => 4,
+    };
+    let attempt = |script: &Springscript| run_springscript(program.clone(), script).unwrap();
+    match synthesize_springscript(mode, max_instructions, attempt) {
+      Some((script, damage)) => println!("{}hull damage: {}", script, damage),
+      None => {
+        eprintln!("no script of at most {} instructions found", max_instructions);
+        process::exit(1);
+      },
+    }
+    return
+  }
+  if args.len() != 2 {
+    fail("unexpected arguments after SCRIPT");
+  }
+  let source = fs::read_to_string(&args[1]).unwrap_or_else(|error| fail(&format!("failed to read '{}': {}", args[1], error)));
+  let script = Springscript::parse(&source).unwrap_or_else(|error| {
+    eprintln!("{}: {}", args[1], error);
+    process::exit(2);
+  });
+  match run_springscript(program, &script).unwrap() {
+    SpringdroidOutcome::HullDamage(damage) => println!("hull damage: {}", damage),
+    SpringdroidOutcome::Fell(_, text) => {
+      print!("{}", text);
+      process::exit(1);
+    },
+  }
+}
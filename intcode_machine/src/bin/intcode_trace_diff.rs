@@ -0,0 +1,78 @@
+use std::env;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Program;
+use intcode_machine::diff_runs;
+
+const USAGE: &str = "\
+usage: intcode_trace_diff PROGRAM [options]
+
+Run a program twice and report the first step where the two runs differ.
+
+options:
+  --inputs A,B,...        inputs for the first run
+  --other-inputs A,B,...  inputs for the second run (default: same as --inputs)
+  --other-program PATH    program for the second run (default: PROGRAM)
+  --context N             steps to show before and after the divergence (default: 3)
+  --max-steps N           stop each run after this many steps (default: 1000000)";
+
+fn parse_inputs(inputs: &str) -> Vec<i32> {
+  inputs.split(',')
+    .map(|x| x.trim())
+    .filter(|x| !x.is_empty())
+    .map(|x| x.parse::<i32>().unwrap_or_else(|_| fail(&format!("invalid input '{}'", x))))
+    .collect()
+}
+
+fn fail(message: &str) -> ! {
+  eprintln!("{}\n\n{}", message, USAGE);
+  process::exit(2);
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let mut program_path = None;
+  let mut inputs = Vec::new();
+  let mut other_inputs = None;
+  let mut other_program_path = None;
+  let mut context = 3;
+  let mut max_steps = 1_000_000;
+  let mut i = 0;
+  while i < args.len() {
+    let value = || args.get(i + 1).cloned().unwrap_or_else(|| fail(&format!("missing value for '{}'", args[i])));
+    match args[i].as_str() {
+      "--inputs" => inputs = parse_inputs(&value()),
+      "--other-inputs" => other_inputs = Some(parse_inputs(&value())),
+      "--other-program" => other_program_path = Some(value()),
+      "--context" => context = value().parse().unwrap_or_else(|_| fail("invalid value for '--context'")),
+      "--max-steps" => max_steps = value().parse().unwrap_or_else(|_| fail("invalid value for '--max-steps'")),
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return
+      },
+      x if program_path.is_none() && !x.starts_with("--") => {
+        program_path = Some(x.to_string());
+        i += 1;
+        continue;
+      },
+      x => fail(&format!("unexpected argument '{}'", x)),
+    }
+    i += 2;
+  }
+  let program_path = program_path.unwrap_or_else(|| fail("missing PROGRAM"));
+  let program = Program::from_file(&program_path);
+  let other_program = match other_program_path {
+    Some(path) => Program::from_file(&path),
+    None => program.clone(),
+  };
+  let other_inputs = other_inputs.unwrap_or_else(|| inputs.clone());
+  match diff_runs((&program, &inputs), (&other_program, &other_inputs), context, max_steps) {
+    Some(divergence) => {
+      print!("{}", divergence);
+      process::exit(1);
+    },
+    None => println!("no divergence"),
+  }
+}
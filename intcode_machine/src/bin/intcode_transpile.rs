@@ -0,0 +1,34 @@
+use std::env;
+use std::fs;
+use std::process;
+
+extern crate intcode_machine;
+
+use intcode_machine::Program;
+use intcode_machine::transpile;
+
+const USAGE: &str = "\
+usage: intcode_transpile PROGRAM [OUTPUT]
+
+Translate the PROGRAM into a Rust module, written to OUTPUT or stdout, whose
+run(program, inputs) function behaves like run_intcode_program. The module
+depends on the intcode_machine crate, to which it falls back for
+instructions which have been modified.";
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  if args.is_empty() || args.len() > 2 || args[0].starts_with('-') {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  }
+  let source = transpile(&Program::from_file(&args[0]));
+  match args.get(1) {
+    Some(output) => {
+      if let Err(error) = fs::write(output, source) {
+        eprintln!("failed to write '{}': {}", output, error);
+        process::exit(1);
+      }
+    },
+    None => print!("{}", source),
+  }
+}
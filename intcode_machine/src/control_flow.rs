@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use super::disassembler::Instruction;
+use super::disassembler::decode_instruction;
+use super::parameter_mode::ParameterMode;
+use super::program::Program;
+
+/// Where control can go after the last instruction of a basic block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub
+enum Successor {
+  /// the next instruction in memory, when a jump is not taken
+  Next(usize),
+  /// the target of a jump with an immediate destination
+  Jump(usize),
+  /// the target of a jump with a position mode destination
+  Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct BasicBlock {
+  pub start: usize,
+  pub instructions: Vec<Instruction>,
+  pub successors: Vec<Successor>,
+}
+
+impl BasicBlock {
+  pub
+  fn end(&self) -> usize {
+    self.instructions.last().map_or(self.start, |x| x.next_address())
+  }
+}
+
+/// A write whose destination is part of a reachable instruction, i.e.
+/// self-modifying code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub
+struct CodeWrite {
+  pub address: usize,
+  pub destination: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct ControlFlowGraph {
+  pub blocks: BTreeMap<usize, BasicBlock>,
+  /// ranges, start inclusive and end exclusive, not covered by reachable code
+  pub unreachable: Vec<(usize, usize)>,
+  pub code_writes: Vec<CodeWrite>,
+  /// reachable addresses which do not decode, or jump out of the program
+  pub invalid: Vec<usize>,
+}
+
+// where control can go after the instruction, or Err if it jumps out of the
+// program
+fn instruction_successors(instruction: &Instruction, length: usize) -> Result<Vec<Successor>, ()> {
+  if instruction.is_halt() {
+    return Ok(vec![])
+  }
+  if !instruction.is_jump() {
+    return Ok(vec![Successor::Next(instruction.next_address())])
+  }
+  let (test_mode, test) = instruction.parameters[0];
+  let (destination_mode, destination) = instruction.parameters[1];
+  let jump_if_true = instruction.opcode == 5;
+  let mut successors = Vec::new();
+  // an immediate test means the jump is either always or never taken
+  let (can_jump, can_fall_through) = match test_mode {
+    ParameterMode::ImmediateMode => ((test != 0) == jump_if_true, (test != 0) != jump_if_true),
+    ParameterMode::PositionMode => (true, true),
+  };
+  if can_jump {
+    match destination_mode {
+      ParameterMode::ImmediateMode if destination < 0 || destination as usize >= length => return Err(()),
+      ParameterMode::ImmediateMode => successors.push(Successor::Jump(destination as usize)),
+      ParameterMode::PositionMode => successors.push(Successor::Unknown),
+    }
+  }
+  if can_fall_through {
+    successors.push(Successor::Next(instruction.next_address()));
+  }
+  Ok(successors)
+}
+
+impl ControlFlowGraph {
+  /// Build the control flow graph of the code reachable from address 0.
+  pub
+  fn from_program(program: &Program) -> ControlFlowGraph {
+    let memory = &program.instructions;
+    let mut instructions: BTreeMap<usize, Instruction> = BTreeMap::new();
+    let mut successors: BTreeMap<usize, Vec<Successor>> = BTreeMap::new();
+    let mut invalid = BTreeSet::new();
+    let mut leaders = BTreeSet::new();
+    let mut pending = vec![0];
+    if !memory.is_empty() {
+      leaders.insert(0);
+    }
+    while let Some(address) = pending.pop() {
+      if address >= memory.len() || instructions.contains_key(&address) || invalid.contains(&address) {
+        continue;
+      }
+      let instruction = match decode_instruction(memory, address) {
+        Some(instruction) => instruction,
+        None => {
+          invalid.insert(address);
+          continue;
+        },
+      };
+      let next = match instruction_successors(&instruction, memory.len()) {
+        Ok(next) => next,
+        Err(()) => {
+          invalid.insert(address);
+          vec![]
+        },
+      };
+      for successor in &next {
+        match successor {
+          Successor::Next(x) => {
+            if instruction.is_jump() {
+              leaders.insert(*x);
+            }
+            pending.push(*x);
+          },
+          Successor::Jump(x) => {
+            leaders.insert(*x);
+            pending.push(*x);
+          },
+          Successor::Unknown => (),
+        }
+      }
+      successors.insert(address, next);
+      instructions.insert(address, instruction);
+    }
+
+    // split the reachable instructions into blocks at the leaders and after
+    // the instructions which end a block
+    let mut blocks = BTreeMap::new();
+    for start in leaders.iter().filter(|x| instructions.contains_key(x)) {
+      let mut block = BasicBlock { start: *start, instructions: vec![], successors: vec![] };
+      let mut address = *start;
+      loop {
+        let instruction = instructions[&address].clone();
+        let next = successors[&address].clone();
+        let next_address = instruction.next_address();
+        block.instructions.push(instruction);
+        let ends_block = next.len() != 1 || next[0] != Successor::Next(next_address);
+        if ends_block || leaders.contains(&next_address) || !instructions.contains_key(&next_address) {
+          block.successors = next;
+          break;
+        }
+        address = next_address;
+      }
+      blocks.insert(*start, block);
+    }
+
+    let mut is_code = vec![false; memory.len()];
+    for instruction in instructions.values() {
+      for x in &mut is_code[instruction.address..instruction.next_address()] {
+        *x = true;
+      }
+    }
+    for address in &invalid {
+      is_code[*address] = true;
+    }
+    let mut unreachable = Vec::new();
+    let mut address = 0;
+    while address < memory.len() {
+      if is_code[address] {
+        address += 1;
+        continue;
+      }
+      let start = address;
+      while address < memory.len() && !is_code[address] {
+        address += 1;
+      }
+      unreachable.push((start, address));
+    }
+
+    let mut code_writes = Vec::new();
+    for instruction in instructions.values() {
+      if let Some(destination) = instruction.destination() {
+        if destination >= 0 && (destination as usize) < memory.len() && is_code[destination as usize] {
+          code_writes.push(CodeWrite { address: instruction.address, destination: destination as usize });
+        }
+      }
+    }
+
+    ControlFlowGraph { blocks, unreachable, code_writes, invalid: invalid.into_iter().collect() }
+  }
+
+  /// Export the graph in Graphviz DOT format, with writes into code and
+  /// invalid instructions highlighted.
+  pub
+  fn to_dot(&self) -> String {
+    let mut output = String::from("digraph cfg {\n  node [shape=box, fontname=\"monospace\"];\n");
+    let mut has_unknown = false;
+    for block in self.blocks.values() {
+      let mut label = String::new();
+      let mut highlight = false;
+      for instruction in &block.instructions {
+        label += &format!("{}: {}", instruction.address, instruction);
+        if let Some(write) = self.code_writes.iter().find(|x| x.address == instruction.address) {
+          label += &format!("  (writes code at {})", write.destination);
+          highlight = true;
+        }
+        if self.invalid.contains(&instruction.address) {
+          label += "  (invalid jump)";
+          highlight = true;
+        }
+        label += "\\l";
+      }
+      let color = if highlight { ", color=red" } else { "" };
+      output += &format!("  b{} [label=\"{}\"{}];\n", block.start, label, color);
+      for successor in &block.successors {
+        output += &match successor {
+          Successor::Next(x) if self.blocks.contains_key(x) => format!("  b{} -> b{};\n", block.start, x),
+          Successor::Jump(x) if self.blocks.contains_key(x) =>
+            format!("  b{} -> b{} [label=\"jump\"];\n", block.start, x),
+          Successor::Next(x) | Successor::Jump(x) =>
+            format!("  b{} -> invalid{};\n  invalid{} [label=\"invalid at {}\", color=red];\n", block.start, x, x, x),
+          Successor::Unknown => {
+            has_unknown = true;
+            format!("  b{} -> unknown [style=dashed];\n", block.start)
+          },
+        };
+      }
+    }
+    if has_unknown {
+      output += "  unknown [label=\"unknown\", shape=ellipse, style=dashed];\n";
+    }
+    for (start, end) in &self.unreachable {
+      output += &format!("  unreachable{} [label=\"unreachable {}..{}\", style=dotted];\n", start, start, end);
+    }
+    output += "}\n";
+    output
+  }
+}
+
+#[cfg(test)]
+mod control_flow_tests {
+  use super::*;
+
+  #[test]
+  fn blocks_and_edges() {
+    // input, skip the add if the input is 0, print and halt
+    let program = Program::from_string("3,12,1006,12,9,1001,12,1,12,4,12,99,0");
+    let graph = ControlFlowGraph::from_program(&program);
+    assert_eq!(graph.blocks.keys().cloned().collect::<Vec<usize>>(), vec![0, 5, 9]);
+    assert_eq!(graph.blocks[&0].instructions.len(), 2);
+    assert_eq!(graph.blocks[&0].successors, vec![Successor::Jump(9), Successor::Next(5)]);
+    assert_eq!(graph.blocks[&5].successors, vec![Successor::Next(9)]);
+    assert_eq!(graph.blocks[&9].successors, vec![]);
+    assert_eq!(graph.blocks[&9].end(), 12);
+    assert_eq!(graph.unreachable, vec![(12, 13)]);
+    assert!(graph.code_writes.is_empty());
+    assert!(graph.invalid.is_empty());
+  }
+
+  #[test]
+  fn unconditional_and_unknown_jumps() {
+    // jump over data, then jump to the address stored at 10
+    let program = Program::from_string("1105,1,5,7,7,5,9,10,99,0,8");
+    let graph = ControlFlowGraph::from_program(&program);
+    assert_eq!(graph.blocks[&0].successors, vec![Successor::Jump(5)]);
+    assert_eq!(graph.blocks[&5].successors, vec![Successor::Unknown, Successor::Next(8)]);
+    assert_eq!(graph.unreachable, vec![(3, 5), (9, 11)]);
+    let dot = graph.to_dot();
+    assert!(dot.contains("  b0 -> b5 [label=\"jump\"];\n"));
+    assert!(dot.contains("  b5 -> unknown [style=dashed];\n"));
+    assert!(dot.contains("  unreachable3 [label=\"unreachable 3..5\", style=dotted];\n"));
+  }
+
+  #[test]
+  fn writes_into_code_and_invalid_jumps() {
+    // the adds rewrite each other's opcodes, and the jump is out of range
+    let program = Program::from_string("1101,1,1,4,1,0,0,0,1105,1,100");
+    let graph = ControlFlowGraph::from_program(&program);
+    assert_eq!(graph.code_writes, vec![
+      CodeWrite { address: 0, destination: 4 },
+      CodeWrite { address: 4, destination: 0 },
+    ]);
+    assert_eq!(graph.invalid, vec![8]);
+    let dot = graph.to_dot();
+    assert!(dot.contains("0: ADD 1, 1, [4]  (writes code at 4)\\l"));
+    assert!(dot.contains("8: JUMP-IF-TRUE 1, 100  (invalid jump)\\l\", color=red]"));
+  }
+
+  #[test]
+  fn day05_diagnostic_program() {
+    let program = Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day05.csv"));
+    let graph = ControlFlowGraph::from_program(&program);
+    // the instruction at 6 only decodes once the add at 2 has rewritten it
+    assert_eq!(graph.invalid, vec![6]);
+    assert!(graph.code_writes.contains(&CodeWrite { address: 2, destination: 6 }));
+    assert!(graph.to_dot().starts_with("digraph cfg {\n"));
+  }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use super::disassembler::Instruction;
+use super::disassembler::decode_instruction;
+use super::disassembler::is_jump_opcode;
+use super::operation::Operation;
+use super::operation_instance::OperationInstance;
+use super::program::Program;
+use super::program::StepError;
+use super::program::StepResult;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub
+struct BranchCoverage {
+  pub taken: usize,
+  pub not_taken: usize,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub
+struct Coverage {
+  /// number of completed runs that contributed to this coverage
+  pub runs: usize,
+  /// number of times each instruction address was executed
+  pub executed: BTreeMap<usize, usize>,
+  /// taken and not taken counts for each JUMP-IF-TRUE/JUMP-IF-FALSE address
+  pub branches: BTreeMap<usize, BranchCoverage>,
+}
+
+impl Coverage {
+  pub
+  fn new() -> Coverage {
+    Coverage::default()
+  }
+
+  pub
+  fn record(&mut self, address: usize, operation: &Operation, instance: &OperationInstance, result: &StepResult) {
+    let next_counter = match result {
+      Ok((next_counter, _)) => Some(*next_counter),
+      // halt is still an executed instruction
+      Err(StepError::EndOfProgram) => None,
+      // the instruction will be executed again once input is available
+      Err(StepError::NeedInput) => return,
+      Err(StepError::Error(_)) | Err(StepError::LimitExceeded(_)) => return,
+    };
+    *self.executed.entry(address).or_insert(0) += 1;
+    if !is_jump_opcode(instance.opcode) {
+      return
+    }
+    if let Some(next_counter) = next_counter {
+      let branch = self.branches.entry(address).or_default();
+      // a jump to the next instruction is indistinguishable from falling through
+      if next_counter == address + operation.size {
+        branch.not_taken += 1;
+      } else {
+        branch.taken += 1;
+      }
+    }
+  }
+
+  /// Produce an annotated listing of the program, in the style of gcov.
+  ///
+  /// Each line is prefixed with the execution count, '#####' for instructions
+  /// which were never executed, or '-' for values which are not instructions.
+  pub
+  fn report(&self, program: &Program) -> String {
+    let memory = &program.instructions;
+    let mut lines = Vec::new();
+    let mut instructions_total = 0;
+    let mut instructions_covered = 0;
+    let mut branch_directions_total = 0;
+    let mut branch_directions_covered = 0;
+    let mut address = 0;
+    while address < memory.len() {
+      let instruction = match self.instruction_at(memory, address) {
+        Some(instruction) => instruction,
+        None => {
+          lines.push(format!("{:>9}:{:>5}: DATA {}", "-", address, memory[address]));
+          address += 1;
+          continue;
+        },
+      };
+      instructions_total += 1;
+      let mut line = match self.executed.get(&address) {
+        Some(count) => {
+          instructions_covered += 1;
+          format!("{:>9}:{:>5}: {}", count, address, instruction)
+        },
+        None => format!("{:>9}:{:>5}: {}", "#####", address, instruction),
+      };
+      if instruction.is_jump() {
+        branch_directions_total += 2;
+        let branch = self.branches.get(&address).cloned().unwrap_or_default();
+        if branch.taken > 0 {
+          branch_directions_covered += 1;
+        }
+        if branch.not_taken > 0 {
+          branch_directions_covered += 1;
+        }
+        line += &format!("  (taken {}, not taken {})", branch.taken, branch.not_taken);
+      }
+      lines.push(line);
+      address = instruction.next_address();
+    }
+    let mut output = format!(
+      "coverage over {} run(s): {}/{} instructions, {}/{} branch directions\n",
+      self.runs,
+      instructions_covered,
+      instructions_total,
+      branch_directions_covered,
+      branch_directions_total);
+    for line in lines {
+      output += &line;
+      output += "\n";
+    }
+    output
+  }
+
+  fn instruction_at(&self, memory: &[i32], address: usize) -> Option<Instruction> {
+    let instruction = decode_instruction(memory, address)?;
+    // an unexecuted value that overlaps executed code is data, not an instruction
+    if
+      !self.executed.contains_key(&address) &&
+      (address + 1..instruction.next_address()).any(|x| self.executed.contains_key(&x))
+    {
+      return None
+    }
+    Some(instruction)
+  }
+}
+
+#[cfg(test)]
+mod coverage_tests {
+  use super::*;
+  use crate::run_intcode_program::run_intcode_program_with_coverage;
+
+  #[test]
+  fn accumulates_across_runs() {
+    let program = Program::from_string("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+    let mut coverage = Coverage::new();
+    run_intcode_program_with_coverage(&mut program.clone(), &Some(vec![0]), &mut coverage);
+    assert_eq!(coverage.runs, 1);
+    assert_eq!(coverage.branches[&2], BranchCoverage { taken: 1, not_taken: 0 });
+    assert!(!coverage.executed.contains_key(&5));
+    run_intcode_program_with_coverage(&mut program.clone(), &Some(vec![1]), &mut coverage);
+    assert_eq!(coverage.runs, 2);
+    assert_eq!(coverage.branches[&2], BranchCoverage { taken: 1, not_taken: 1 });
+    assert_eq!(coverage.executed[&0], 2);
+    assert_eq!(coverage.executed[&5], 1);
+    assert_eq!(coverage.executed[&11], 2);
+  }
+
+  #[test]
+  fn keeps_coverage_of_faulting_runs() {
+    // prints, then writes out of range
+    let program = Program::from_string("4,0,1101,1,1,100,99");
+    let mut coverage = Coverage::new();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      run_intcode_program_with_coverage(&mut program.clone(), &Some(vec![]), &mut coverage)
+    }));
+    assert!(result.is_err());
+    assert_eq!(coverage.runs, 0);
+    assert_eq!(coverage.executed[&0], 1);
+  }
+
+  #[test]
+  fn report_marks_unexecuted_code() {
+    let program = Program::from_string("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+    let mut coverage = Coverage::new();
+    run_intcode_program_with_coverage(&mut program.clone(), &Some(vec![0]), &mut coverage);
+    let report = coverage.report(&program);
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "coverage over 1 run(s): 4/5 instructions, 1/2 branch directions");
+    assert_eq!(lines[1], "        1:    0: INPUT [12]");
+    assert_eq!(lines[2], "        1:    2: JUMP-IF-FALSE [12], [15]  (taken 1, not taken 0)");
+    assert_eq!(lines[3], "    #####:    5: ADD [13], [14], [13]");
+    assert_eq!(lines[4], "        1:    9: PRINT [13]");
+    assert_eq!(lines[5], "        1:   11: HALT");
+    assert_eq!(lines[6], "        -:   12: DATA -1");
+  }
+}
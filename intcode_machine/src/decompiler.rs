@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use super::control_flow::BasicBlock;
+use super::control_flow::ControlFlowGraph;
+use super::control_flow::Successor;
+use super::disassembler::Instruction;
+use super::instruction_type::InstructionType;
+use super::parameter_mode::ParameterMode;
+use super::program::Program;
+
+// a boolean expression, as tested by a jump or produced by LESS-THAN/EQUALS
+#[derive(Debug, Clone)]
+struct Condition {
+  left: String,
+  operator: &'static str,
+  right: String,
+}
+
+impl Condition {
+  fn negate(&self) -> Condition {
+    let operator = match self.operator {
+      "<" => ">=",
+      ">=" => "<",
+      "==" => "!=",
+      "!=" => "==",
+      x => panic!("unexpected operator '{}'", x),
+    };
+    Condition { left: self.left.clone(), operator, right: self.right.clone() }
+  }
+
+  fn render(&self) -> String {
+    format!("{} {} {}", self.left, self.operator, self.right)
+  }
+}
+
+struct Decompiler<'a> {
+  memory: &'a [InstructionType],
+  blocks: Vec<&'a BasicBlock>,
+  is_code: Vec<bool>,
+  invalid: &'a [usize],
+  // jumps which have been turned into structured control flow
+  consumed: BTreeSet<usize>,
+  // loop headers whose bodies are being emitted
+  active_loops: BTreeSet<usize>,
+  goto_targets: BTreeSet<usize>,
+  labels: BTreeSet<usize>,
+  output: Vec<String>,
+}
+
+impl<'a> Decompiler<'a> {
+  fn operand(&self, parameter: &(ParameterMode, InstructionType)) -> String {
+    match parameter {
+      (ParameterMode::ImmediateMode, value) => value.to_string(),
+      (ParameterMode::PositionMode, address) => self.variable(*address),
+    }
+  }
+
+  // cells in the reachable code are shown as memory, everything else is a variable
+  fn variable(&self, address: InstructionType) -> String {
+    if address >= 0 && (address as usize) < self.is_code.len() && self.is_code[address as usize] {
+      format!("mem[{}]", address)
+    } else {
+      format!("v{}", address)
+    }
+  }
+
+  fn index_of(&self, address: usize) -> Option<usize> {
+    self.blocks.iter().position(|block| block.start == address)
+  }
+
+  fn terminator(&self, index: usize) -> Option<&'a Instruction> {
+    let instruction = self.blocks[index].instructions.last()?;
+    if instruction.is_jump() && !self.consumed.contains(&instruction.address) {
+      Some(instruction)
+    } else {
+      None
+    }
+  }
+
+  fn jump_target(&self, index: usize) -> Option<usize> {
+    self.blocks[index].successors.iter().find_map(|x| match x {
+      Successor::Jump(target) => Some(*target),
+      _ => None,
+    })
+  }
+
+  fn is_unconditional(&self, index: usize) -> bool {
+    !self.blocks[index].successors.iter().any(|x| matches!(x, Successor::Next(_)))
+  }
+
+  // the condition under which the jump ending the block is taken, using the
+  // comparison which produced the tested value when it is in the same block
+  // and its operands still hold the values it compared at the jump
+  fn jump_condition(&self, index: usize) -> Condition {
+    let block = self.blocks[index];
+    let jump = block.instructions.last().unwrap();
+    let test = &jump.parameters[0];
+    let mut condition = Condition { left: self.operand(test), operator: "!=", right: "0".to_string() };
+    if test.0 == ParameterMode::PositionMode {
+      let mut written = vec![test.1];
+      for instruction in block.instructions.iter().rev().skip(1) {
+        if instruction.destination() != Some(test.1) {
+          written.extend(instruction.destination());
+          continue;
+        }
+        let overwritten = instruction.parameters[..2].iter()
+          .any(|(mode, value)| *mode == ParameterMode::PositionMode && written.contains(value));
+        if (instruction.opcode == 7 || instruction.opcode == 8) && !overwritten {
+          condition = Condition {
+            left: self.operand(&instruction.parameters[0]),
+            operator: if instruction.opcode == 7 { "<" } else { "==" },
+            right: self.operand(&instruction.parameters[1]),
+          };
+        }
+        break;
+      }
+    }
+    if jump.opcode == 6 {
+      condition.negate()
+    } else {
+      condition
+    }
+  }
+
+  fn line(&mut self, depth: usize, text: String) {
+    self.output.push(format!("{}{}", "  ".repeat(depth), text));
+  }
+
+  fn statement(&self, instruction: &Instruction) -> String {
+    let p = |index: usize| self.operand(&instruction.parameters[index]);
+    let destination = |index: usize| self.variable(instruction.parameters[index].1);
+    match instruction.opcode {
+      1 => format!("{} = {} + {};", destination(2), p(0), p(1)),
+      2 => format!("{} = {} * {};", destination(2), p(0), p(1)),
+      3 => format!("{} = input();", destination(0)),
+      4 => format!("output({});", p(0)),
+      7 => format!("{} = ({} < {});", destination(2), p(0), p(1)),
+      8 => format!("{} = ({} == {});", destination(2), p(0), p(1)),
+      99 => "halt;".to_string(),
+      _ => format!("// {}", instruction),
+    }
+  }
+
+  fn emit_block(&mut self, index: usize, depth: usize) {
+    let block = self.blocks[index];
+    if self.labels.contains(&block.start) {
+      self.line(depth.saturating_sub(1), format!("L{}:", block.start));
+    }
+    for instruction in &block.instructions {
+      if !instruction.is_jump() {
+        let statement = self.statement(instruction);
+        self.line(depth, statement);
+      }
+    }
+    let next_start = self.blocks.get(index + 1).map(|x| x.start);
+    if let Some(jump) = self.terminator(index) {
+      if self.is_unconditional(index) && self.jump_target(index).is_some() && self.jump_target(index) == next_start {
+        // a jump to the block which is emitted next anyway
+        return
+      }
+      let target = match self.jump_target(index) {
+        Some(target) => {
+          self.goto_targets.insert(target);
+          format!("L{}", target)
+        },
+        None => format!("*{}", self.operand(&jump.parameters[1])),
+      };
+      let text = if self.is_unconditional(index) {
+        format!("goto {};", target)
+      } else {
+        format!("if ({}) goto {};", self.jump_condition(index).render(), target)
+      };
+      self.line(depth, text);
+    }
+    // falling through to code which is not emitted next
+    for successor in &block.successors {
+      if let Successor::Next(next) = successor {
+        if self.invalid.contains(next) {
+          let value = self.memory[*next];
+          self.line(depth, format!("invalid; // {} at {} does not decode", value, next));
+        } else if next_start != Some(*next) {
+          self.goto_targets.insert(*next);
+          self.line(depth, format!("goto L{};", next));
+        }
+      }
+    }
+  }
+
+  // the last block in [index, end) which jumps back to the start of the block at index
+  fn loop_end(&self, index: usize, end: usize) -> Option<usize> {
+    if self.active_loops.contains(&self.blocks[index].start) {
+      return None
+    }
+    (index..end).rev().find(|x| {
+      self.terminator(*x).is_some() && self.jump_target(*x) == Some(self.blocks[index].start)
+    })
+  }
+
+  fn emit_range(&mut self, mut index: usize, end: usize, depth: usize) {
+    while index < end {
+      if let Some(last) = self.loop_end(index, end) {
+        let jump_address = self.blocks[last].instructions.last().unwrap().address;
+        let unconditional = self.is_unconditional(last);
+        let condition = self.jump_condition(last);
+        self.consumed.insert(jump_address);
+        self.active_loops.insert(self.blocks[index].start);
+        self.line(depth, if unconditional { "loop {" } else { "do {" }.to_string());
+        self.emit_range(index, last + 1, depth + 1);
+        if unconditional {
+          self.line(depth, "}".to_string());
+        } else {
+          self.line(depth, format!("}} while ({});", condition.render()));
+        }
+        self.active_loops.remove(&self.blocks[index].start);
+        index = last + 1;
+        continue;
+      }
+      if let Some(next_index) = self.emit_if(index, end, depth) {
+        index = next_index;
+        continue;
+      }
+      self.emit_block(index, depth);
+      index += 1;
+    }
+  }
+
+  // a conditional forward jump over a run of blocks is an if, and if that
+  // run ends with an unconditional forward jump, the blocks it skips are the else
+  fn emit_if(&mut self, index: usize, end: usize, depth: usize) -> Option<usize> {
+    let jump = self.terminator(index)?;
+    if self.is_unconditional(index) {
+      return None
+    }
+    let target = self.jump_target(index)?;
+    let then_start = index + 1;
+    let then_end = self.index_of(target)?;
+    if target <= jump.address || then_end <= then_start || then_end > end {
+      return None
+    }
+    if self.blocks[then_start].start != jump.next_address() {
+      return None
+    }
+    let condition = self.jump_condition(index).negate();
+    self.consumed.insert(jump.address);
+    // the block itself, without its jump
+    self.emit_block(index, depth);
+    let mut else_range = None;
+    if let Some(else_jump) = self.terminator(then_end - 1) {
+      if let Some(else_target) = self.jump_target(then_end - 1) {
+        if let Some(else_end) = self.index_of(else_target) {
+          if self.is_unconditional(then_end - 1) && else_target > else_jump.address && else_end <= end {
+            self.consumed.insert(else_jump.address);
+            else_range = Some((then_end, else_end));
+          }
+        }
+      }
+    }
+    self.line(depth, format!("if ({}) {{", condition.render()));
+    self.emit_range(then_start, then_end, depth + 1);
+    match else_range {
+      Some((else_start, else_end)) => {
+        self.line(depth, "} else {".to_string());
+        self.emit_range(else_start, else_end, depth + 1);
+        self.line(depth, "}".to_string());
+        Some(else_end)
+      },
+      None => {
+        self.line(depth, "}".to_string());
+        Some(then_end)
+      },
+    }
+  }
+}
+
+/// Decompile the code reachable from address 0 into structured pseudo-code.
+///
+/// Forward conditional jumps become if and if/else, backward jumps become
+/// loops, and any other jump is left as a goto. Memory cells outside of the
+/// code are named as variables and declared with their initial values.
+pub
+fn decompile(program: &Program) -> String {
+  let graph = ControlFlowGraph::from_program(program);
+  let memory = &program.instructions;
+  let mut is_code = vec![false; memory.len()];
+  for block in graph.blocks.values() {
+    for x in &mut is_code[block.start..block.end()] {
+      *x = true;
+    }
+  }
+  for address in &graph.invalid {
+    is_code[*address] = true;
+  }
+  let mut decompiler = Decompiler {
+    memory,
+    blocks: graph.blocks.values().collect(),
+    is_code,
+    invalid: &graph.invalid,
+    consumed: BTreeSet::new(),
+    active_loops: BTreeSet::new(),
+    goto_targets: BTreeSet::new(),
+    labels: BTreeSet::new(),
+    output: Vec::new(),
+  };
+  // the first pass finds the targets of the remaining gotos, which need labels
+  decompiler.emit_range(0, decompiler.blocks.len(), 1);
+  decompiler.labels = std::mem::take(&mut decompiler.goto_targets);
+  decompiler.consumed.clear();
+  decompiler.output.clear();
+  decompiler.emit_range(0, decompiler.blocks.len(), 1);
+
+  let mut variables = BTreeMap::new();
+  for block in &decompiler.blocks {
+    for instruction in &block.instructions {
+      for (mode, address) in &instruction.parameters {
+        let name = decompiler.variable(*address);
+        if *mode == ParameterMode::PositionMode && name.starts_with('v') {
+          let initial = decompiler.memory.get(*address as usize).cloned();
+          variables.insert(*address, initial);
+        }
+      }
+    }
+  }
+  let mut output = String::from("program {\n");
+  for (address, initial) in &variables {
+    match initial {
+      Some(value) => output += &format!("  var v{} = {};\n", address, value),
+      None => output += &format!("  var v{}; // outside of memory\n", address),
+    }
+  }
+  if !variables.is_empty() {
+    output += "\n";
+  }
+  for line in &decompiler.output {
+    output += line;
+    output += "\n";
+  }
+  for (start, end) in &graph.unreachable {
+    output += &format!("  // unreachable: {}..{}\n", start, end);
+  }
+  output += "}\n";
+  output
+}
+
+#[cfg(test)]
+mod decompiler_tests {
+  use super::*;
+
+  #[test]
+  fn stores_comparisons() {
+    // output 1 if the input is less than 8, otherwise 0
+    let program = Program::from_string("3,9,7,9,10,9,4,9,99,-1,8");
+    assert_eq!(decompile(&program), "\
+program {
+  var v9 = -1;
+  var v10 = 8;
+
+  v9 = input();
+  v9 = (v9 < v10);
+  output(v9);
+  halt;
+  // unreachable: 9..11
+}
+");
+  }
+
+  #[test]
+  fn if_with_overwritten_comparison() {
+    // the comparison overwrites its operand, so the jump tests the result
+    let program = Program::from_string("1007,9,8,9,1005,9,11,104,1,99,5,104,2,99");
+    assert_eq!(decompile(&program), "\
+program {
+  mem[9] = (mem[9] < 8);
+  if (mem[9] == 0) {
+    output(1);
+    halt;
+  }
+  output(2);
+  halt;
+  // unreachable: 10..11
+}
+");
+  }
+
+  #[test]
+  fn if_else() {
+    let program = Program::from_string("3,20,1008,20,8,21,1005,21,15,104,0,1105,1,17,0,104,1,99,0,0,0,0");
+    assert_eq!(decompile(&program), "\
+program {
+  var v20 = 0;
+  var v21 = 0;
+
+  v20 = input();
+  v21 = (v20 == 8);
+  if (v20 != 8) {
+    output(0);
+  } else {
+    output(1);
+  }
+  halt;
+  // unreachable: 14..15
+  // unreachable: 18..22
+}
+");
+  }
+
+  #[test]
+  fn jumps_which_do_not_nest_are_gotos() {
+    // the day 5 example which compares the input with 8
+    let program = Program::from_string("
+3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,
+1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,
+999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99
+");
+    assert_eq!(decompile(&program), "\
+program {
+  var v20 = 0;
+  var v21 = 0;
+
+  v21 = input();
+  v20 = (v21 == 8);
+  if (v21 != 8) {
+    v20 = (8 < v21);
+    if (8 >= v21) goto L31;
+  } else {
+    v20 = v21 * 125;
+    output(v20);
+    goto L46;
+  L31:
+    output(999);
+    goto L46;
+  }
+  v20 = 1000 + 1;
+  output(v20);
+L46:
+  halt;
+  // unreachable: 19..22
+  // unreachable: 45..46
+}
+");
+  }
+
+  #[test]
+  fn counting_loop() {
+    // output 3, 2 and 1
+    let program = Program::from_string("4,12,1001,12,-1,12,1005,12,0,99,0,0,3");
+    assert_eq!(decompile(&program), "\
+program {
+  var v12 = 3;
+
+  do {
+    output(v12);
+    v12 = v12 + -1;
+  } while (v12 != 0);
+  halt;
+  // unreachable: 10..13
+}
+");
+  }
+
+  #[test]
+  fn unknown_jump_is_a_goto() {
+    let program = Program::from_string("1106,0,5,0,0,105,1,9,99,8");
+    assert_eq!(decompile(&program), "\
+program {
+  var v9 = 8;
+
+  goto *v9;
+  // unreachable: 3..5
+  // unreachable: 8..10
+}
+");
+  }
+
+  #[test]
+  fn day05_diagnostic_program() {
+    let program = Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day05.csv"));
+    let pseudo_code = decompile(&program);
+    assert!(pseudo_code.starts_with("program {\n"));
+    // the first instruction stores the system id
+    assert!(pseudo_code.contains("  v225 = input();\n"));
+    // the instruction at 6 is rewritten before it runs, so it cannot be followed
+    assert!(pseudo_code.contains("  mem[6] = v225 + mem[6];\n  invalid; // 1100 at 6 does not decode\n"));
+  }
+}
@@ -0,0 +1,306 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+use super::instruction_type::InstructionType;
+use super::random::Random;
+
+/// Hardware attached to a range of addresses, which handles the reads and
+/// writes of instruction parameters there instead of memory. Offsets are
+/// from the start of the range, and `steps` is how many instructions the
+/// program has executed.
+pub
+trait Device: fmt::Debug {
+  fn read(&mut self, offset: usize, steps: usize) -> InstructionType;
+
+  fn write(&mut self, offset: usize, value: InstructionType, steps: usize);
+
+  /// The state of the device, kept in snapshots of the program.
+  fn save(&self) -> Vec<InstructionType>;
+
+  /// Go back to a state from `save`.
+  fn load(&mut self, state: &[InstructionType]);
+}
+
+// a 64 bit value as two values, high bits first
+fn split(value: u64) -> [InstructionType; 2] {
+  [(value >> 32) as u32 as InstructionType, value as u32 as InstructionType]
+}
+
+fn join(state: &[InstructionType]) -> u64 {
+  match state {
+    [high, low] => (u64::from(*high as u32) << 32) | u64::from(*low as u32),
+    _ => panic!("expected a device state of 2 values, got {:?}", state),
+  }
+}
+
+type SharedDevice = Rc<RefCell<dyn Device>>;
+
+/// The devices attached to a program, by address range.
+///
+/// The ranges have to be within memory, as the addresses of instructions are
+/// checked against it, so programs using devices are padded to cover them.
+#[derive(Debug, Default)]
+pub
+struct Devices {
+  attached: Vec<(Range<usize>, SharedDevice)>,
+}
+
+impl Devices {
+  /// Attach the device to `size` addresses from `start`. The caller can keep
+  /// a clone of the device to look at it while or after the program runs.
+  pub
+  fn attach<D: Device + 'static>(&mut self, start: usize, size: usize, device: Rc<RefCell<D>>) {
+    let range = start..start + size;
+    if let Some((other, _)) = self.attached.iter().find(|(x, _)| x.start < range.end && range.start < x.end) {
+      panic!("expected device addresses {:?} to be free, they overlap {:?}", range, other);
+    }
+    self.attached.push((range, device));
+  }
+
+  fn find(&self, address: usize) -> Option<(usize, &SharedDevice)> {
+    self.attached.iter()
+      .find(|(range, _)| range.contains(&address))
+      .map(|(range, device)| (address - range.start, device))
+  }
+
+  /// The value read from the device at the address, if there is one.
+  pub
+  fn read(&self, address: usize, steps: usize) -> Option<InstructionType> {
+    let (offset, device) = self.find(address)?;
+    let value = device.borrow_mut().read(offset, steps);
+    Some(value)
+  }
+
+  /// Write to the device at the address, returning false if there is none.
+  pub
+  fn write(&self, address: usize, value: InstructionType, steps: usize) -> bool {
+    match self.find(address) {
+      Some((offset, device)) => {
+        device.borrow_mut().write(offset, value, steps);
+        true
+      },
+      None => false,
+    }
+  }
+
+  /// The state of each device, in the order they were attached.
+  pub
+  fn save(&self) -> Vec<Vec<InstructionType>> {
+    self.attached.iter().map(|(_, device)| device.borrow().save()).collect()
+  }
+
+  /// Go back to the states from `save`, one for each device.
+  pub
+  fn load(&self, states: &[Vec<InstructionType>]) {
+    if states.len() != self.attached.len() {
+      panic!("expected the states of {} devices, got {}", self.attached.len(), states.len());
+    }
+    for ((_, device), state) in self.attached.iter().zip(states) {
+      device.borrow_mut().load(state);
+    }
+  }
+}
+
+/// Counts steps, reading how many have been executed since it was last
+/// written, plus the value written.
+#[derive(Debug, Clone, Default)]
+pub
+struct Timer {
+  start: isize,
+}
+
+impl Device for Timer {
+  fn read(&mut self, _offset: usize, steps: usize) -> InstructionType {
+    (steps as isize - self.start) as InstructionType
+  }
+
+  fn write(&mut self, _offset: usize, value: InstructionType, steps: usize) {
+    self.start = steps as isize - value as isize;
+  }
+
+  fn save(&self) -> Vec<InstructionType> {
+    split(self.start as i64 as u64).to_vec()
+  }
+
+  fn load(&mut self, state: &[InstructionType]) {
+    self.start = join(state) as i64 as isize;
+  }
+}
+
+/// Reads pseudo random values from a fixed seed, below the last positive
+/// value written, or any positive value before that.
+#[derive(Debug, Clone)]
+pub
+struct RandomSource {
+  random: Random,
+  bound: InstructionType,
+}
+
+impl RandomSource {
+  pub
+  fn new(seed: u64) -> RandomSource {
+    RandomSource { random: Random::new(seed), bound: InstructionType::MAX }
+  }
+}
+
+impl Device for RandomSource {
+  fn read(&mut self, _offset: usize, _steps: usize) -> InstructionType {
+    self.random.below(self.bound as usize) as InstructionType
+  }
+
+  fn write(&mut self, _offset: usize, value: InstructionType, _steps: usize) {
+    self.bound = if value > 0 { value } else { InstructionType::MAX };
+  }
+
+  fn save(&self) -> Vec<InstructionType> {
+    let mut state = split(self.random.state()).to_vec();
+    state.push(self.bound);
+    state
+  }
+
+  fn load(&mut self, state: &[InstructionType]) {
+    self.random = Random::new(join(&state[..2]));
+    self.bound = state[2];
+  }
+}
+
+/// Pixels in rows, one address each, which read back what was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct Framebuffer {
+  pub width: usize,
+  pub height: usize,
+  pub pixels: Vec<InstructionType>,
+}
+
+impl Framebuffer {
+  pub
+  fn new(width: usize, height: usize) -> Framebuffer {
+    Framebuffer { width, height, pixels: vec![0; width * height] }
+  }
+
+  /// The number of addresses to attach it to.
+  pub
+  fn size(&self) -> usize {
+    self.pixels.len()
+  }
+
+  /// The pixels as text, with '#' for any value but 0.
+  pub
+  fn render(&self) -> String {
+    let mut output = String::new();
+    for row in self.pixels.chunks(self.width.max(1)) {
+      output.extend(row.iter().map(|x| if *x == 0 { '.' } else { '#' }));
+      output.push('\n');
+    }
+    output
+  }
+}
+
+impl Device for Framebuffer {
+  fn read(&mut self, offset: usize, _steps: usize) -> InstructionType {
+    self.pixels[offset]
+  }
+
+  fn write(&mut self, offset: usize, value: InstructionType, _steps: usize) {
+    self.pixels[offset] = value;
+  }
+
+  fn save(&self) -> Vec<InstructionType> {
+    self.pixels.clone()
+  }
+
+  fn load(&mut self, state: &[InstructionType]) {
+    self.pixels = state.to_vec();
+  }
+}
+
+#[cfg(test)]
+mod devices_tests {
+  use super::*;
+  use crate::program::Interrupt;
+  use crate::program::Program;
+  use crate::program::ProgramInstance;
+  use crate::program::Snapshot;
+
+  fn outputs(program_instance: &mut ProgramInstance) -> Vec<InstructionType> {
+    let mut outputs = Vec::new();
+    while let Interrupt::Output(value) = program_instance.resume() {
+      outputs.push(value);
+    }
+    outputs
+  }
+
+  fn padded(program: &str, size: usize) -> Program {
+    let mut program = Program::from_string(program);
+    program.instructions.resize(size, 0);
+    program
+  }
+
+  #[test]
+  fn draws_to_the_framebuffer() {
+    // draws a diagonal, then prints the first and last pixels
+    let mut program_instance = ProgramInstance::new(padded("1101,1,0,20,1101,2,0,23,4,20,4,22,4,23,99", 24));
+    let framebuffer = Rc::new(RefCell::new(Framebuffer::new(2, 2)));
+    program_instance.context.get_mut().devices.attach(20, 4, framebuffer.clone());
+    assert_eq!(outputs(&mut program_instance), vec![1, 0, 2]);
+    assert_eq!(framebuffer.borrow().render(), "#.\n.#\n");
+    // memory under the device is left alone
+    assert_eq!(program_instance.get_program().instructions[20..], [0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn counts_steps() {
+    // reads the timer, sets it to 10, then reads it twice
+    let mut program_instance = ProgramInstance::new(padded("4,20,1101,10,0,20,4,20,4,20,99", 21));
+    program_instance.context.get_mut().devices.attach(20, 1, Rc::new(RefCell::new(Timer::default())));
+    assert_eq!(outputs(&mut program_instance), vec![0, 11, 12]);
+  }
+
+  #[test]
+  fn repeats_random_values_from_the_seed() {
+    // limits the values below 6, then reads five of them
+    let source = "1101,6,0,20,4,20,4,20,4,20,4,20,4,20,99";
+    let run = |seed| {
+      let mut program_instance = ProgramInstance::new(padded(source, 21));
+      program_instance.context.get_mut().devices.attach(20, 1, Rc::new(RefCell::new(RandomSource::new(seed))));
+      outputs(&mut program_instance)
+    };
+    let values = run(7);
+    assert_eq!(values, run(7));
+    assert_ne!(values, run(8));
+    assert!(values.iter().all(|x| (0..6).contains(x)));
+  }
+
+  #[test]
+  fn snapshots_keep_device_state() {
+    // reads the timer and a random value, and sets a pixel
+    let mut program_instance = ProgramInstance::new(padded("4,20,4,21,1101,1,0,22,99", 24));
+    let framebuffer = Rc::new(RefCell::new(Framebuffer::new(2, 1)));
+    {
+      let devices = &mut program_instance.context.get_mut().devices;
+      devices.attach(20, 1, Rc::new(RefCell::new(Timer::default())));
+      devices.attach(21, 1, Rc::new(RefCell::new(RandomSource::new(3))));
+      devices.attach(22, 2, framebuffer.clone());
+    }
+    assert!(matches!(program_instance.resume(), Interrupt::Output(0)));
+    let snapshot = program_instance.snapshot();
+    let first = outputs(&mut program_instance);
+    assert_eq!(framebuffer.borrow().pixels, vec![1, 0]);
+    let restored = Snapshot::from_string(&snapshot.to_string()).unwrap();
+    assert_eq!(restored, snapshot);
+    program_instance.restore(&restored);
+    assert_eq!(framebuffer.borrow().pixels, vec![0, 0]);
+    assert_eq!(outputs(&mut program_instance), first);
+  }
+
+  #[test]
+  #[should_panic(expected = "expected device addresses 3..5 to be free, they overlap 0..4")]
+  fn rejects_overlapping_devices() {
+    let mut devices = Devices::default();
+    devices.attach(0, 4, Rc::new(RefCell::new(Framebuffer::new(2, 2))));
+    devices.attach(3, 2, Rc::new(RefCell::new(Timer::default())));
+  }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::panic;
+
+use super::fuzzer::panic_message;
+use super::instruction_type::InstructionType;
+use super::limits::ExecutionLimits;
+use super::program::Program;
+use super::program_generator::GeneratedProgram;
+use super::program_generator::generate_program;
+use super::run_intcode_program::run_intcode_program_with_limits;
+
+/// The most steps a generated program may take on either interpreter to be
+/// compared, since programs which rewrite their code may never halt.
+pub
+const MAX_COMPARED_STEPS: usize = 10_000;
+
+/// Another interpreter to compare against, which runs the memory in place
+/// with the inputs for at most the given number of steps and returns the
+/// outputs, or None if it didn't halt within them, panicking on a fault.
+pub
+type OtherInterpreter<'a> = &'a dyn Fn(&mut Vec<InstructionType>, &[InstructionType], usize) -> Option<Vec<InstructionType>>;
+
+/// How a run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+enum Behaviour {
+  Halted { outputs: Vec<InstructionType>, memory: Vec<InstructionType> },
+  /// with the panic message, which is not compared
+  Faulted(String),
+}
+
+impl fmt::Display for Behaviour {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Behaviour::Halted { outputs, memory } => write!(f, "halted with outputs {:?} and memory {:?}", outputs, memory),
+      Behaviour::Faulted(message) => write!(f, "faulted with '{}'", message),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub
+enum Mismatch {
+  Outputs,
+  Memory,
+  /// only one of them faulted
+  Fault,
+}
+
+#[derive(Debug, Clone)]
+pub
+struct Counterexample {
+  pub generated: GeneratedProgram,
+  pub mismatch: Mismatch,
+  /// the behaviour of this crate
+  pub expected: Behaviour,
+  /// the behaviour of the other interpreter
+  pub actual: Behaviour,
+}
+
+impl fmt::Display for Counterexample {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{:?} mismatch for {}", self.mismatch, self.generated)?;
+    writeln!(f, "  intcode_machine {}", self.expected)?;
+    write!(f, "  other {}", self.actual)
+  }
+}
+
+fn run_catching<F: FnOnce(&mut Vec<InstructionType>) -> Vec<InstructionType>>(
+  memory: &mut Vec<InstructionType>,
+  run: F,
+) -> Behaviour
+{
+  match panic::catch_unwind(panic::AssertUnwindSafe(|| run(memory))) {
+    Ok(outputs) => Behaviour::Halted { outputs, memory: memory.clone() },
+    Err(payload) => Behaviour::Faulted(panic_message(&*payload)),
+  }
+}
+
+/// Run the generated program on this crate and on the other interpreter, and
+/// return how they differ, if they do. Programs which take more than
+/// `MAX_COMPARED_STEPS` steps on either interpreter never differ.
+pub
+fn compare(generated: &GeneratedProgram, other: OtherInterpreter) -> Option<Counterexample> {
+  let memory = generated.to_program().instructions;
+  let inputs = Some(generated.inputs.clone());
+  let limits = ExecutionLimits { max_steps: Some(MAX_COMPARED_STEPS), ..Default::default() };
+  let mut stopped = false;
+  let expected = run_catching(&mut memory.clone(), |memory| {
+    let mut program = Program { instructions: std::mem::take(memory) };
+    let outputs = run_intcode_program_with_limits(&mut program, &inputs, &limits).unwrap_or_else(|_| {
+      stopped = true;
+      Vec::new()
+    });
+    *memory = program.instructions;
+    outputs
+  });
+  if stopped {
+    return None
+  }
+  let actual = run_catching(&mut memory.clone(), |memory| {
+    other(memory, &generated.inputs, MAX_COMPARED_STEPS).unwrap_or_else(|| {
+      stopped = true;
+      Vec::new()
+    })
+  });
+  if stopped {
+    return None
+  }
+  let mismatch = match (&expected, &actual) {
+    (Behaviour::Faulted(_), Behaviour::Faulted(_)) => return None,
+    (Behaviour::Halted { outputs, .. }, Behaviour::Halted { outputs: other_outputs, .. })
+      if outputs != other_outputs => Mismatch::Outputs,
+    (Behaviour::Halted { memory, .. }, Behaviour::Halted { memory: other_memory, .. })
+      if memory != other_memory => Mismatch::Memory,
+    (Behaviour::Halted { .. }, Behaviour::Halted { .. }) => return None,
+    _ => Mismatch::Fault,
+  };
+  Some(Counterexample { generated: generated.clone(), mismatch, expected, actual })
+}
+
+/// Shrink the counterexample for as long as a smaller program still shows
+/// the same kind of mismatch.
+pub
+fn shrink_counterexample(counterexample: Counterexample, other: OtherInterpreter) -> Counterexample {
+  let mut smallest = counterexample;
+  'shrinking: loop {
+    for candidate in smallest.generated.shrink() {
+      if let Some(found) = compare(&candidate, other) {
+        if found.mismatch == smallest.mismatch {
+          smallest = found;
+          continue 'shrinking;
+        }
+      }
+    }
+    return smallest
+  }
+}
+
+/// Compare this crate with another interpreter on a number of generated
+/// programs, and return the smallest counterexample found for each kind of
+/// mismatch.
+///
+/// Both interpreters are expected to panic on faults, which are caught with
+/// their messages, though the panic hook still prints each of them.
+pub
+fn differential_test(other: OtherInterpreter, seed: u64, programs: usize) -> Vec<Counterexample> {
+  let mut found: Vec<Counterexample> = Vec::new();
+  for index in 0..programs as u64 {
+    let generated = generate_program(seed.wrapping_add(index), 1 + (index % 12) as usize);
+    if let Some(counterexample) = compare(&generated, other) {
+      if found.iter().all(|x| x.mismatch != counterexample.mismatch) {
+        found.push(shrink_counterexample(counterexample, other));
+      }
+    }
+  }
+  found.sort_by_key(|x| x.mismatch);
+  found
+}
+
+#[cfg(test)]
+mod differential_tests {
+  use super::*;
+
+  fn same_interpreter(memory: &mut Vec<InstructionType>, inputs: &[InstructionType], max_steps: usize) -> Option<Vec<InstructionType>> {
+    let mut program = Program { instructions: memory.clone() };
+    let limits = ExecutionLimits { max_steps: Some(max_steps), ..Default::default() };
+    let outputs = run_intcode_program_with_limits(&mut program, &Some(inputs.to_vec()), &limits).ok();
+    *memory = program.instructions;
+    outputs
+  }
+
+  #[test]
+  fn no_mismatch_with_itself() {
+    assert!(differential_test(&same_interpreter, 1, 200).is_empty());
+  }
+
+  #[test]
+  fn skips_programs_which_do_not_halt() {
+    use crate::program_generator::GeneratedInstruction;
+    use crate::program_generator::Operand;
+    let looping = GeneratedProgram {
+      instructions: vec![GeneratedInstruction { opcode: 5, operands: vec![Operand::Immediate(1), Operand::Target(0)] }],
+      data: vec![0],
+      inputs: vec![],
+    };
+    let other = |_: &mut Vec<InstructionType>, _: &[InstructionType], _| -> Option<Vec<InstructionType>> { unreachable!() };
+    assert!(compare(&looping, &other).is_none());
+  }
+
+  #[test]
+  fn skips_programs_which_do_not_halt_on_the_other_interpreter() {
+    let generated = generate_program(1, 3);
+    let other = |_: &mut Vec<InstructionType>, _: &[InstructionType], max_steps| -> Option<Vec<InstructionType>> {
+      assert_eq!(max_steps, MAX_COMPARED_STEPS);
+      None
+    };
+    assert!(compare(&generated, &other).is_none());
+  }
+
+  #[test]
+  fn shrinks_to_a_minimal_counterexample() {
+    // prints an extra zero before halting, so even an empty program differs
+    let other = |memory: &mut Vec<InstructionType>, inputs: &[InstructionType], max_steps| {
+      let mut outputs = same_interpreter(memory, inputs, max_steps)?;
+      outputs.push(0);
+      Some(outputs)
+    };
+    let found = differential_test(&other, 1, 50);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].mismatch, Mismatch::Outputs);
+    assert_eq!(found[0].generated.to_program().instructions, vec![99, 0]);
+    assert!(found[0].generated.inputs.is_empty());
+  }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use super::instruction_type::InstructionType;
+use super::operation::ParameterKind;
+use super::operation_instance::try_parse_operation_intcode;
+use super::operation_registry::OperationRegistry;
+use super::operation_registry::standard_registry;
+use super::parameter_mode::ParameterMode;
+
+/// Whether the opcode is JUMP-IF-TRUE or JUMP-IF-FALSE, whatever the
+/// operations are called.
+pub
+fn is_jump_opcode(opcode: InstructionType) -> bool {
+  opcode == 5 || opcode == 6
+}
+
+/// A decoded instruction, as it appears in memory at an address.
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct Instruction {
+  pub address: usize,
+  pub opcode: InstructionType,
+  pub name: &'static str,
+  pub size: usize,
+  pub parameters: Vec<(ParameterMode, InstructionType)>,
+  pub parameter_kinds: &'static [ParameterKind],
+}
+
+impl Instruction {
+  pub
+  fn next_address(&self) -> usize {
+    self.address + self.size
+  }
+
+  pub
+  fn is_jump(&self) -> bool {
+    is_jump_opcode(self.opcode)
+  }
+
+  pub
+  fn is_halt(&self) -> bool {
+    self.opcode == 99
+  }
+
+  /// The address written by the instruction, if it writes to memory.
+  pub
+  fn destination(&self) -> Option<InstructionType> {
+    let index = self.parameter_kinds.iter().position(|x| *x == ParameterKind::Write)?;
+    Some(self.parameters[index].1)
+  }
+}
+
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let parameters: Vec<String> = self.parameters.iter().map(|(mode, value)| match mode {
+      ParameterMode::PositionMode => format!("[{}]", value),
+      ParameterMode::ImmediateMode => format!("{}", value),
+    }).collect();
+    if parameters.is_empty() {
+      write!(f, "{}", self.name)
+    } else {
+      write!(f, "{} {}", self.name, parameters.join(", "))
+    }
+  }
+}
+
+/// Decode the instruction at the address, or None if the value there is not
+/// a known operation or the instruction does not fit in memory.
+pub
+fn decode_instruction(memory: &[InstructionType], address: usize) -> Option<Instruction> {
+  decode_instruction_with_operations(memory, address, standard_registry())
+}
+
+/// Decode the instruction at the address, like `decode_instruction`, but
+/// with the given operations.
+pub
+fn decode_instruction_with_operations(
+  memory: &[InstructionType],
+  address: usize,
+  operations: &OperationRegistry,
+) -> Option<Instruction>
+{
+  let operation_instance = try_parse_operation_intcode(*memory.get(address)?)?;
+  let operation = operations.get(operation_instance.opcode)?;
+  if address + operation.size > memory.len() {
+    return None
+  }
+  let modes = [
+    operation_instance.parameter1_mode,
+    operation_instance.parameter2_mode,
+    operation_instance.parameter3_mode,
+  ];
+  Some(Instruction {
+    address,
+    opcode: operation_instance.opcode,
+    name: operation.name,
+    size: operation.size,
+    parameters: modes.iter().cloned().zip(memory[address + 1..address + operation.size].iter().cloned()).collect(),
+    parameter_kinds: operation.parameters,
+  })
+}
+
+#[cfg(test)]
+mod disassembler_tests {
+  use super::*;
+
+  #[test]
+  fn decode_and_display() {
+    let memory = vec![1002, 4, 3, 4, 33, 99];
+    let instruction = decode_instruction(&memory, 0).unwrap();
+    assert_eq!(instruction.name, "MULTIPLY");
+    assert_eq!(instruction.next_address(), 4);
+    assert_eq!(instruction.destination(), Some(4));
+    assert_eq!(instruction.to_string(), "MULTIPLY [4], 3, [4]");
+    assert_eq!(decode_instruction(&memory, 5).unwrap().to_string(), "HALT");
+  }
+
+  #[test]
+  fn decode_invalid() {
+    // unknown opcode, bad parameter mode, truncated instruction, past the end
+    let memory = vec![42, 201, 1, 0];
+    assert_eq!(decode_instruction(&memory, 0), None);
+    assert_eq!(decode_instruction(&memory, 1), None);
+    assert_eq!(decode_instruction(&memory, 2), None);
+    assert_eq!(decode_instruction(&memory, 4), None);
+  }
+}
@@ -1,46 +1,52 @@
-use std::convert::TryFrom;
-
-use super::instruction_type::InstructionType;
-use super::operation_instance::parse_operation_intcode;
-use super::operations::OPERATIONS;
+use super::instruction_cache::decode_operation;
+use super::operation::validate_operation;
 use super::program::Program;
 use super::program::ProgramContext;
 use super::program::StepError;
+use super::program::StepResult;
+use super::trace::TraceEvent;
 
 pub
 fn execute_instruction_at(
   program: &mut Program,
   program_context: &ProgramContext,
-) -> Result<(usize, Option<InstructionType>), StepError>
+) -> StepResult
 {
-  // operation intcodes must be positive
-  let raw_intcode: InstructionType = program.instructions[program_context.counter.get()];
-  if raw_intcode < 0 {
-    panic!("expected the opcode to be positive, got '{}' instead", raw_intcode);
+  let counter = program_context.counter.get();
+  program_context.trace(|| TraceEvent::Fetch { address: counter, value: program.instructions[counter] });
+  let cached = program_context.instruction_cache.borrow().get(counter);
+  let decoded = cached.unwrap_or_else(|| decode_operation(&program.instructions, counter, program_context.operations()));
+  let operation = decoded.operation;
+  let operation_instance = decoded.instance;
+  program_context.trace(|| {
+    // the instruction may be truncated by the end of the program, which the
+    // validation below will report
+    let modes = [
+      operation_instance.parameter1_mode,
+      operation_instance.parameter2_mode,
+      operation_instance.parameter3_mode,
+    ];
+    let end = (counter + operation.size).min(program.instructions.len());
+    TraceEvent::Decode {
+      address: counter,
+      name: operation.name,
+      parameters: modes.iter().cloned().zip(program.instructions[counter + 1..end].iter().cloned()).collect(),
+    }
+  });
+  if cached.is_none() {
+    // the parameters are only checked again once a write invalidates them
+    validate_operation(&program.instructions, counter, &operation, &operation_instance);
+    program_context.instruction_cache.borrow_mut().insert(program.instructions.len(), counter, decoded);
   }
-  let operation_intcode: InstructionType = InstructionType::try_from(raw_intcode).unwrap();
-  let operation_instance = parse_operation_intcode(operation_intcode);
-  if program_context.trace {
-    println!(
-      "Executing instruction at offset '{}': {:?} {:?}",
-      program_context.counter.get(),
-      &program.instructions[program_context.counter.get()..program_context.counter.get() + 4],
-      operation_instance);
+  let result = (operation.execute)(&mut (program.instructions), program_context, &operation_instance);
+  if let Some(coverage) = &mut *program_context.coverage.borrow_mut() {
+    coverage.record(counter, &operation, &operation_instance, &result);
   }
-  if !OPERATIONS.contains_key(&operation_instance.opcode) {
-    let mut keys = Vec::new();
-    for key in OPERATIONS.keys() {
-      keys.push(key);
-    }
-    keys.sort();
-    panic!(
-      "Unknown operation with opcode '{}', expected one of these: {:?}",
-      &operation_instance.opcode,
-      keys);
+  match result {
+    Ok(_) | Err(StepError::EndOfProgram) => program_context.steps.set(program_context.steps.get() + 1),
+    _ => (),
   }
-  let operation = &OPERATIONS[&operation_instance.opcode];
-  let result = (operation.execute)(&mut (program.instructions), &program_context, &operation_instance);
-  if program_context.counter.get() == usize::max_value() {
+  if program_context.counter.get() == usize::MAX {
     // halt
     return Err(StepError::Error("program counter overflow"))
   }
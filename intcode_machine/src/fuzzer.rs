@@ -0,0 +1,477 @@
+use std::collections::BTreeSet;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io;
+use std::panic;
+use std::path::Path;
+
+use super::coverage::Coverage;
+use super::disassembler::is_jump_opcode;
+use super::instruction_type::InstructionType;
+use super::limits::ExecutionLimits;
+use super::operation::ParameterKind;
+use super::operation_instance::try_parse_operation_intcode;
+use super::operation_registry::OperationRegistry;
+use super::parameter_mode::ParameterMode;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+use super::random::Random;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub
+enum FaultKind {
+  InvalidOpcode,
+  InvalidParameterMode,
+  AddressOutOfBounds,
+  BadJump,
+  Overflow,
+  Other,
+}
+
+impl FaultKind {
+  /// Classify a fault by the instruction at the counter, in memory as it was
+  /// when it faulted, or None if nothing about the instruction explains it.
+  /// Reads from attached devices are not followed.
+  pub
+  fn diagnose(memory: &[InstructionType], counter: usize, operations: &OperationRegistry) -> Option<FaultKind> {
+    let intcode = *memory.get(counter)?;
+    if !(0..=99_999).contains(&intcode) {
+      return Some(FaultKind::InvalidOpcode)
+    }
+    let instance = match try_parse_operation_intcode(intcode) {
+      Some(instance) => instance,
+      None => return Some(FaultKind::InvalidParameterMode),
+    };
+    let operation = match operations.get(instance.opcode) {
+      Some(operation) => operation,
+      None => return Some(FaultKind::InvalidOpcode),
+    };
+    if counter + operation.size > memory.len() {
+      return Some(FaultKind::AddressOutOfBounds)
+    }
+    let modes = [instance.parameter1_mode, instance.parameter2_mode, instance.parameter3_mode];
+    let mut values = Vec::new();
+    for (index, kind) in operation.parameters.iter().enumerate() {
+      let raw = memory[counter + 1 + index];
+      let in_range = raw >= 0 && (raw as usize) < memory.len();
+      values.push(match (modes[index], kind) {
+        (ParameterMode::ImmediateMode, ParameterKind::Write) => return Some(FaultKind::InvalidParameterMode),
+        (ParameterMode::ImmediateMode, ParameterKind::Read) => raw,
+        (ParameterMode::PositionMode, _) if !in_range => return Some(FaultKind::AddressOutOfBounds),
+        (ParameterMode::PositionMode, ParameterKind::Write) => raw,
+        (ParameterMode::PositionMode, ParameterKind::Read) => memory[raw as usize],
+      });
+    }
+    match instance.opcode {
+      1 if values[0].checked_add(values[1]).is_none() => Some(FaultKind::Overflow),
+      2 if values[0].checked_mul(values[1]).is_none() => Some(FaultKind::Overflow),
+      opcode if is_jump_opcode(opcode) => {
+        let taken = (values[0] != 0) == (opcode == 5);
+        let in_range = values[1] >= 0 && (values[1] as usize) < memory.len();
+        if taken && !in_range { Some(FaultKind::BadJump) } else { None }
+      },
+      _ => None,
+    }
+  }
+
+  // classify the message of the panic which stopped the interpreter, for
+  // faults the instruction does not explain
+  fn from_message(message: &str) -> FaultKind {
+    if message.contains("opcode") || message.contains("Unknown operation") || message.contains("digits") {
+      FaultKind::InvalidOpcode
+    } else if message.contains("parameter mode") {
+      FaultKind::InvalidParameterMode
+    } else if message.contains("jump") {
+      FaultKind::BadJump
+    } else if message.contains("address") || message.contains("invalid instruction") || message.contains("index out of") {
+      FaultKind::AddressOutOfBounds
+    } else if message.contains("overflow") {
+      FaultKind::Overflow
+    } else {
+      FaultKind::Other
+    }
+  }
+}
+
+impl fmt::Display for FaultKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      FaultKind::InvalidOpcode => "invalid-opcode",
+      FaultKind::InvalidParameterMode => "invalid-parameter-mode",
+      FaultKind::AddressOutOfBounds => "address-out-of-bounds",
+      FaultKind::BadJump => "bad-jump",
+      FaultKind::Overflow => "overflow",
+      FaultKind::Other => "other",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// A fault found by the fuzzer, with the smallest inputs found to reproduce
+/// it.
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct Fault {
+  pub kind: FaultKind,
+  /// the address of the instruction which faulted
+  pub address: usize,
+  pub message: String,
+  pub inputs: Vec<InstructionType>,
+}
+
+impl fmt::Display for Fault {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} at '{}' with inputs {:?}: {}", self.kind, self.address, self.inputs, self.message)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+enum FuzzOutcome {
+  Halted,
+  /// the program asked for more input than it was given
+  NeedInput,
+  StepLimit,
+  Fault { kind: FaultKind, address: usize, message: String },
+}
+
+/// Something a run exercised, the fuzzer keeps inputs which exercise
+/// something new.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub
+enum Feature {
+  Address(usize),
+  /// a JUMP-IF-TRUE/JUMP-IF-FALSE address, and whether the jump was taken
+  Branch(usize, bool),
+}
+
+fn features(coverage: &Coverage) -> BTreeSet<Feature> {
+  let mut features: BTreeSet<Feature> = coverage.executed.keys().map(|x| Feature::Address(*x)).collect();
+  for (address, branch) in &coverage.branches {
+    if branch.taken > 0 {
+      features.insert(Feature::Branch(*address, true));
+    }
+    if branch.not_taken > 0 {
+      features.insert(Feature::Branch(*address, false));
+    }
+  }
+  features
+}
+
+pub
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+  if let Some(message) = payload.downcast_ref::<String>() {
+    message.clone()
+  } else if let Some(message) = payload.downcast_ref::<&str>() {
+    message.to_string()
+  } else {
+    String::from("unknown panic")
+  }
+}
+
+/// Run the program once with the inputs, and return what it exercised and
+/// how it ended. Faults panic in the interpreter, so they are caught here,
+/// but the panic message is still printed unless the panic hook is replaced.
+pub
+fn fuzz_run(program: &Program, inputs: &[InstructionType], max_steps: usize) -> (BTreeSet<Feature>, FuzzOutcome) {
+  let mut program_instance = ProgramInstance::new(program.clone());
+  {
+    let mut context = program_instance.context.borrow_mut();
+    *context.inputs.borrow_mut() = Some(inputs.iter().cloned().collect());
+    *context.coverage.borrow_mut() = Some(Coverage::new());
+    context.limits = ExecutionLimits { max_steps: Some(max_steps), ..Default::default() };
+  }
+  let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+    loop {
+      match program_instance.step() {
+        Ok((next_counter, _)) => program_instance.context.borrow_mut().counter.set(next_counter),
+        Err(StepError::EndOfProgram) => return Ok(FuzzOutcome::Halted),
+        Err(StepError::NeedInput) => return Ok(FuzzOutcome::NeedInput),
+        Err(StepError::LimitExceeded(_)) => return Ok(FuzzOutcome::StepLimit),
+        Err(StepError::Error(msg)) => return Err(msg.to_string()),
+      }
+    }
+  }));
+  let context = program_instance.context.borrow();
+  let address = context.counter.get();
+  let coverage = context.coverage.borrow_mut().take().unwrap_or_default();
+  let memory = &program_instance.get_program().instructions;
+  let fault = |message: String| {
+    let kind = FaultKind::diagnose(memory, address, context.operations())
+      .unwrap_or_else(|| FaultKind::from_message(&message));
+    FuzzOutcome::Fault { kind, address, message }
+  };
+  let outcome = match result {
+    Ok(Ok(outcome)) => outcome,
+    Ok(Err(message)) => fault(message),
+    Err(payload) => fault(panic_message(&*payload)),
+  };
+  (features(&coverage), outcome)
+}
+
+#[derive(Debug)]
+pub
+struct Fuzzer {
+  program: Program,
+  /// inputs which each exercised something new when they were found
+  pub corpus: Vec<Vec<InstructionType>>,
+  pub faults: Vec<Fault>,
+  /// everything exercised so far
+  pub features: BTreeSet<Feature>,
+  pub max_steps: usize,
+  pub max_inputs: usize,
+  /// values likely to matter, every value in the program and a few edge cases
+  dictionary: Vec<InstructionType>,
+  random: Random,
+}
+
+impl Fuzzer {
+  pub
+  fn new(program: Program, seed: u64) -> Fuzzer {
+    let mut dictionary: BTreeSet<InstructionType> = program.instructions.iter().cloned().collect();
+    dictionary.extend(&[0, 1, -1, InstructionType::MIN, InstructionType::MAX]);
+    Fuzzer {
+      program,
+      corpus: Vec::new(),
+      faults: Vec::new(),
+      features: BTreeSet::new(),
+      max_steps: 100_000,
+      max_inputs: 16,
+      dictionary: dictionary.into_iter().collect(),
+      random: Random::new(seed),
+    }
+  }
+
+  /// Run the inputs, keeping them in the corpus if they exercise something
+  /// new, and recording a new fault. Returns the outcome and whether the
+  /// inputs were kept.
+  pub
+  fn run(&mut self, inputs: &[InstructionType]) -> (FuzzOutcome, bool) {
+    let (features, outcome) = fuzz_run(&self.program, inputs, self.max_steps);
+    let is_new = !features.is_subset(&self.features);
+    if is_new {
+      self.features.extend(features);
+      self.corpus.push(inputs.to_vec());
+    }
+    if let FuzzOutcome::Fault { kind, address, .. } = &outcome {
+      if !self.faults.iter().any(|x| x.kind == *kind && x.address == *address) {
+        let inputs = self.minimize(inputs, *kind, *address);
+        let message = match fuzz_run(&self.program, &inputs, self.max_steps).1 {
+          FuzzOutcome::Fault { message, .. } => message,
+          _ => unreachable!("minimized inputs no longer fault"),
+        };
+        self.faults.push(Fault { kind: *kind, address: *address, message, inputs });
+      }
+    }
+    (outcome, is_new)
+  }
+
+  /// Mutate an input from the corpus and run it.
+  pub
+  fn fuzz_one(&mut self) -> (FuzzOutcome, bool) {
+    let inputs = self.mutate();
+    self.run(&inputs)
+  }
+
+  fn random_value(&mut self) -> InstructionType {
+    match self.random.below(3) {
+      0 => self.dictionary[self.random.below(self.dictionary.len())],
+      1 => self.random.below(201) as InstructionType - 100,
+      _ => self.random.next() as InstructionType,
+    }
+  }
+
+  fn mutate(&mut self) -> Vec<InstructionType> {
+    let mut inputs = if self.corpus.is_empty() {
+      vec![]
+    } else {
+      self.corpus[self.random.below(self.corpus.len())].clone()
+    };
+    for _ in 0..1 + self.random.below(3) {
+      let position = self.random.below(inputs.len() + 1);
+      match self.random.below(5) {
+        0 | 1 if !inputs.is_empty() => {
+          let position = position.min(inputs.len() - 1);
+          inputs[position] = self.random_value();
+        },
+        2 if !inputs.is_empty() => {
+          inputs.remove(position.min(inputs.len() - 1));
+        },
+        3 if !self.corpus.is_empty() => {
+          // splice in the tail of another input
+          let other = self.corpus[self.random.below(self.corpus.len())].clone();
+          let start = self.random.below(other.len() + 1);
+          inputs.truncate(position);
+          inputs.extend_from_slice(&other[start..]);
+        },
+        _ => {
+          let value = self.random_value();
+          inputs.insert(position, value);
+        },
+      }
+    }
+    inputs.truncate(self.max_inputs);
+    inputs
+  }
+
+  /// Shrink the inputs while they still produce the same fault, first by
+  /// removing values and then by binary searching each value towards zero.
+  pub
+  fn minimize(&self, inputs: &[InstructionType], kind: FaultKind, address: usize) -> Vec<InstructionType> {
+    let reproduces = |inputs: &[InstructionType]| match fuzz_run(&self.program, inputs, self.max_steps).1 {
+      FuzzOutcome::Fault { kind: x, address: y, .. } => x == kind && y == address,
+      _ => false,
+    };
+    let mut inputs = inputs.to_vec();
+    let mut changed = true;
+    while changed {
+      changed = false;
+      let mut i = inputs.len();
+      while i > 0 {
+        i -= 1;
+        let mut candidate = inputs.clone();
+        candidate.remove(i);
+        if reproduces(&candidate) {
+          inputs = candidate;
+          changed = true;
+        }
+      }
+      for i in 0..inputs.len() {
+        if inputs[i] == 0 {
+          continue;
+        }
+        // binary search between zero, or the last value which didn't
+        // reproduce the fault, and the smallest value which did
+        let mut candidate = inputs.clone();
+        let mut low = 0;
+        let mut high = i64::from(inputs[i]);
+        candidate[i] = 0;
+        if reproduces(&candidate) {
+          high = 0;
+        }
+        while (high - low).abs() > 1 {
+          let middle = low + (high - low) / 2;
+          candidate[i] = middle as InstructionType;
+          if reproduces(&candidate) {
+            high = middle;
+          } else {
+            low = middle;
+          }
+        }
+        if high != i64::from(inputs[i]) {
+          inputs[i] = high as InstructionType;
+          changed = true;
+        }
+      }
+    }
+    inputs
+  }
+
+  /// Load every corpus file in the directory, one comma separated input
+  /// sequence per file, running each so its coverage counts.
+  pub
+  fn load_corpus(&mut self, directory: &Path) -> io::Result<usize> {
+    let mut loaded = 0;
+    let mut paths: Vec<_> = fs::read_dir(directory)?.filter_map(|x| x.ok()).map(|x| x.path()).collect();
+    paths.sort();
+    for path in paths.iter().filter(|x| x.extension().is_some_and(|x| x == "txt")) {
+      let inputs = parse_inputs(&fs::read_to_string(path)?);
+      self.run(&inputs);
+      loaded += 1;
+    }
+    Ok(loaded)
+  }
+
+  /// Save the corpus, and the reproducers of the faults in a 'faults'
+  /// subdirectory, named after what they contain so existing files are
+  /// simply overwritten.
+  pub
+  fn save(&self, directory: &Path) -> io::Result<()> {
+    fs::create_dir_all(directory.join("faults"))?;
+    for inputs in &self.corpus {
+      let mut hasher = DefaultHasher::new();
+      inputs.hash(&mut hasher);
+      fs::write(directory.join(format!("{:016x}.txt", hasher.finish())), format_inputs(inputs))?;
+    }
+    for fault in &self.faults {
+      let name = format!("{}-{}.txt", fault.kind, fault.address);
+      fs::write(directory.join("faults").join(name), format_inputs(&fault.inputs))?;
+    }
+    Ok(())
+  }
+}
+
+fn format_inputs(inputs: &[InstructionType]) -> String {
+  let values: Vec<String> = inputs.iter().map(|x| x.to_string()).collect();
+  values.join(",") + "\n"
+}
+
+fn parse_inputs(content: &str) -> Vec<InstructionType> {
+  content.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()).filter_map(|x| x.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod fuzzer_tests {
+  use super::*;
+
+  #[test]
+  fn classifies_faults() {
+    // the input is written over the parameter of the PRINT
+    let program = Program::from_string("3,3,4,0,99");
+    assert_eq!(fuzz_run(&program, &[4], 100).1, FuzzOutcome::Halted);
+    assert_eq!(fuzz_run(&program, &[], 100).1, FuzzOutcome::NeedInput);
+    match fuzz_run(&program, &[5], 100).1 {
+      FuzzOutcome::Fault { kind, address, .. } => assert_eq!((kind, address), (FaultKind::AddressOutOfBounds, 2)),
+      x => panic!("expected a fault, got {:?}", x),
+    }
+    assert_eq!(fuzz_run(&Program::from_string("1105,1,0"), &[], 100).1, FuzzOutcome::StepLimit);
+  }
+
+  #[test]
+  fn diagnoses_faults_from_the_instruction() {
+    let diagnose = |program: &str, counter| {
+      FaultKind::diagnose(&Program::from_string(program).instructions, counter, &OperationRegistry::standard())
+    };
+    assert_eq!(diagnose("123456", 0), Some(FaultKind::InvalidOpcode));
+    assert_eq!(diagnose("42,0", 0), Some(FaultKind::InvalidOpcode));
+    assert_eq!(diagnose("304,0", 0), Some(FaultKind::InvalidParameterMode));
+    assert_eq!(diagnose("103,0", 0), Some(FaultKind::InvalidParameterMode));
+    assert_eq!(diagnose("4,9", 0), Some(FaultKind::AddressOutOfBounds));
+    assert_eq!(diagnose("1,0,0", 0), Some(FaultKind::AddressOutOfBounds));
+    assert_eq!(diagnose("1105,1,9", 0), Some(FaultKind::BadJump));
+    assert_eq!(diagnose("1106,1,9", 0), None);
+    assert_eq!(diagnose("1102,100000,100000,0", 0), Some(FaultKind::Overflow));
+  }
+
+  #[test]
+  fn finds_and_minimizes_bad_jump() {
+    // jumps out of the program only when the input is 42
+    let program = Program::from_string("3,14,1008,14,42,15,1006,15,13,1105,1,999,0,99,0,0");
+    let mut fuzzer = Fuzzer::new(program, 7);
+    fuzzer.run(&[1, 2, 3]);
+    for _ in 0..1_000 {
+      if !fuzzer.faults.is_empty() {
+        break;
+      }
+      fuzzer.fuzz_one();
+    }
+    assert_eq!(fuzzer.faults.len(), 1);
+    assert_eq!(fuzzer.faults[0].kind, FaultKind::BadJump);
+    assert_eq!(fuzzer.faults[0].address, 9);
+    assert_eq!(fuzzer.faults[0].inputs, vec![42]);
+    assert!(fuzzer.features.contains(&Feature::Branch(6, true)));
+    assert!(fuzzer.features.contains(&Feature::Branch(6, false)));
+  }
+
+  #[test]
+  fn minimizes_values_towards_zero() {
+    let program = Program::from_string("3,3,4,0,99");
+    let fuzzer = Fuzzer::new(program, 1);
+    assert_eq!(fuzzer.minimize(&[7, 1000, 3], FaultKind::AddressOutOfBounds, 2), vec![5]);
+    assert_eq!(fuzzer.minimize(&[InstructionType::MAX], FaultKind::AddressOutOfBounds, 2), vec![5]);
+  }
+}
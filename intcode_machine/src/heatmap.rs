@@ -0,0 +1,178 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::trace::TraceEvent;
+use super::trace::Tracer;
+
+/// How often an address was read, written and executed, where an address
+/// is executed when it holds the opcode or a parameter of an instruction run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub
+struct AccessCounts {
+  pub reads: usize,
+  pub writes: usize,
+  pub executes: usize,
+}
+
+impl AccessCounts {
+  /// The larger of each count.
+  pub
+  fn max(self, other: AccessCounts) -> AccessCounts {
+    AccessCounts {
+      reads: self.reads.max(other.reads),
+      writes: self.writes.max(other.writes),
+      executes: self.executes.max(other.executes),
+    }
+  }
+}
+
+/// The access counts of each address, from 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct Heatmap {
+  pub counts: Vec<AccessCounts>,
+}
+
+// 0 to 255, on a log scale so rare accesses still show
+fn intensity(count: usize, peak: usize) -> usize {
+  if count == 0 || peak == 0 {
+    return 0
+  }
+  (255.0 * (count as f64).ln_1p() / (peak as f64).ln_1p()).round() as usize
+}
+
+impl Heatmap {
+  fn counts_mut(&mut self, address: usize) -> &mut AccessCounts {
+    if address >= self.counts.len() {
+      self.counts.resize(address + 1, AccessCounts::default());
+    }
+    &mut self.counts[address]
+  }
+
+  pub
+  fn record(&mut self, event: &TraceEvent) {
+    match event {
+      TraceEvent::Decode { address, parameters, .. } => {
+        for address in *address..=address + parameters.len() {
+          self.counts_mut(address).executes += 1;
+        }
+      },
+      TraceEvent::Read { address, .. } => self.counts_mut(*address).reads += 1,
+      TraceEvent::Write { address, .. } => self.counts_mut(*address).writes += 1,
+      _ => {},
+    }
+  }
+
+  /// The largest count of each kind over all addresses.
+  pub
+  fn peak(&self) -> AccessCounts {
+    self.counts.iter().fold(AccessCounts::default(), |peak, x| peak.max(*x))
+  }
+
+  /// A plain PPM image with a pixel for each of `addresses` addresses, in
+  /// rows of `width`, where writes are red, reads green and executes blue,
+  /// each as bright as its count compared to the peak.
+  pub
+  fn render_ppm(&self, width: usize, addresses: usize, peak: AccessCounts) -> String {
+    let width = width.max(1);
+    let height = addresses.div_ceil(width);
+    let mut output = format!("P3\n{} {}\n255\n", width, height);
+    for row in 0..height {
+      let pixels: Vec<String> = (row * width..(row + 1) * width)
+        .map(|address| {
+          let counts = self.counts.get(address).copied().unwrap_or_default();
+          format!(
+            "{} {} {}",
+            intensity(counts.writes, peak.writes),
+            intensity(counts.reads, peak.reads),
+            intensity(counts.executes, peak.executes))
+        })
+        .collect();
+      output += &pixels.join(" ");
+      output.push('\n');
+    }
+    output
+  }
+}
+
+#[derive(Debug, Default)]
+struct Heatmaps {
+  total: Heatmap,
+  frames: Vec<Heatmap>,
+}
+
+/// A tracer which counts the accesses to each address over the whole run,
+/// and in frames of `steps_per_frame` steps. Clones share the counts, so one
+/// can be given to the program and the other kept to read them.
+#[derive(Debug, Clone)]
+pub
+struct HeatmapTracer {
+  steps_per_frame: usize,
+  heatmaps: Rc<RefCell<Heatmaps>>,
+}
+
+impl HeatmapTracer {
+  pub
+  fn new(steps_per_frame: usize) -> HeatmapTracer {
+    HeatmapTracer { steps_per_frame: steps_per_frame.max(1), heatmaps: Rc::default() }
+  }
+
+  pub
+  fn total(&self) -> Heatmap {
+    self.heatmaps.borrow().total.clone()
+  }
+
+  pub
+  fn frames(&self) -> Vec<Heatmap> {
+    self.heatmaps.borrow().frames.clone()
+  }
+}
+
+impl Tracer for HeatmapTracer {
+  fn event(&mut self, step: usize, event: &TraceEvent) {
+    let heatmaps = &mut *self.heatmaps.borrow_mut();
+    heatmaps.total.record(event);
+    let frame = step / self.steps_per_frame;
+    if frame >= heatmaps.frames.len() {
+      heatmaps.frames.resize(frame + 1, Heatmap::default());
+    }
+    heatmaps.frames[frame].record(event);
+  }
+}
+
+#[cfg(test)]
+mod heatmap_tests {
+  use super::*;
+  use crate::program::Program;
+  use crate::run_intcode_program::run_intcode_program_with_tracer;
+
+  #[test]
+  fn counts_accesses() {
+    let tracer = HeatmapTracer::new(1);
+    let mut program = Program::from_string("1,0,0,0,99");
+    run_intcode_program_with_tracer(&mut program, &Some(Vec::new()), Box::new(tracer.clone()));
+    let executed = AccessCounts { executes: 1, ..Default::default() };
+    assert_eq!(tracer.total().counts, vec![
+      AccessCounts { reads: 2, writes: 1, executes: 1 },
+      executed,
+      executed,
+      executed,
+      executed,
+    ]);
+    let frames = tracer.frames();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].counts.len(), 4);
+    assert_eq!(frames[1].counts[4], executed);
+    assert_eq!(frames[1].counts[0], AccessCounts::default());
+  }
+
+  #[test]
+  fn renders_ppm() {
+    let heatmap = Heatmap { counts: vec![
+      AccessCounts { reads: 3, writes: 1, executes: 1 },
+      AccessCounts { reads: 1, writes: 0, executes: 1 },
+    ] };
+    assert_eq!(heatmap.peak(), AccessCounts { reads: 3, writes: 1, executes: 1 });
+    assert_eq!(heatmap.render_ppm(2, 3, heatmap.peak()), "P3\n2 2\n255\n255 255 255 0 128 255\n0 0 0 0 0 0\n");
+  }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeSet;
+
+use super::instruction_type::InstructionType;
+use super::program::Interrupt;
+use super::program::ProgramInstance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Direction {
+  Up,
+  Right,
+  Down,
+  Left,
+}
+
+impl Direction {
+  pub
+  fn turned(self, clockwise: bool) -> Direction {
+    let directions = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+    let index = directions.iter().position(|x| *x == self).unwrap();
+    directions[if clockwise { (index + 1) % 4 } else { (index + 3) % 4 }]
+  }
+}
+
+/// The panels of an unbounded hull, all black until painted white, with y
+/// growing downwards.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct Hull {
+  pub white: BTreeSet<(i32, i32)>,
+  /// every panel painted at least once, in either colour
+  pub painted: BTreeSet<(i32, i32)>,
+}
+
+impl Hull {
+  pub
+  fn is_white(&self, position: (i32, i32)) -> bool {
+    self.white.contains(&position)
+  }
+
+  pub
+  fn paint(&mut self, position: (i32, i32), white: bool) {
+    if white {
+      self.white.insert(position);
+    } else {
+      self.white.remove(&position);
+    }
+    self.painted.insert(position);
+  }
+
+  // the smallest corners around the white panels, inclusive
+  fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+    let first = self.white.iter().next()?;
+    Some(self.white.iter().fold((*first, *first), |((x0, y0), (x1, y1)), (x, y)| {
+      ((x0.min(*x), y0.min(*y)), (x1.max(*x), y1.max(*y)))
+    }))
+  }
+
+  fn rows(&self) -> Vec<Vec<bool>> {
+    match self.bounds() {
+      Some(((x0, y0), (x1, y1))) => (y0..=y1)
+        .map(|y| (x0..=x1).map(|x| self.is_white((x, y))).collect())
+        .collect(),
+      None => Vec::new(),
+    }
+  }
+
+  /// The white panels as '#' and the black ones as '.', cropped to the white
+  /// panels.
+  pub
+  fn render_text(&self) -> String {
+    let mut output = String::new();
+    for row in self.rows() {
+      output.extend(row.iter().map(|white| if *white { '#' } else { '.' }));
+      output.push('\n');
+    }
+    output
+  }
+
+  /// A plain PBM image of the hull, cropped like `render_text`.
+  pub
+  fn render_pbm(&self) -> String {
+    let rows = self.rows();
+    let width = rows.first().map_or(0, |x| x.len());
+    let mut output = format!("P1\n{} {}\n", width, rows.len());
+    for row in rows {
+      // in PBM, 1 is black
+      let values: Vec<&str> = row.iter().map(|white| if *white { "0" } else { "1" }).collect();
+      output += &values.join(" ");
+      output.push('\n');
+    }
+    output
+  }
+}
+
+/// A robot which moves over the hull, painting it as its brain tells it to.
+///
+/// For each panel, the brain is given 0 for black or 1 for white, and
+/// answers with the colour to paint, then 0 to turn left or 1 to turn right,
+/// before the robot moves forward one panel.
+#[derive(Debug, Clone)]
+pub
+struct HullRobot {
+  pub position: (i32, i32),
+  pub direction: Direction,
+  pub hull: Hull,
+}
+
+impl HullRobot {
+  pub
+  fn new(hull: Hull) -> HullRobot {
+    HullRobot { position: (0, 0), direction: Direction::Up, hull }
+  }
+
+  /// Paint the current panel, turn and move forward.
+  pub
+  fn paint_and_move(&mut self, colour: InstructionType, turn: InstructionType) {
+    if !(0..=1).contains(&colour) {
+      panic!("expected a colour of 0 or 1, got '{}'", colour);
+    }
+    if !(0..=1).contains(&turn) {
+      panic!("expected a turn of 0 or 1, got '{}'", turn);
+    }
+    self.hull.paint(self.position, colour == 1);
+    self.direction = self.direction.turned(turn == 1);
+    let (x, y) = self.position;
+    self.position = match self.direction {
+      Direction::Up => (x, y - 1),
+      Direction::Right => (x + 1, y),
+      Direction::Down => (x, y + 1),
+      Direction::Left => (x - 1, y),
+    };
+  }
+
+  /// Run the brain until it halts.
+  pub
+  fn run(&mut self, brain: &mut ProgramInstance) {
+    let mut pending = Vec::new();
+    loop {
+      match brain.resume() {
+        Interrupt::Output(value) => {
+          pending.push(value);
+          if pending.len() == 2 {
+            self.paint_and_move(pending[0], pending[1]);
+            pending.clear();
+          }
+        },
+        Interrupt::NeedInput => brain.push_input(self.hull.is_white(self.position) as InstructionType),
+        Interrupt::Halted => break,
+        Interrupt::Stopped(limit) => panic!("unexpected stop by {}", limit),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod hull_robot_tests {
+  use super::*;
+  use crate::program::Program;
+
+  // the moves of the example robot, whatever the colours it is given
+  const EXAMPLE: &str = "104,1,104,0,104,0,104,0,104,1,104,0,104,1,104,0,104,0,104,1,104,1,104,0,104,1,104,0,99";
+
+  #[test]
+  fn paints_the_example() {
+    let mut robot = HullRobot::new(Hull::default());
+    robot.run(&mut ProgramInstance::new(Program::from_string(EXAMPLE)));
+    assert_eq!(robot.hull.painted.len(), 6);
+    assert_eq!(robot.position, (0, -1));
+    assert_eq!(robot.direction, Direction::Left);
+    assert_eq!(robot.hull.render_text(), "..#\n..#\n##.\n");
+    assert_eq!(robot.hull.render_pbm(), "P1\n3 3\n1 1 0\n1 1 0\n0 0 1\n");
+  }
+
+  #[test]
+  fn gives_the_colour_of_the_current_panel() {
+    // paints white and turns right until it is given white
+    let brain = "3,100,1005,100,12,104,1,104,1,1105,1,0,99";
+    let mut program = Program::from_string(brain);
+    program.instructions.resize(101, 0);
+    let mut robot = HullRobot::new(Hull::default());
+    let mut program_instance = ProgramInstance::new(program);
+    robot.run(&mut program_instance);
+    // after four right turns, the robot is back on the first panel
+    assert_eq!(robot.hull.painted.len(), 4);
+    assert_eq!(robot.position, (0, 0));
+  }
+}
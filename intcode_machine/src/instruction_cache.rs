@@ -0,0 +1,116 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::instruction_type::InstructionType;
+use super::operation::Operation;
+use super::operation_instance::OperationInstance;
+use super::operation_instance::parse_operation_intcode;
+use super::operation_registry::OperationRegistry;
+
+/// An instruction which has been parsed, looked up and validated, so it can
+/// be executed again without repeating that work.
+#[derive(Clone, Copy)]
+pub
+struct DecodedInstruction {
+  pub operation: Operation,
+  pub instance: OperationInstance,
+}
+
+// the largest instruction, a write this many addresses before an instruction
+// cannot touch it
+const MAX_INSTRUCTION_SIZE: usize = 4;
+
+/// Decoded instructions by address, which must be invalidated whenever the
+/// memory they were decoded from is written.
+#[derive(Default)]
+pub
+struct InstructionCache {
+  entries: Vec<Option<DecodedInstruction>>,
+}
+
+impl fmt::Debug for InstructionCache {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "InstructionCache {{ decoded: {} }}", self.entries.iter().filter(|x| x.is_some()).count())
+  }
+}
+
+impl InstructionCache {
+  pub
+  fn get(&self, address: usize) -> Option<DecodedInstruction> {
+    self.entries.get(address).and_then(|x| *x)
+  }
+
+  pub
+  fn insert(&mut self, memory_size: usize, address: usize, decoded: DecodedInstruction) {
+    if self.entries.len() != memory_size {
+      self.entries = vec![None; memory_size];
+    }
+    self.entries[address] = Some(decoded);
+  }
+
+  /// Forget any instruction which includes the address.
+  pub
+  fn invalidate(&mut self, address: usize) {
+    let start = address.saturating_sub(MAX_INSTRUCTION_SIZE - 1);
+    let end = (address + 1).min(self.entries.len());
+    for entry in self.entries.iter_mut().take(end).skip(start) {
+      *entry = None;
+    }
+  }
+
+  pub
+  fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
+
+/// Parse the intcode at the counter and look up its operation, panicking if
+/// it is not one of the given operations.
+pub
+fn decode_operation(memory: &[InstructionType], counter: usize, operations: &OperationRegistry) -> DecodedInstruction {
+  // operation intcodes must be positive
+  let raw_intcode: InstructionType = memory[counter];
+  if raw_intcode < 0 {
+    panic!("expected the opcode to be positive, got '{}' instead", raw_intcode);
+  }
+  let operation_intcode: InstructionType = InstructionType::try_from(raw_intcode).unwrap();
+  let instance = parse_operation_intcode(operation_intcode);
+  match operations.get(instance.opcode) {
+    Some(operation) => DecodedInstruction { operation: *operation, instance },
+    None => panic!(
+      "Unknown operation with opcode '{}', expected one of these: {}",
+      &instance.opcode,
+      operations),
+  }
+}
+
+#[cfg(test)]
+mod instruction_cache_tests {
+  use super::*;
+  use crate::operation_registry::standard_registry;
+  use crate::program::Program;
+  use crate::run_intcode_program::run_intcode_program;
+
+  #[test]
+  fn invalidates_overlapping_instructions() {
+    let memory = vec![1002, 4, 3, 4, 99];
+    let mut cache = InstructionCache::default();
+    cache.insert(memory.len(), 0, decode_operation(&memory, 0, standard_registry()));
+    cache.insert(memory.len(), 4, decode_operation(&memory, 4, standard_registry()));
+    assert_eq!(cache.get(0).unwrap().operation.name, "MULTIPLY");
+    cache.invalidate(4);
+    assert!(cache.get(0).is_some());
+    assert!(cache.get(4).is_none());
+    cache.invalidate(3);
+    assert!(cache.get(0).is_none());
+  }
+
+  #[test]
+  fn executes_rewritten_instruction() {
+    // the add at 0 is turned into a multiply by 4, before the jump at 16
+    // runs it a second time
+    let mut program = Program::from_string("1,20,21,20,1101,1,1,0,1001,22,1,22,1008,22,2,23,1006,23,0,99,2,3,0,0");
+    run_intcode_program(&mut program, &Some(vec![]));
+    assert_eq!(program.instructions[20], 15);
+  }
+}
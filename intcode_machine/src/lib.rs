@@ -1,14 +1,180 @@
+mod adventure;
+mod arcade;
+mod ascii;
+mod control_flow;
+mod coverage;
+mod decompiler;
+mod devices;
+mod differential;
+mod disassembler;
 mod execute_instruction;
+mod fuzzer;
+mod heatmap;
+mod hull_robot;
+mod instruction_cache;
 mod instruction_type;
+mod limits;
+mod loop_detection;
+mod monitor;
 mod operation;
 mod operation_instance;
+mod operation_registry;
 mod operations;
 mod parameter_mode;
+mod probe;
 mod program;
+mod program_generator;
+mod random;
 mod run_intcode_program;
+mod scaffold;
+mod session;
+mod springscript;
+mod symbolic;
+mod trace;
+mod trace_diff;
+mod transpiler;
 
+pub use crate::adventure::Exploration;
+pub use crate::adventure::IntcodeGame;
+pub use crate::adventure::Reply;
+pub use crate::adventure::Room;
+pub use crate::adventure::Solution;
+pub use crate::adventure::TextGame;
+pub use crate::adventure::explore;
+pub use crate::adventure::parse_rooms;
+pub use crate::adventure::solve;
+pub use crate::arcade::Arcade;
+pub use crate::arcade::ArcadeStatus;
+pub use crate::arcade::Joystick;
+pub use crate::arcade::Screen;
+pub use crate::arcade::Tile;
+pub use crate::ascii::AsciiMachine;
+pub use crate::ascii::AsciiOutput;
+pub use crate::ascii::AsciiStatus;
+pub use crate::ascii::encode_line;
+pub use crate::control_flow::BasicBlock;
+pub use crate::control_flow::CodeWrite;
+pub use crate::control_flow::ControlFlowGraph;
+pub use crate::control_flow::Successor;
+pub use crate::coverage::BranchCoverage;
+pub use crate::coverage::Coverage;
+pub use crate::decompiler::decompile;
+pub use crate::devices::Device;
+pub use crate::devices::Devices;
+pub use crate::devices::Framebuffer;
+pub use crate::devices::RandomSource;
+pub use crate::devices::Timer;
+pub use crate::differential::Behaviour;
+pub use crate::differential::Counterexample;
+pub use crate::differential::MAX_COMPARED_STEPS;
+pub use crate::differential::Mismatch;
+pub use crate::differential::OtherInterpreter;
+pub use crate::differential::compare;
+pub use crate::differential::differential_test;
+pub use crate::differential::shrink_counterexample;
+pub use crate::disassembler::Instruction;
+pub use crate::disassembler::decode_instruction;
+pub use crate::disassembler::decode_instruction_with_operations;
+pub use crate::fuzzer::Fault;
+pub use crate::fuzzer::FaultKind;
+pub use crate::fuzzer::Feature;
+pub use crate::fuzzer::FuzzOutcome;
+pub use crate::fuzzer::Fuzzer;
+pub use crate::fuzzer::fuzz_run;
+pub use crate::fuzzer::panic_message;
+pub use crate::heatmap::AccessCounts;
+pub use crate::heatmap::Heatmap;
+pub use crate::heatmap::HeatmapTracer;
+pub use crate::hull_robot::Direction;
+pub use crate::hull_robot::Hull;
+pub use crate::hull_robot::HullRobot;
+pub use crate::limits::ExecutionLimits;
+pub use crate::limits::Limit;
+pub use crate::limits::LimitExceeded;
+pub use crate::loop_detection::DetectedLoop;
+pub use crate::loop_detection::LoopCheckpoint;
+pub use crate::loop_detection::LoopDetectionResult;
+pub use crate::loop_detection::detect_infinite_loop;
+pub use crate::monitor::Monitor;
+pub use crate::monitor::MonitorStatus;
+pub use crate::operation::Operation;
+pub use crate::operation::ParameterKind;
+pub use crate::operation_instance::OperationInstance;
+pub use crate::operation_registry::OperationRegistry;
+pub use crate::operations::get_parameter_value;
+pub use crate::operations::set_value;
+pub use crate::parameter_mode::ParameterMode;
+pub use crate::probe::Probe;
+pub use crate::probe::beam_row;
+pub use crate::probe::beam_rows;
+pub use crate::probe::find_square;
+pub use crate::program::Interrupt;
 pub use crate::program::Program;
+pub use crate::program::ProgramContext;
 pub use crate::program::ProgramInstance;
+pub use crate::program::Snapshot;
 pub use crate::program::StepError;
+pub use crate::program::StepResult;
+pub use crate::program_generator::GeneratedInstruction;
+pub use crate::program_generator::GeneratedProgram;
+pub use crate::program_generator::Operand;
+pub use crate::program_generator::generate_program;
 pub use crate::run_intcode_program::run_intcode_program;
+pub use crate::run_intcode_program::run_intcode_program_with_coverage;
+pub use crate::run_intcode_program::run_intcode_program_with_limits;
+pub use crate::run_intcode_program::run_intcode_program_with_operations;
+pub use crate::run_intcode_program::run_intcode_program_with_tracer;
 pub use crate::run_intcode_program::trace_intcode_program;
+pub use crate::scaffold::MAX_FUNCTIONS;
+pub use crate::scaffold::MAX_ROUTINE_LENGTH;
+pub use crate::scaffold::Move;
+pub use crate::scaffold::Routines;
+pub use crate::scaffold::ScaffoldMap;
+pub use crate::scaffold::camera_view;
+pub use crate::scaffold::compress_path;
+pub use crate::scaffold::join_moves;
+pub use crate::scaffold::run_scaffold;
+pub use crate::session::ReplayMismatch;
+pub use crate::session::Session;
+pub use crate::session::SessionEntry;
+pub use crate::session::SessionRecorder;
+pub use crate::session::record_intcode_program;
+pub use crate::session::replay_intcode_program;
+pub use crate::springscript::Gate;
+pub use crate::springscript::MAX_INSTRUCTIONS;
+pub use crate::springscript::Register;
+pub use crate::springscript::SpringFrame;
+pub use crate::springscript::SpringInstruction;
+pub use crate::springscript::SpringMode;
+pub use crate::springscript::Springscript;
+pub use crate::springscript::SpringscriptError;
+pub use crate::springscript::SpringdroidOutcome;
+pub use crate::springscript::parse_failure_frames;
+pub use crate::springscript::run_springscript;
+pub use crate::springscript::synthesize_springscript;
+pub use crate::symbolic::Assignment;
+pub use crate::symbolic::MAX_ENUMERATED_VALUES;
+pub use crate::symbolic::Constraint;
+pub use crate::symbolic::Expression;
+pub use crate::symbolic::PathEnd;
+pub use crate::symbolic::SolverResult;
+pub use crate::symbolic::Symbol;
+pub use crate::symbolic::SymbolDomain;
+pub use crate::symbolic::SymbolicGoal;
+pub use crate::symbolic::SymbolicPath;
+pub use crate::symbolic::explore_intcode_program;
+pub use crate::symbolic::solve_constraints;
+pub use crate::symbolic::solve_intcode_program;
+pub use crate::trace::BufferTracer;
+pub use crate::trace::JsonLinesTracer;
+pub use crate::trace::TextTracer;
+pub use crate::trace::TraceEvent;
+pub use crate::trace::Tracer;
+pub use crate::trace_diff::Divergence;
+pub use crate::trace_diff::TraceStep;
+pub use crate::trace_diff::diff_runs;
+pub use crate::trace_diff::diff_traces;
+pub use crate::trace_diff::group_trace_steps;
+pub use crate::trace_diff::trace_run;
+pub use crate::transpiler::interpret_step;
+pub use crate::transpiler::transpile;
@@ -0,0 +1,169 @@
+use std::fmt;
+
+use super::instruction_type::InstructionType;
+use super::operation_instance::try_parse_operation_intcode;
+use super::operation_registry::OperationRegistry;
+
+/// Limits on a program's execution, None means unlimited.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub
+struct ExecutionLimits {
+  pub max_steps: Option<usize>,
+  /// the most values of memory, checked once by
+  /// `run_intcode_program_with_limits` before running, as memory does not
+  /// grow while a program runs
+  pub max_memory: Option<usize>,
+  /// the most outputs, stopping before the next operation which prints
+  pub max_outputs: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Limit {
+  Steps(usize),
+  Memory(usize),
+  Outputs(usize),
+}
+
+impl fmt::Display for Limit {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Limit::Steps(x) => write!(f, "step limit of '{}'", x),
+      Limit::Memory(x) => write!(f, "memory limit of '{}'", x),
+      Limit::Outputs(x) => write!(f, "output limit of '{}'", x),
+    }
+  }
+}
+
+/// The state of a program when it was stopped by one of its limits, before
+/// the instruction at the counter was executed.
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct LimitExceeded {
+  pub limit: Limit,
+  pub counter: usize,
+  pub steps: usize,
+  pub memory: Vec<InstructionType>,
+  pub outputs: Vec<InstructionType>,
+}
+
+impl fmt::Display for LimitExceeded {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "stopped by {} at counter '{}' after '{}' steps and '{}' outputs",
+      self.limit,
+      self.counter,
+      self.steps,
+      self.outputs.len())
+  }
+}
+
+impl ExecutionLimits {
+  /// Check the program's memory against its limit, before running it.
+  pub
+  fn check_memory(&self, memory: &[InstructionType]) -> Option<Limit> {
+    match self.max_memory {
+      Some(max_memory) if memory.len() > max_memory => Some(Limit::Memory(max_memory)),
+      _ => None,
+    }
+  }
+
+  // check the limits other than memory before executing the next instruction
+  pub
+  fn check(
+    &self,
+    memory: &[InstructionType],
+    counter: usize,
+    steps: usize,
+    outputs: usize,
+    operations: &OperationRegistry,
+  ) -> Option<Limit>
+  {
+    if let Some(max_steps) = self.max_steps {
+      if steps >= max_steps {
+        return Some(Limit::Steps(max_steps))
+      }
+    }
+    if let Some(max_outputs) = self.max_outputs {
+      // only stop when the next instruction would print
+      let prints = || try_parse_operation_intcode(memory[counter])
+        .and_then(|x| operations.get(x.opcode))
+        .is_some_and(|x| x.prints);
+      if outputs >= max_outputs && prints() {
+        return Some(Limit::Outputs(max_outputs))
+      }
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod limits_tests {
+  use super::*;
+  use crate::program::Program;
+  use crate::run_intcode_program::run_intcode_program_with_limits;
+
+  #[test]
+  fn step_limit_stops_infinite_loop() {
+    let program = Program::from_string("1105,1,0");
+    let limits = ExecutionLimits { max_steps: Some(100), ..Default::default() };
+    let stopped = run_intcode_program_with_limits(&mut program.clone(), &Some(vec![]), &limits).unwrap_err();
+    assert_eq!(stopped, LimitExceeded {
+      limit: Limit::Steps(100),
+      counter: 0,
+      steps: 100,
+      memory: vec![1105, 1, 0],
+      outputs: vec![],
+    });
+    assert_eq!(stopped.to_string(), "stopped by step limit of '100' at counter '0' after '100' steps and '0' outputs");
+  }
+
+  #[test]
+  fn output_limit() {
+    // print the value at 9 forever, incrementing it each time
+    let program = Program::from_string("4,9,101,1,9,9,1105,1,0,7");
+    let limits = ExecutionLimits { max_outputs: Some(3), ..Default::default() };
+    let stopped = run_intcode_program_with_limits(&mut program.clone(), &Some(vec![]), &limits).unwrap_err();
+    assert_eq!(stopped.limit, Limit::Outputs(3));
+    assert_eq!(stopped.outputs, vec![7, 8, 9]);
+    assert_eq!(stopped.counter, 0);
+    assert_eq!(stopped.memory[9], 10);
+  }
+
+  #[test]
+  fn output_limit_follows_the_operations() {
+    use std::rc::Rc;
+    use crate::operation_registry::OperationRegistry;
+    use crate::program::ProgramInstance;
+    use crate::program::StepError;
+    // PRINT moved to opcode 9, and 4 no longer prints
+    let mut registry = OperationRegistry::standard();
+    let print = registry.remove(4).unwrap();
+    registry.register(9, print);
+    let mut program_instance = ProgramInstance::new(Program::from_string("9,5,9,5,99,7"));
+    program_instance.set_operations(Rc::new(registry));
+    program_instance.context.get_mut().limits = ExecutionLimits { max_outputs: Some(1), ..Default::default() };
+    assert!(matches!(program_instance.step(), Ok((2, Some(7)))));
+    program_instance.context.get_mut().counter.set(2);
+    assert!(matches!(program_instance.step(), Err(StepError::LimitExceeded(Limit::Outputs(1)))));
+  }
+
+  #[test]
+  fn memory_limit() {
+    let program = Program::from_string("1,0,0,0,99");
+    let limits = ExecutionLimits { max_memory: Some(4), ..Default::default() };
+    let stopped = run_intcode_program_with_limits(&mut program.clone(), &Some(vec![]), &limits).unwrap_err();
+    assert_eq!(stopped.limit, Limit::Memory(4));
+    assert_eq!(stopped.steps, 0);
+    // checked once up front, not before each step
+    assert_eq!(limits.check(&program.instructions, 0, 0, 0, &OperationRegistry::standard()), None);
+  }
+
+  #[test]
+  fn halting_within_limits() {
+    let program = Program::from_string("4,3,99,7");
+    let limits = ExecutionLimits { max_steps: Some(2), max_memory: Some(4), max_outputs: Some(1) };
+    assert_eq!(run_intcode_program_with_limits(&mut program.clone(), &Some(vec![]), &limits), Ok(vec![7]));
+  }
+}
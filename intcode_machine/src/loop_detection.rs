@@ -0,0 +1,204 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use super::instruction_type::InstructionType;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+
+/// When to take a snapshot of the machine state to compare with earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub
+enum LoopCheckpoint {
+  /// every n steps
+  Interval(usize),
+  /// after every jump to the same or a lower address
+  BackwardJumps,
+}
+
+/// A cycle in which the machine returns to exactly the same state, and so
+/// will never halt.
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct DetectedLoop {
+  /// the counter at the repeated state
+  pub entry: usize,
+  /// the step at which the repeated state was first seen
+  pub first_step: usize,
+  /// the number of steps in the cycle
+  pub length: usize,
+  /// the addresses of the instructions executed in the cycle
+  pub addresses: Vec<usize>,
+}
+
+impl fmt::Display for DetectedLoop {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "infinite loop of '{}' steps entered at '{}' on step '{}', through addresses {:?}",
+      self.length,
+      self.entry,
+      self.first_step,
+      self.addresses)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+enum LoopDetectionResult {
+  Halted(Vec<InstructionType>),
+  NeedInput(Vec<InstructionType>),
+  Loop(DetectedLoop),
+  /// neither halted nor proven to loop within the step limit
+  Undecided,
+}
+
+// everything which determines how the machine continues, the machine has no
+// relative base, and the inputs are only ever consumed from the front
+#[derive(Hash, PartialEq)]
+struct MachineState {
+  counter: usize,
+  memory: Vec<InstructionType>,
+  inputs: VecDeque<InstructionType>,
+}
+
+fn snapshot(program_instance: &ProgramInstance) -> MachineState {
+  let context = program_instance.context.borrow();
+  let inputs = context.inputs.borrow().clone().unwrap_or_default();
+  MachineState {
+    counter: context.counter.get(),
+    memory: program_instance.get_program().instructions.clone(),
+    inputs,
+  }
+}
+
+/// Run the program and try to prove that it is stuck, by looking for a
+/// machine state which repeats exactly.
+pub
+fn detect_infinite_loop(
+  program: &Program,
+  inputs: &[InstructionType],
+  checkpoint: LoopCheckpoint,
+  max_steps: usize,
+) -> LoopDetectionResult
+{
+  let mut program_instance = ProgramInstance::new(program.clone());
+  *program_instance.context.borrow_mut().inputs.borrow_mut() = Some(inputs.iter().cloned().collect());
+  // states are compared in full when their hashes match, so a collision
+  // cannot produce a false positive
+  let mut seen: HashMap<u64, Vec<(usize, MachineState)>> = HashMap::new();
+  let mut outputs = Vec::new();
+  for step in 0..max_steps {
+    let counter = program_instance.context.borrow().counter.get();
+    let next_counter = match program_instance.step() {
+      Ok((next_counter, output)) => {
+        outputs.extend(output);
+        next_counter
+      },
+      Err(StepError::EndOfProgram) => return LoopDetectionResult::Halted(outputs),
+      Err(StepError::NeedInput) => return LoopDetectionResult::NeedInput(outputs),
+      Err(StepError::Error(msg)) => panic!("unexpected error: {}", msg),
+      Err(StepError::LimitExceeded(limit)) => panic!("unexpected stop by {}", limit),
+    };
+    program_instance.context.borrow_mut().counter.set(next_counter);
+    let is_checkpoint = match checkpoint {
+      LoopCheckpoint::Interval(interval) => (step + 1) % interval.max(1) == 0,
+      LoopCheckpoint::BackwardJumps => next_counter <= counter,
+    };
+    if !is_checkpoint {
+      continue;
+    }
+    let state = snapshot(&program_instance);
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    let states = seen.entry(hasher.finish()).or_default();
+    if let Some((first_step, _)) = states.iter().find(|(_, x)| *x == state) {
+      let first_step = *first_step;
+      let (length, addresses) = follow_loop(&mut program_instance, &state);
+      return LoopDetectionResult::Loop(DetectedLoop { entry: state.counter, first_step, length, addresses })
+    }
+    states.push((step + 1, state));
+  }
+  LoopDetectionResult::Undecided
+}
+
+// run once more around the loop, to find its shortest period, since the
+// checkpoints may be several periods apart, and the instructions in it
+fn follow_loop(program_instance: &mut ProgramInstance, entry_state: &MachineState) -> (usize, Vec<usize>) {
+  let mut addresses = BTreeSet::new();
+  let mut length = 0;
+  loop {
+    let counter = program_instance.context.borrow().counter.get();
+    addresses.insert(counter);
+    let (next_counter, _) = program_instance.step().unwrap();
+    program_instance.context.borrow_mut().counter.set(next_counter);
+    length += 1;
+    if snapshot(program_instance) == *entry_state {
+      return (length, addresses.into_iter().collect())
+    }
+  }
+}
+
+#[cfg(test)]
+mod loop_detection_tests {
+  use super::*;
+
+  #[test]
+  fn detects_self_jump() {
+    let program = Program::from_string("1105,1,0");
+    let result = detect_infinite_loop(&program, &[], LoopCheckpoint::BackwardJumps, 100);
+    assert_eq!(result, LoopDetectionResult::Loop(DetectedLoop {
+      entry: 0,
+      first_step: 1,
+      length: 1,
+      addresses: vec![0],
+    }));
+  }
+
+  #[test]
+  fn detects_loop_on_wrong_input() {
+    // spins at 15 unless the input is 5
+    let program = Program::from_string("3,13,1008,13,5,14,1006,14,15,104,1,99,0,0,0,1105,1,15");
+    assert_eq!(
+      detect_infinite_loop(&program, &[5], LoopCheckpoint::Interval(1), 100),
+      LoopDetectionResult::Halted(vec![1]));
+    match detect_infinite_loop(&program, &[4], LoopCheckpoint::Interval(3), 100) {
+      LoopDetectionResult::Loop(detected) => {
+        assert_eq!(detected.length, 1);
+        assert_eq!(detected.addresses, vec![15]);
+      },
+      x => panic!("expected a loop, got {:?}", x),
+    }
+  }
+
+  #[test]
+  fn counting_loop_is_not_a_cycle() {
+    // count down from 5 and halt, every state is different
+    let program = Program::from_string("101,-1,10,10,1005,10,0,104,7,99,5");
+    assert_eq!(
+      detect_infinite_loop(&program, &[], LoopCheckpoint::BackwardJumps, 1000),
+      LoopDetectionResult::Halted(vec![7]));
+  }
+
+  #[test]
+  fn undecided_within_step_limit() {
+    // counts up forever, so no state repeats
+    let program = Program::from_string("101,1,5,5,1105,1,0");
+    assert_eq!(
+      detect_infinite_loop(&program, &[], LoopCheckpoint::BackwardJumps, 1000),
+      LoopDetectionResult::Undecided);
+  }
+
+  #[test]
+  fn report_loop() {
+    let detected = DetectedLoop { entry: 4, first_step: 10, length: 2, addresses: vec![4, 7] };
+    assert_eq!(
+      detected.to_string(),
+      "infinite loop of '2' steps entered at '4' on step '10', through addresses [4, 7]");
+  }
+}
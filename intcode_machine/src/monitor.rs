@@ -0,0 +1,340 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::panic;
+use std::rc::Rc;
+
+use super::disassembler::decode_instruction;
+use super::fuzzer::panic_message;
+use super::instruction_type::InstructionType;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+use super::trace::TraceEvent;
+use super::trace::Tracer;
+
+/// How many of the latest writes are highlighted.
+const RECENT_WRITES: usize = 8;
+
+/// Values per row of the memory pane.
+const MEMORY_COLUMNS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+enum MonitorStatus {
+  Paused,
+  Running,
+  WaitingForInput,
+  Halted,
+  Faulted(String),
+}
+
+// keeps the addresses written during a step
+#[derive(Debug, Clone, Default)]
+struct WriteRecorder {
+  writes: Rc<RefCell<Vec<usize>>>,
+}
+
+impl Tracer for WriteRecorder {
+  fn event(&mut self, _step: usize, event: &TraceEvent) {
+    if let TraceEvent::Write { address, .. } = event {
+      self.writes.borrow_mut().push(*address);
+    }
+  }
+}
+
+/// A program run one step at a time, with what is needed to watch it: the
+/// outputs so far and the latest writes, and a text screen of it all.
+#[derive(Debug)]
+pub
+struct Monitor<'a> {
+  program_instance: ProgramInstance<'a>,
+  writes: WriteRecorder,
+  /// the addresses written most recently, latest last
+  pub recent_writes: VecDeque<usize>,
+  pub outputs: Vec<InstructionType>,
+  pub status: MonitorStatus,
+  /// show memory in hexadecimal rather than decimal
+  pub hex: bool,
+}
+
+impl<'a> Monitor<'a> {
+  pub
+  fn new(program: Program) -> Monitor<'a> {
+    let program_instance = ProgramInstance::new(program);
+    let writes = WriteRecorder::default();
+    {
+      let context = program_instance.context.borrow();
+      *context.inputs.borrow_mut() = Some(VecDeque::new());
+      *context.tracer.borrow_mut() = Some(Box::new(writes.clone()));
+    }
+    Monitor {
+      program_instance,
+      writes,
+      recent_writes: VecDeque::new(),
+      outputs: Vec::new(),
+      status: MonitorStatus::Paused,
+      hex: false,
+    }
+  }
+
+  pub
+  fn program_instance(&self) -> &ProgramInstance<'a> {
+    &self.program_instance
+  }
+
+  pub
+  fn counter(&self) -> usize {
+    self.program_instance.context.borrow().counter.get()
+  }
+
+  pub
+  fn steps(&self) -> usize {
+    self.program_instance.context.borrow().steps.get()
+  }
+
+  /// The inputs given but not read yet.
+  pub
+  fn inputs(&self) -> Vec<InstructionType> {
+    let context = self.program_instance.context.borrow();
+    let inputs = context.inputs.borrow();
+    inputs.iter().flatten().copied().collect()
+  }
+
+  pub
+  fn push_input(&mut self, value: InstructionType) {
+    self.program_instance.push_input(value);
+    if self.status == MonitorStatus::WaitingForInput {
+      self.status = MonitorStatus::Paused;
+    }
+  }
+
+  fn is_stopped(&self) -> bool {
+    matches!(self.status, MonitorStatus::Halted | MonitorStatus::Faulted(_))
+  }
+
+  /// Execute one instruction, unless the program has stopped, returning
+  /// whether it did. Faults are caught and kept in the status.
+  pub
+  fn step(&mut self) -> bool {
+    if self.is_stopped() {
+      return false
+    }
+    let program_instance = &mut self.program_instance;
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| program_instance.step()));
+    for address in self.writes.writes.borrow_mut().drain(..) {
+      self.recent_writes.retain(|x| *x != address);
+      self.recent_writes.push_back(address);
+      if self.recent_writes.len() > RECENT_WRITES {
+        self.recent_writes.pop_front();
+      }
+    }
+    match result {
+      Ok(Ok((next_counter, output))) => {
+        self.program_instance.context.borrow().counter.set(next_counter);
+        self.outputs.extend(output);
+        if self.status == MonitorStatus::WaitingForInput {
+          self.status = MonitorStatus::Paused;
+        }
+        true
+      },
+      Ok(Err(StepError::NeedInput)) => {
+        self.status = MonitorStatus::WaitingForInput;
+        false
+      },
+      Ok(Err(StepError::EndOfProgram)) => {
+        self.status = MonitorStatus::Halted;
+        false
+      },
+      Ok(Err(StepError::Error(message))) => {
+        self.status = MonitorStatus::Faulted(message.to_string());
+        false
+      },
+      Ok(Err(StepError::LimitExceeded(limit))) => {
+        self.status = MonitorStatus::Faulted(format!("stopped by {}", limit));
+        false
+      },
+      Err(payload) => {
+        self.status = MonitorStatus::Faulted(panic_message(&*payload));
+        false
+      },
+    }
+  }
+
+  /// Run at most `max_steps` instructions, stopping early if the program
+  /// needs input, halts or faults. It is still running afterwards unless it
+  /// stopped.
+  pub
+  fn run(&mut self, max_steps: usize) {
+    self.status = MonitorStatus::Running;
+    for _ in 0..max_steps {
+      if !self.step() {
+        return
+      }
+    }
+  }
+
+  pub
+  fn pause(&mut self) {
+    if self.status == MonitorStatus::Running {
+      self.status = MonitorStatus::Paused;
+    }
+  }
+
+  fn format_value(&self, value: InstructionType) -> String {
+    match (self.hex, value < 0) {
+      (false, _) => value.to_string(),
+      (true, false) => format!("{:#x}", value),
+      (true, true) => format!("-{:#x}", value.unsigned_abs()),
+    }
+  }
+
+  // the instructions around the counter, decoding from 0 up to it to find
+  // where the earlier ones start
+  fn disassembly(&self, lines: usize) -> Vec<String> {
+    let memory = &self.program_instance.get_program().instructions;
+    let counter = self.counter();
+    let mut starts = Vec::new();
+    let mut address = 0;
+    while address < counter {
+      starts.push(address);
+      address = decode_instruction(memory, address).map_or(address + 1, |x| x.next_address());
+    }
+    // the counter may be in the middle of what was decoded
+    starts.retain(|x| *x < counter);
+    let before = lines / 2;
+    let mut shown: Vec<usize> = starts[starts.len().saturating_sub(before)..].to_vec();
+    let mut address = counter;
+    while shown.len() < lines && address < memory.len() {
+      shown.push(address);
+      address = decode_instruction(memory, address).map_or(address + 1, |x| x.next_address());
+    }
+    shown.iter().map(|address| {
+      let marker = if *address == counter { '>' } else { ' ' };
+      match decode_instruction(memory, *address) {
+        Some(instruction) => {
+          let values: Vec<String> = memory[*address..instruction.next_address()].iter().map(|x| x.to_string()).collect();
+          format!("{} {:5}  {:<20} {}", marker, address, values.join(","), instruction)
+        },
+        None => format!("{} {:5}  {:<20} ?", marker, address, memory[*address]),
+      }
+    }).collect()
+  }
+
+  // rows of memory around the counter, with the latest writes in brackets
+  fn memory_rows(&self, lines: usize) -> Vec<String> {
+    let memory = &self.program_instance.get_program().instructions;
+    let rows = memory.len().div_ceil(MEMORY_COLUMNS);
+    let first = (self.counter() / MEMORY_COLUMNS).saturating_sub(lines / 3).min(rows.saturating_sub(lines));
+    (first..rows.min(first + lines)).map(|row| {
+      let start = row * MEMORY_COLUMNS;
+      let values: Vec<String> = memory[start..memory.len().min(start + MEMORY_COLUMNS)].iter().enumerate()
+        .map(|(offset, value)| {
+          let value = self.format_value(*value);
+          if self.recent_writes.contains(&(start + offset)) {
+            format!("[{}]", value)
+          } else {
+            format!(" {} ", value)
+          }
+        })
+        .collect();
+      format!("{:5}: {}", start, values.join(""))
+    }).collect()
+  }
+
+  /// The screen as text, `height` lines of at most `width` characters: the
+  /// status, the instructions and memory around the counter, the inputs
+  /// not read yet, the latest outputs and the keys.
+  pub
+  fn render(&self, width: usize, height: usize) -> String {
+    let status = match &self.status {
+      MonitorStatus::Paused => "paused".to_string(),
+      MonitorStatus::Running => "running".to_string(),
+      MonitorStatus::WaitingForInput => "waiting for input".to_string(),
+      MonitorStatus::Halted => "halted".to_string(),
+      MonitorStatus::Faulted(message) => format!("faulted: {}", message),
+    };
+    // the lines besides the panes
+    let panes = height.saturating_sub(6);
+    let disassembly_lines = (panes * 2 / 5).max(1);
+    let memory_lines = (panes * 2 / 5).max(1);
+    let output_lines = panes.saturating_sub(disassembly_lines + memory_lines).max(1);
+    let mut lines = vec![format!("pc {}  steps {}  {}", self.counter(), self.steps(), status)];
+    lines.push("-- disassembly".to_string());
+    lines.extend(self.disassembly(disassembly_lines));
+    lines.resize(2 + disassembly_lines, String::new());
+    lines.push(format!("-- memory ({})", if self.hex { "hex" } else { "decimal" }));
+    lines.extend(self.memory_rows(memory_lines));
+    lines.resize(3 + disassembly_lines + memory_lines, String::new());
+    let inputs: Vec<String> = self.inputs().iter().map(|x| x.to_string()).collect();
+    lines.push(format!("-- inputs: {}", inputs.join(" ")));
+    lines.push(format!("-- outputs ({})", self.outputs.len()));
+    lines.extend(self.outputs[self.outputs.len().saturating_sub(output_lines)..].iter().map(|x| x.to_string()));
+    lines.resize(5 + disassembly_lines + memory_lines + output_lines, String::new());
+    lines.push("keys: s [N] step, r run, p pause, i VALUE... input, x hex, q quit".to_string());
+    lines.truncate(height);
+    let mut screen = String::new();
+    for line in lines {
+      screen.extend(line.trim_end().chars().take(width));
+      screen.push('\n');
+    }
+    screen
+  }
+}
+
+#[cfg(test)]
+mod monitor_tests {
+  use super::*;
+
+  // adds the input to 7 and prints it
+  const ADDER: &str = "3,9,1001,9,7,9,4,9,99,0";
+
+  #[test]
+  fn steps_through_input_and_output() {
+    let mut monitor = Monitor::new(Program::from_string(ADDER));
+    assert!(!monitor.step());
+    assert_eq!(monitor.status, MonitorStatus::WaitingForInput);
+    monitor.push_input(5);
+    assert_eq!(monitor.inputs(), vec![5]);
+    monitor.run(100);
+    assert_eq!(monitor.status, MonitorStatus::Halted);
+    assert_eq!(monitor.outputs, vec![12]);
+    assert_eq!(monitor.recent_writes, vec![9]);
+    assert!(!monitor.step());
+  }
+
+  #[test]
+  fn catches_faults() {
+    let mut monitor = Monitor::new(Program::from_string("1105,1,50,99"));
+    monitor.run(10);
+    assert!(matches!(&monitor.status, MonitorStatus::Faulted(message) if message.contains("out of range")));
+  }
+
+  #[test]
+  fn renders_the_screen() {
+    let mut monitor = Monitor::new(Program::from_string(ADDER));
+    monitor.push_input(-1);
+    monitor.step();
+    monitor.step();
+    assert_eq!(monitor.render(60, 17), "\
+pc 6  steps 2  paused
+-- disassembly
+      0  3,9                  INPUT [9]
+      2  1001,9,7,9           ADD [9], 7, [9]
+>     6  4,9                  PRINT [9]
+      8  99                   HALT
+-- memory (decimal)
+    0:  3  9  1001  9  7  9  4  9
+    8:  99 [6]
+
+
+-- inputs:
+-- outputs (0)
+
+
+
+keys: s [N] step, r run, p pause, i VALUE... input, x hex, q
+");
+    monitor.hex = true;
+    assert!(monitor.render(60, 17).contains("    8:  0x63 [0x6]"));
+  }
+}
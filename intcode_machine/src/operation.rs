@@ -1,31 +1,35 @@
-use super::instruction_type::InstructionType;
 use super::operation_instance::OperationInstance;
-use super::operations::OPERATIONS;
 use super::parameter_mode::ParameterMode;
 use super::program::ProgramContext;
-use super::program::StepError;
+use super::program::StepResult;
 
+/// What an operation does with one of its parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum ParameterKind {
+  /// a value, read from memory in position mode
+  Read,
+  /// the address the result is written to
+  Write,
+}
+
+#[derive(Clone, Copy)]
 pub
 struct Operation {
   pub name: &'static str,
   pub size: usize,
+  /// one for each value after the opcode
+  pub parameters: &'static [ParameterKind],
+  /// whether it prints a value, so the output limit stops before it
+  pub prints: bool,
   pub execute: fn(
     program: &mut Vec<i32>,
     program_context: &ProgramContext,
-    op_instance: &OperationInstance) -> Result<(usize, Option<InstructionType>), StepError>,
+    op_instance: &OperationInstance) -> StepResult,
 }
 
 pub
-fn validate_operation(program: &Vec<i32>, counter: usize, op_instance: &OperationInstance) -> usize {
-  if !OPERATIONS.contains_key(&op_instance.opcode) {
-    let mut keys = Vec::new();
-    for key in OPERATIONS.keys() {
-      keys.push(*key);
-    }
-    keys.sort();
-    panic!("invalid opcode key '{}', expected on of: {:?}", op_instance.opcode, keys);
-  }
-  let operation: &Operation = &OPERATIONS[&op_instance.opcode];
+fn validate_operation(program: &[i32], counter: usize, operation: &Operation, op_instance: &OperationInstance) -> usize {
   if !(1..=4).contains(&operation.size) {
     panic!("expected operation size to be in range [1..4], but found '{}'", operation.size);
   }
@@ -1,7 +1,7 @@
 use super::parameter_mode::ParameterMode;
 use super::instruction_type::InstructionType;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub
 struct OperationInstance {
   pub opcode: InstructionType,
@@ -71,15 +71,29 @@ fn parse_operation_intcode(operation_intcode: InstructionType) -> OperationInsta
   }
 }
 
+/// Like parse_operation_intcode, but returns None rather than panicking.
+pub
+fn try_parse_operation_intcode(operation_intcode: InstructionType) -> Option<OperationInstance> {
+  if !(0..=99_999).contains(&operation_intcode) {
+    return None
+  }
+  let digits = split_into_five_digits_right_to_left(operation_intcode);
+  if digits[2..].iter().any(|digit| !(0..=1).contains(digit)) {
+    return None
+  }
+  Some(parse_operation_intcode(operation_intcode))
+}
+
 #[cfg(test)]
 mod parse_operation_intcode_tests {
   use super::OperationInstance;
   use super::ParameterMode;
   use super::parse_operation_intcode;
+  use super::try_parse_operation_intcode;
 
   #[test]
   fn test_parse_operation_intcode() {
-    assert_eq!(parse_operation_intcode(01001), OperationInstance {
+    assert_eq!(parse_operation_intcode(1001), OperationInstance {
       opcode: 1,
       parameter1_mode: ParameterMode::PositionMode,
       parameter2_mode: ParameterMode::ImmediateMode,
@@ -93,6 +107,19 @@ mod parse_operation_intcode_tests {
     });
   }
 
+  #[test]
+  fn test_try_parse_operation_intcode() {
+    assert_eq!(try_parse_operation_intcode(1101), Some(OperationInstance {
+      opcode: 1,
+      parameter1_mode: ParameterMode::ImmediateMode,
+      parameter2_mode: ParameterMode::ImmediateMode,
+      parameter3_mode: ParameterMode::PositionMode,
+    }));
+    assert_eq!(try_parse_operation_intcode(-1), None);
+    assert_eq!(try_parse_operation_intcode(11201), None);
+    assert_eq!(try_parse_operation_intcode(100_000), None);
+  }
+
   #[test]
   #[should_panic(expected = "expected parameter mode for parameter 1 to be 0 or 1")]
   fn test_invalid_parameter_mode_for_1() {
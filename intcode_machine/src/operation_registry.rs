@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use super::instruction_type::InstructionType;
+use super::operation::Operation;
+use super::operations::STANDARD_OPERATIONS;
+
+/// The operations a machine is built from, by opcode.
+///
+/// Starting from the standard set, operations can be added or overridden,
+/// for example to try out an experimental opcode.
+#[derive(Clone)]
+pub
+struct OperationRegistry {
+  operations: BTreeMap<InstructionType, Operation>,
+}
+
+impl OperationRegistry {
+  pub
+  fn empty() -> OperationRegistry {
+    OperationRegistry { operations: BTreeMap::new() }
+  }
+
+  pub
+  fn standard() -> OperationRegistry {
+    standard_registry().clone()
+  }
+
+  /// Add the operation, and return the one it replaces, if any.
+  pub
+  fn register(&mut self, opcode: InstructionType, operation: Operation) -> Option<Operation> {
+    if !(0..=99).contains(&opcode) {
+      panic!("expected an opcode of at most two digits, got '{}' for {}", opcode, operation.name);
+    }
+    if !(1..=4).contains(&operation.size) || operation.size != operation.parameters.len() + 1 {
+      panic!(
+        "expected operation size to be in range [1..4] and one more than its {} parameters, but found '{}' for {}",
+        operation.parameters.len(),
+        operation.size,
+        operation.name);
+    }
+    self.operations.insert(opcode, operation)
+  }
+
+  pub
+  fn remove(&mut self, opcode: InstructionType) -> Option<Operation> {
+    self.operations.remove(&opcode)
+  }
+
+  pub
+  fn get(&self, opcode: InstructionType) -> Option<&Operation> {
+    self.operations.get(&opcode)
+  }
+
+  pub
+  fn opcodes(&self) -> Vec<InstructionType> {
+    self.operations.keys().cloned().collect()
+  }
+}
+
+impl fmt::Display for OperationRegistry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let operations: Vec<String> = self.operations.iter()
+      .map(|(opcode, operation)| format!("{} {}", opcode, operation.name))
+      .collect();
+    write!(f, "[{}]", operations.join(", "))
+  }
+}
+
+impl fmt::Debug for OperationRegistry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "OperationRegistry {}", self)
+  }
+}
+
+/// The standard operations, shared by every machine not given others.
+pub
+fn standard_registry() -> &'static OperationRegistry {
+  static STANDARD: OnceLock<OperationRegistry> = OnceLock::new();
+  STANDARD.get_or_init(|| {
+    let mut registry = OperationRegistry::empty();
+    for (opcode, operation) in STANDARD_OPERATIONS.iter() {
+      registry.register(*opcode, *operation);
+    }
+    registry
+  })
+}
+
+#[cfg(test)]
+mod operation_registry_tests {
+  use super::*;
+  use std::rc::Rc;
+  use crate::operation::ParameterKind;
+  use crate::operation_instance::OperationInstance;
+  use crate::operations::get_parameter_value;
+  use crate::operations::set_value;
+  use crate::program::Program;
+  use crate::program::ProgramContext;
+  use crate::program::StepResult;
+  use crate::run_intcode_program::run_intcode_program_with_operations;
+
+  static DOUBLE: Operation = Operation {
+    name: "DOUBLE",
+    size: 2,
+    parameters: &[ParameterKind::Write],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let value = get_parameter_value(program, program_context, op_instance, 1);
+      let dst = program[program_context.counter.get() + 1];
+      set_value(program, program_context, dst, value * 2);
+      Ok((program_context.counter.get() + 2, None))
+    },
+  };
+
+  fn with_double() -> Rc<OperationRegistry> {
+    let mut registry = OperationRegistry::standard();
+    assert!(registry.register(9, DOUBLE).is_none());
+    Rc::new(registry)
+  }
+
+  #[test]
+  fn runs_added_operation() {
+    let mut program = Program::from_string("9,7,9,7,4,7,99,21");
+    assert_eq!(run_intcode_program_with_operations(&mut program, &Some(vec![]), with_double()), vec![84]);
+  }
+
+  #[test]
+  fn overrides_standard_operation() {
+    let mut registry = OperationRegistry::standard();
+    let replaced = registry.register(4, Operation { name: "PRINT-DOUBLE", ..DOUBLE });
+    assert_eq!(replaced.unwrap().name, "PRINT");
+    let mut program = Program::from_string("4,3,99,5");
+    assert_eq!(run_intcode_program_with_operations(&mut program, &Some(vec![]), Rc::new(registry)), vec![]);
+    assert_eq!(program.instructions[3], 10);
+  }
+
+  #[test]
+  #[should_panic(expected = "expected one of these: [1 ADD, 2 MULTIPLY, 3 INPUT, 4 PRINT, 5 JUMP-IF-TRUE, \
+    6 JUMP-IF-FALSE, 7 LESS-THAN, 8 EQUALS, 9 DOUBLE, 99 HALT]")]
+  fn unknown_opcode_lists_active_operations() {
+    let mut program = Program::from_string("10,0,99");
+    run_intcode_program_with_operations(&mut program, &Some(vec![]), with_double());
+  }
+
+  #[test]
+  #[should_panic(expected = "one more than its 1 parameters")]
+  fn rejects_inconsistent_size() {
+    OperationRegistry::empty().register(9, Operation { size: 3, ..DOUBLE });
+  }
+}
@@ -1,54 +1,52 @@
 use std::io;
 
-use phf::phf_map;
-
 use super::instruction_type::InstructionType;
 use super::operation::Operation;
-use super::operation::validate_operation;
+use super::operation::ParameterKind;
 use super::operation_instance::OperationInstance;
 use super::parameter_mode::ParameterMode;
 use super::program::ProgramContext;
 use super::program::StepError;
+use super::program::StepResult;
+use super::trace::TraceEvent;
 
+/// The standard operations, by opcode.
 pub
-static OPERATIONS: phf::Map<InstructionType, Operation> = phf_map! {
-  01i32 => Operation {
+static STANDARD_OPERATIONS: [(InstructionType, Operation); 9] = [
+  (1, Operation {
     name: "ADD",
     size: 4,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let op1 = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      let op2 = get_parameter_value(program, program_context.counter.get(), op_instance, 2);
+    parameters: &[ParameterKind::Read, ParameterKind::Read, ParameterKind::Write],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let op1 = get_parameter_value(program, program_context, op_instance, 1);
+      let op2 = get_parameter_value(program, program_context, op_instance, 2);
       assert!(op_instance.parameter3_mode != ParameterMode::ImmediateMode);
       let dst = program[program_context.counter.get() + 3];
-      program[dst as usize] = op1 + op2;
-      if program_context.trace {
-        println!("  ADD: added '{}' + '{}' = '{}', stored in '{}'", op1, op2, op1 + op2, dst);
-      }
-      Ok((program_context.counter.get() + size, None))
+      set_value(program, program_context, dst, op1 + op2);
+      Ok((program_context.counter.get() + 4, None))
     },
-  },
-  02i32 => Operation {
+  }),
+  (2, Operation {
     name: "MULTIPLY",
     size: 4,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let op1 = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      let op2 = get_parameter_value(program, program_context.counter.get(), op_instance, 2);
+    parameters: &[ParameterKind::Read, ParameterKind::Read, ParameterKind::Write],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let op1 = get_parameter_value(program, program_context, op_instance, 1);
+      let op2 = get_parameter_value(program, program_context, op_instance, 2);
       assert!(op_instance.parameter3_mode != ParameterMode::ImmediateMode);
       let dst = program[program_context.counter.get() + 3];
-      program[dst as usize] = op1 * op2;
-      if program_context.trace {
-        println!("  MULTIPLY: added '{}' * '{}' = '{}', stored in '{}'", op1, op2, op1 * op2, dst);
-      }
-      Ok((program_context.counter.get() + size, None))
+      set_value(program, program_context, dst, op1 * op2);
+      Ok((program_context.counter.get() + 4, None))
     },
-  },
-  03i32 => Operation {
+  }),
+  (3, Operation {
     name: "INPUT",
     size: 2,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
+    parameters: &[ParameterKind::Write],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
       assert!(op_instance.parameter1_mode != ParameterMode::ImmediateMode);
       let dst = program[program_context.counter.get() + 1];
       let input: i32 = match &mut *program_context.inputs.borrow_mut() {
@@ -56,7 +54,7 @@ static OPERATIONS: phf::Map<InstructionType, Operation> = phf_map! {
           if i.is_empty() {
             return Err(StepError::NeedInput)
           }
-          i.remove(0)
+          i.pop_front().unwrap()
         },
         None => {
           let mut input = String::new();
@@ -65,112 +63,123 @@ static OPERATIONS: phf::Map<InstructionType, Operation> = phf_map! {
           input.parse::<i32>().unwrap()
         },
       };
-      program[dst as usize] = input;
-      if program_context.trace {
-        println!("  INPUT: got '{}' and stored it at '{}'", input, dst);
-      }
-      Ok((program_context.counter.get() + size, None))
+      program_context.trace(|| TraceEvent::Input { value: input });
+      set_value(program, program_context, dst, input);
+      Ok((program_context.counter.get() + 2, None))
     },
-  },
-  04i32 => Operation {
+  }),
+  (4, Operation {
     name: "PRINT",
     size: 2,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let value_to_print = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      if program_context.trace {
-        println!("  PRINT: printing '{}'", value_to_print);
-      }
-      Ok((program_context.counter.get() + size, Some(value_to_print)))
+    parameters: &[ParameterKind::Read],
+    prints: true,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let value_to_print = get_parameter_value(program, program_context, op_instance, 1);
+      program_context.trace(|| TraceEvent::Output { value: value_to_print });
+      Ok((program_context.counter.get() + 2, Some(value_to_print)))
     },
-  },
-  05i32 => Operation {
+  }),
+  (5, Operation {
     name: "JUMP-IF-TRUE",
     size: 3,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let test = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      let dst_raw = get_parameter_value(program, program_context.counter.get(), op_instance, 2);
-      if program_context.trace {
-        println!("  JUMP-IF-TRUE: jumping to '{}' if '{}' is not '0': {}", dst_raw, test, test != 0);
-      }
+    parameters: &[ParameterKind::Read, ParameterKind::Read],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let test = get_parameter_value(program, program_context, op_instance, 1);
+      let dst_raw = get_parameter_value(program, program_context, op_instance, 2);
       if test != 0 {
-        let dst: usize = validate_jump_destination(dst_raw, &program);
+        let dst: usize = validate_jump_destination(dst_raw, program);
+        program_context.trace(|| TraceEvent::Jump {
+          address: program_context.counter.get(),
+          destination: dst,
+          taken: true,
+        });
         return Ok((dst, None))
       }
-      Ok((program_context.counter.get() + size, None))
+      program_context.trace(|| TraceEvent::Jump {
+        address: program_context.counter.get(),
+        destination: program_context.counter.get() + 3,
+        taken: false,
+      });
+      Ok((program_context.counter.get() + 3, None))
     },
-  },
-  06i32 => Operation {
+  }),
+  (6, Operation {
     name: "JUMP-IF-FALSE",
     size: 3,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let test = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      let dst_raw = get_parameter_value(program, program_context.counter.get(), op_instance, 2);
-      if program_context.trace {
-        println!("  JUMP-IF-FALSE: jumping to '{}' if '{}' is '0': {}", dst_raw, test, test == 0);
-      }
+    parameters: &[ParameterKind::Read, ParameterKind::Read],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let test = get_parameter_value(program, program_context, op_instance, 1);
+      let dst_raw = get_parameter_value(program, program_context, op_instance, 2);
       if test == 0 {
-        let dst: usize = validate_jump_destination(dst_raw, &program);
+        let dst: usize = validate_jump_destination(dst_raw, program);
+        program_context.trace(|| TraceEvent::Jump {
+          address: program_context.counter.get(),
+          destination: dst,
+          taken: true,
+        });
         return Ok((dst, None))
       }
-      Ok((program_context.counter.get() + size, None))
+      program_context.trace(|| TraceEvent::Jump {
+        address: program_context.counter.get(),
+        destination: program_context.counter.get() + 3,
+        taken: false,
+      });
+      Ok((program_context.counter.get() + 3, None))
     },
-  },
-  07i32 => Operation {
+  }),
+  (7, Operation {
     name: "LESS-THAN",
     size: 4,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let op1 = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      let op2 = get_parameter_value(program, program_context.counter.get(), op_instance, 2);
+    parameters: &[ParameterKind::Read, ParameterKind::Read, ParameterKind::Write],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let op1 = get_parameter_value(program, program_context, op_instance, 1);
+      let op2 = get_parameter_value(program, program_context, op_instance, 2);
       assert!(op_instance.parameter3_mode != ParameterMode::ImmediateMode);
       let dst = program[program_context.counter.get() + 3];
-      if op1 < op2 {
-        program[dst as usize] = 1;
-      } else {
-        program[dst as usize] = 0;
-      }
-      if program_context.trace {
-        println!("  LESS-THAN: assigning '1' to '{}' if '{}' < '{}': {}", dst, op1, op2, op1 < op2);
-      }
-      Ok((program_context.counter.get() + size, None))
+      set_value(program, program_context, dst, if op1 < op2 { 1 } else { 0 });
+      Ok((program_context.counter.get() + 4, None))
     },
-  },
-  08i32 => Operation {
+  }),
+  (8, Operation {
     name: "EQUALS",
     size: 4,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      let size = validate_operation(program, program_context.counter.get(), op_instance);
-      let op1 = get_parameter_value(program, program_context.counter.get(), op_instance, 1);
-      let op2 = get_parameter_value(program, program_context.counter.get(), op_instance, 2);
+    parameters: &[ParameterKind::Read, ParameterKind::Read, ParameterKind::Write],
+    prints: false,
+    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> StepResult {
+      let op1 = get_parameter_value(program, program_context, op_instance, 1);
+      let op2 = get_parameter_value(program, program_context, op_instance, 2);
       assert!(op_instance.parameter3_mode != ParameterMode::ImmediateMode);
       let dst = program[program_context.counter.get() + 3];
-      if op1 == op2 {
-        program[dst as usize] = 1;
-      } else {
-        program[dst as usize] = 0;
-      }
-      if program_context.trace {
-        println!("  EQUALS: assigning '1' to '{}' if '{}' == '{}': {}", dst, op1, op2, op1 == op2);
-      }
-      Ok((program_context.counter.get() + size, None))
+      set_value(program, program_context, dst, if op1 == op2 { 1 } else { 0 });
+      Ok((program_context.counter.get() + 4, None))
     },
-  },
-  99i32 => Operation {
+  }),
+  (99, Operation {
     name: "HALT",
     size: 1,
-    execute: |program: &mut Vec<i32>, program_context: &ProgramContext, op_instance: &OperationInstance| -> Result<(usize, Option<InstructionType>), StepError> {
-      if program_context.trace {
-        println!("  HALT");
-      }
+    parameters: &[],
+    prints: false,
+    execute: |_program: &mut Vec<i32>, program_context: &ProgramContext, _op_instance: &OperationInstance| -> StepResult {
+      program_context.trace(|| TraceEvent::Halt { address: program_context.counter.get() });
       Err(StepError::EndOfProgram)
     },
-  },
-};
+  }),
+];
 
-fn get_parameter_value(program: &Vec<i32>, counter: usize, op_instance: &OperationInstance, parameter_index: usize) -> i32 {
+/// Read a parameter of the instruction at the counter, following it to an
+/// address in position mode.
+pub
+fn get_parameter_value(
+  program: &[i32],
+  program_context: &ProgramContext,
+  op_instance: &OperationInstance,
+  parameter_index: usize,
+) -> i32
+{
+  let counter = program_context.counter.get();
   let mode = match parameter_index {
     1 => {
       &op_instance.parameter1_mode
@@ -187,16 +196,33 @@ fn get_parameter_value(program: &Vec<i32>, counter: usize, op_instance: &Operati
   };
   if *mode == ParameterMode::PositionMode {
     let address = program[counter + parameter_index] as usize;
+    if let Some(value) = program_context.devices.read(address, program_context.steps.get()) {
+      program_context.trace(|| TraceEvent::Read { address, value });
+      return value
+    }
     if address > program.len() - 1 {
       panic!("address out of bounds");
     }
-    return program[address]
+    program_context.trace(|| TraceEvent::Read { address, value: program[address] });
+    program[address]
   } else {
-    return program[counter + parameter_index]
+    program[counter + parameter_index]
+  }
+}
+
+/// Write to memory, forgetting any instruction decoded from the address, or
+/// to the device attached there.
+pub
+fn set_value(program: &mut [i32], program_context: &ProgramContext, address: i32, value: i32) {
+  program_context.trace(|| TraceEvent::Write { address: address as usize, value });
+  if program_context.devices.write(address as usize, value, program_context.steps.get()) {
+    return
   }
+  program[address as usize] = value;
+  program_context.instruction_cache.borrow_mut().invalidate(address as usize);
 }
 
-fn validate_jump_destination(jump_destination: i32, program: & Vec<i32>,) -> usize {
+fn validate_jump_destination(jump_destination: i32, program: &[i32]) -> usize {
   if jump_destination < 0 {
     panic!("invalid jump operation, negative destination '{}'", jump_destination);
   }
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use super::instruction_type::InstructionType;
+use super::program::Interrupt;
+use super::program::Program;
+use super::program::ProgramInstance;
+
+/// A program which answers one query per run, such as whether a point is
+/// in a beam, reset to its pristine image before each run.
+///
+/// Answers are cached, so asking again is free, and resetting only restores
+/// the addresses the last run changed.
+#[derive(Debug)]
+pub
+struct Probe {
+  image: Vec<InstructionType>,
+  program_instance: ProgramInstance<'static>,
+  answers: HashMap<Vec<InstructionType>, Vec<InstructionType>>,
+  /// how many times the program has actually been run
+  pub runs: usize,
+}
+
+impl Probe {
+  pub
+  fn new(program: Program) -> Probe {
+    Probe {
+      image: program.instructions.clone(),
+      program_instance: ProgramInstance::new(program),
+      answers: HashMap::new(),
+      runs: 0,
+    }
+  }
+
+  /// The outputs of a fresh run with the inputs. Faults panic, as they do in
+  /// `run_intcode_program`.
+  pub
+  fn query(&mut self, inputs: &[InstructionType]) -> Vec<InstructionType> {
+    if let Some(outputs) = self.answers.get(inputs) {
+      return outputs.clone()
+    }
+    if self.runs > 0 {
+      self.program_instance.reset(&self.image);
+    }
+    self.runs += 1;
+    for input in inputs {
+      self.program_instance.push_input(*input);
+    }
+    let mut outputs = Vec::new();
+    loop {
+      match self.program_instance.resume() {
+        Interrupt::Output(value) => outputs.push(value),
+        Interrupt::NeedInput => panic!("unexpected request for input and given inputs exhausted"),
+        Interrupt::Halted => break,
+        Interrupt::Stopped(limit) => panic!("unexpected stop by {}", limit),
+      }
+    }
+    self.answers.insert(inputs.to_vec(), outputs.clone());
+    outputs
+  }
+
+  /// Whether the program answers 1 for the point.
+  pub
+  fn is_set(&mut self, x: InstructionType, y: InstructionType) -> bool {
+    match self.query(&[x, y]).as_slice() {
+      [0] => false,
+      [1] => true,
+      outputs => panic!("expected a single output of 0 or 1 for ({}, {}), got '{:?}'", x, y, outputs),
+    }
+  }
+}
+
+/// The first and last x set in the row, below `width`, assuming the set
+/// points are a single run which starts no further left than `previous`,
+/// the bounds of the row above, as in a beam spreading from the origin.
+pub
+fn beam_row(
+  probe: &mut Probe,
+  y: InstructionType,
+  previous: Option<(InstructionType, InstructionType)>,
+  width: InstructionType,
+) -> Option<(InstructionType, InstructionType)>
+{
+  let (start, previous_end) = previous.unwrap_or((0, 0));
+  let first = (start..width).find(|x| probe.is_set(*x, y))?;
+  // the row above ends no further right, so everything up to its end is set
+  let mut last = first.max(previous_end);
+  while last + 1 < width && probe.is_set(last + 1, y) {
+    last += 1;
+  }
+  Some((first, last))
+}
+
+/// The bounds of the beam in each row, see `beam_row`.
+pub
+fn beam_rows(
+  probe: &mut Probe,
+  width: InstructionType,
+  height: InstructionType,
+) -> Vec<Option<(InstructionType, InstructionType)>>
+{
+  let mut rows = Vec::new();
+  let mut previous = None;
+  for y in 0..height {
+    let row = beam_row(probe, y, previous, width);
+    // rows near the origin may miss a narrow beam entirely
+    previous = row.or(previous);
+    rows.push(row);
+  }
+  rows
+}
+
+/// The top left corner of the first square of the size which fits in the
+/// beam, by its bottom row, within the width and height.
+pub
+fn find_square(
+  probe: &mut Probe,
+  size: InstructionType,
+  width: InstructionType,
+  height: InstructionType,
+) -> Option<(InstructionType, InstructionType)>
+{
+  let mut previous = None;
+  for y in 0..height {
+    let row = beam_row(probe, y, previous, width);
+    previous = row.or(previous);
+    if let Some((first, _)) = row {
+      let top = y - (size - 1);
+      let right = first + (size - 1);
+      if top >= 0 && right < width && probe.is_set(right, top) {
+        return Some((first, top))
+      }
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod probe_tests {
+  use super::*;
+  use crate::run_intcode_program::run_intcode_program;
+
+  // answers 1 for y / 2 <= x <= y
+  const BEAM: &str = "3,27,3,28,1,27,27,29,7,29,28,30,7,28,27,31,1,30,31,32,1008,32,0,33,4,33,99,0,0,0,0,0,0,0";
+
+  #[test]
+  fn queries_match_fresh_runs() {
+    let mut probe = Probe::new(Program::from_string(BEAM));
+    for (x, y) in &[(3, 4), (0, 0), (5, 4), (1, 3), (3, 4)] {
+      let mut program = Program::from_string(BEAM);
+      assert_eq!(probe.query(&[*x, *y]), run_intcode_program(&mut program, &Some(vec![*x, *y])));
+    }
+    assert_eq!(probe.runs, 4);
+  }
+
+  #[test]
+  fn bounds_beam_rows() {
+    let mut probe = Probe::new(Program::from_string(BEAM));
+    assert_eq!(beam_rows(&mut probe, 4, 6), vec![
+      Some((0, 0)),
+      Some((1, 1)),
+      Some((1, 2)),
+      Some((2, 3)),
+      Some((2, 3)),
+      Some((3, 3)),
+    ]);
+  }
+
+  #[test]
+  fn finds_first_square() {
+    let mut probe = Probe::new(Program::from_string(BEAM));
+    assert_eq!(find_square(&mut probe, 2, 100, 100), Some((2, 3)));
+    assert_eq!(find_square(&mut probe, 4, 100, 100), Some((6, 9)));
+    // every square found by brute force is no higher
+    let fits = |probe: &mut Probe, x, y| (0..4).all(|i| (0..4).all(|j| probe.is_set(x + i, y + j)));
+    let highest = (0..20).flat_map(|y| (0..20).map(move |x| (x, y))).find(|(x, y)| fits(&mut probe, *x, *y));
+    assert_eq!(highest.map(|(_, y)| y), Some(9));
+  }
+}
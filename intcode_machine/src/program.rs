@@ -1,11 +1,23 @@
 use std::cell::Cell;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::rc::Rc;
 
+use super::coverage::Coverage;
+use super::devices::Devices;
 use super::execute_instruction::execute_instruction_at;
+use super::instruction_cache::InstructionCache;
 use super::instruction_type::InstructionType;
+use super::limits::ExecutionLimits;
+use super::limits::Limit;
+use super::operation_registry::OperationRegistry;
+use super::operation_registry::standard_registry;
+use super::trace::TraceEvent;
+use super::trace::Tracer;
 
 #[derive(Debug, Clone)]
 pub
@@ -13,12 +25,38 @@ struct Program {
   pub instructions: Vec<InstructionType>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub
 struct ProgramContext {
   pub counter: Cell<usize>,
-  pub inputs: RefCell<Option<Vec<InstructionType>>>,
-  pub trace: bool,
+  pub inputs: RefCell<Option<VecDeque<InstructionType>>>,
+  pub steps: Cell<usize>,
+  pub outputs: Cell<usize>,
+  pub limits: ExecutionLimits,
+  pub tracer: RefCell<Option<Box<dyn Tracer>>>,
+  pub coverage: RefCell<Option<Coverage>>,
+  pub instruction_cache: RefCell<InstructionCache>,
+  /// the standard operations are used if not set
+  pub operations: Option<Rc<OperationRegistry>>,
+  pub devices: Devices,
+}
+
+impl ProgramContext {
+  // the event is only created if a tracer is installed
+  pub
+  fn trace<F: FnOnce() -> TraceEvent>(&self, make_event: F) {
+    if let Some(tracer) = &mut *self.tracer.borrow_mut() {
+      tracer.event(self.steps.get(), &make_event());
+    }
+  }
+
+  pub
+  fn operations(&self) -> &OperationRegistry {
+    match &self.operations {
+      Some(operations) => operations,
+      None => standard_registry(),
+    }
+  }
 }
 
 impl Program {
@@ -44,16 +82,18 @@ impl Program {
     Program::from_string(&content)
   }
 
-  pub
-  fn to_string(self) -> String {
+}
+
+impl fmt::Display for Program {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     let mut output = String::new();
-    for instruction in self.instructions {
+    for instruction in &self.instructions {
       output += &format!("{},", instruction);
     }
-    if output.ends_with(",") {
+    if output.ends_with(',') {
       output.pop();
     }
-    output
+    write!(f, "{}", output)
   }
 }
 
@@ -71,6 +111,94 @@ enum StepError {
   NeedInput,
   EndOfProgram,
   Error(&'static str),
+  LimitExceeded(Limit),
+}
+
+pub
+type StepResult = Result<(usize, Option<InstructionType>), StepError>;
+
+/// Why `ProgramInstance::resume` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Interrupt {
+  Output(InstructionType),
+  /// no input is left, so more has to be pushed before resuming
+  NeedInput,
+  Halted,
+  /// stopped by one of the limits in the context, before the instruction at
+  /// the counter
+  Stopped(Limit),
+}
+
+/// The state of a program instance, see `ProgramInstance::snapshot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct Snapshot {
+  pub memory: Vec<InstructionType>,
+  pub counter: usize,
+  pub steps: usize,
+  pub outputs: usize,
+  /// inputs given but not read yet
+  pub inputs: Vec<InstructionType>,
+  /// the state of each attached device, in the order they were attached
+  pub devices: Vec<Vec<InstructionType>>,
+}
+
+impl Snapshot {
+  /// Parse a snapshot written by its `Display`, one field per line, e.g.
+  /// 'counter 12' or 'memory 1,0,0,3,99'. Device states end with ';', e.g.
+  /// 'devices 0,5;1,0;', and the line may be left out if there are none.
+  pub
+  fn from_string(input_string: &str) -> Result<Snapshot, String> {
+    let mut fields = HashMap::new();
+    for line in input_string.lines() {
+      let line = line.trim();
+      if line.is_empty() {
+        continue;
+      }
+      let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+      fields.insert(name, value.trim());
+    }
+    let field = |name: &str| fields.get(name).copied()
+      .ok_or_else(|| format!("expected a '{}' line in the snapshot", name));
+    let number = |name: &str| field(name)?.parse::<usize>()
+      .map_err(|_| format!("expected a number for '{}' in the snapshot", name));
+    let values = |name: &str| -> Result<Vec<InstructionType>, String> {
+      field(name)?.split(',').map(|x| x.trim()).filter(|x| !x.is_empty())
+        .map(|x| x.parse::<InstructionType>().map_err(|_| format!("expected numbers for '{}' in the snapshot, got '{}'", name, x)))
+        .collect()
+    };
+    Ok(Snapshot {
+      memory: values("memory")?,
+      counter: number("counter")?,
+      steps: number("steps")?,
+      outputs: number("outputs")?,
+      inputs: values("inputs")?,
+      devices: match fields.get("devices") {
+        Some(devices) => devices.split_terminator(';')
+          .map(|x| x.split(',').map(|x| x.trim()).filter(|x| !x.is_empty())
+            .map(|x| x.parse::<InstructionType>().map_err(|_| format!("expected numbers for 'devices' in the snapshot, got '{}'", x)))
+            .collect())
+          .collect::<Result<Vec<Vec<InstructionType>>, String>>()?,
+        None => Vec::new(),
+      },
+    })
+  }
+}
+
+impl fmt::Display for Snapshot {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let join = |values: &[InstructionType]| values.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",");
+    writeln!(f, "counter {}", self.counter)?;
+    writeln!(f, "steps {}", self.steps)?;
+    writeln!(f, "outputs {}", self.outputs)?;
+    writeln!(f, "inputs {}", join(&self.inputs))?;
+    if !self.devices.is_empty() {
+      let devices: Vec<String> = self.devices.iter().map(|x| format!("{};", join(x))).collect();
+      writeln!(f, "devices {}", devices.concat())?;
+    }
+    writeln!(f, "memory {}", join(&self.memory))
+  }
 }
 
 impl<'a> ProgramInstance<'a> {
@@ -81,11 +209,7 @@ impl<'a> ProgramInstance<'a> {
     ProgramInstance {
       owned_program: Some(rc.clone()),
       ref_program: None,
-      context: RefCell::new(ProgramContext {
-        counter: Cell::new(0),
-        inputs: RefCell::new(None),
-        trace: false,
-      }),
+      context: RefCell::new(ProgramContext::default()),
     }
   }
 
@@ -94,16 +218,22 @@ impl<'a> ProgramInstance<'a> {
     ProgramInstance {
       owned_program: None,
       ref_program: Some(program_ref),
-      context: RefCell::new(ProgramContext {
-        counter: Cell::new(0),
-        inputs: RefCell::new(None),
-        trace: false,
-      }),
+      context: RefCell::new(ProgramContext::default()),
     }
   }
 
+  /// Run with these operations instead of the standard ones.
+  pub
+  fn set_operations(&mut self, operations: Rc<OperationRegistry>) {
+    let context = self.context.get_mut();
+    context.operations = Some(operations);
+    context.instruction_cache.get_mut().clear();
+  }
+
   pub
-  fn get_program_mut(&'a mut self) -> &'a mut Program {
+  fn get_program_mut(&mut self) -> &mut Program {
+    // the program may be changed in any way, so nothing decoded can be kept
+    self.context.get_mut().instruction_cache.get_mut().clear();
     match &mut self.owned_program {
       Some(x) => Rc::get_mut(x).unwrap(),
       None => {
@@ -116,9 +246,9 @@ impl<'a> ProgramInstance<'a> {
   }
 
   pub
-  fn get_program(&'a self) -> &'a Program {
+  fn get_program(&self) -> &Program {
     match & self.owned_program {
-      Some(x) => &*x,
+      Some(x) => x,
       None => {
         match & self.ref_program {
           Some(x) => x,
@@ -128,17 +258,145 @@ impl<'a> ProgramInstance<'a> {
     }
   }
 
+  fn restore_memory(&mut self, image: &[InstructionType]) {
+    let context = self.context.get_mut();
+    let program = match &mut self.owned_program {
+      Some(x) => Rc::get_mut(x).unwrap(),
+      None => {
+        match &mut self.ref_program {
+          Some(x) => x,
+          None => panic!("neither owned nor ref program available"),
+        }
+      },
+    };
+    if program.instructions.len() != image.len() {
+      program.instructions = image.to_vec();
+      context.instruction_cache.get_mut().clear();
+    } else {
+      let instruction_cache = context.instruction_cache.get_mut();
+      for (address, (value, original)) in program.instructions.iter_mut().zip(image).enumerate() {
+        if value != original {
+          *value = *original;
+          instruction_cache.invalidate(address);
+        }
+      }
+    }
+  }
+
+  /// Restore memory to the image and start again, with no inputs. Only the
+  /// instructions decoded from changed addresses are forgotten, so a program
+  /// can be run many times without decoding it again. Attached devices keep
+  /// their state, which a snapshot taken at the start can restore.
+  pub
+  fn reset(&mut self, image: &[InstructionType]) {
+    self.restore_memory(image);
+    let context = self.context.get_mut();
+    context.counter.set(0);
+    context.steps.set(0);
+    context.outputs.set(0);
+    *context.inputs.get_mut() = Some(VecDeque::new());
+  }
+
+  /// The memory, counter, unread inputs and device states, to go back to
+  /// later.
+  pub
+  fn snapshot(&self) -> Snapshot {
+    let context = self.context.borrow();
+    let inputs = context.inputs.borrow();
+    Snapshot {
+      memory: self.get_program().instructions.clone(),
+      counter: context.counter.get(),
+      steps: context.steps.get(),
+      outputs: context.outputs.get(),
+      inputs: inputs.iter().flatten().copied().collect(),
+      devices: context.devices.save(),
+    }
+  }
+
+  /// Go back to a snapshot, forgetting only the instructions decoded from
+  /// addresses changed since, as `reset` does.
+  pub
+  fn restore(&mut self, snapshot: &Snapshot) {
+    self.restore_memory(&snapshot.memory);
+    let context = self.context.get_mut();
+    context.counter.set(snapshot.counter);
+    context.steps.set(snapshot.steps);
+    context.outputs.set(snapshot.outputs);
+    *context.inputs.get_mut() = Some(snapshot.inputs.iter().copied().collect());
+    context.devices.load(&snapshot.devices);
+  }
+
+  /// Give the program an input, after any it has not read yet.
+  pub
+  fn push_input(&self, value: InstructionType) {
+    let context = self.context.borrow();
+    context.inputs.borrow_mut().get_or_insert_with(VecDeque::new).push_back(value);
+  }
+
+  /// Run until the program prints a value, needs an input, halts or is
+  /// stopped by a limit, so it can be driven one output at a time. Faults
+  /// panic, as they do in `run_intcode_program`.
+  pub
+  fn resume(&mut self) -> Interrupt {
+    if self.context.borrow().inputs.borrow().is_none() {
+      // never read from stdin, the caller pushes the inputs
+      *self.context.borrow().inputs.borrow_mut() = Some(VecDeque::new());
+    }
+    loop {
+      match self.step() {
+        Ok((next_counter, output)) => {
+          self.context.borrow_mut().counter.set(next_counter);
+          if let Some(value) = output {
+            return Interrupt::Output(value)
+          }
+        },
+        Err(StepError::NeedInput) => return Interrupt::NeedInput,
+        Err(StepError::EndOfProgram) => return Interrupt::Halted,
+        Err(StepError::Error(msg)) => panic!("unexpected error: {}", msg),
+        Err(StepError::LimitExceeded(limit)) => return Interrupt::Stopped(limit),
+      }
+    }
+  }
+
   fn program_has_ended(& self) -> bool {
     self.context.borrow().counter.get() >= self.get_program().instructions.len()
   }
 
   pub
-  fn step(&'a mut self) -> Result<(usize, Option<InstructionType>), StepError> {
+  fn step(&mut self) -> StepResult {
     if self.program_has_ended() {
       // reached the end of the program
       return Err(StepError::EndOfProgram)
     }
-    execute_instruction_at(&mut self.get_program_mut(), &*self.context.borrow())
+    {
+      let context = self.context.borrow();
+      let limit = context.limits.check(
+        &self.get_program().instructions,
+        context.counter.get(),
+        context.steps.get(),
+        context.outputs.get(),
+        context.operations());
+      if let Some(limit) = limit {
+        return Err(StepError::LimitExceeded(limit))
+      }
+    }
+    // borrow the program and the context separately, since the context is
+    // only read while the program is modified
+    let program = match &mut self.owned_program {
+      Some(x) => Rc::get_mut(x).unwrap(),
+      None => {
+        match &mut self.ref_program {
+          Some(x) => x,
+          None => panic!("neither owned nor ref program available"),
+        }
+      },
+    };
+    let context = self.context.borrow();
+    let result = execute_instruction_at(program, &context);
+    if let Ok((_, Some(_))) = result {
+      context.outputs.set(context.outputs.get() + 1);
+    }
+    result
   }
 }
 
@@ -151,5 +409,47 @@ mod run_intcode_program_tests {
     let program = Program::from_string("1,2,3");
     assert_eq!(program.to_string(), "1,2,3");
   }
+
+  #[test]
+  fn resume_stops_at_each_output_and_input() {
+    let mut program_instance = ProgramInstance::new(Program::from_string("104,7,3,9,4,9,99,0,0,0"));
+    assert_eq!(program_instance.resume(), Interrupt::Output(7));
+    assert_eq!(program_instance.resume(), Interrupt::NeedInput);
+    program_instance.push_input(5);
+    assert_eq!(program_instance.resume(), Interrupt::Output(5));
+    assert_eq!(program_instance.resume(), Interrupt::Halted);
+    assert_eq!(program_instance.resume(), Interrupt::Halted);
+  }
+
+  #[test]
+  fn reset_forgets_rewritten_instructions() {
+    // the input is written to 6, which is then run
+    let image = Program::from_string("3,6,1105,1,6,99,0,7,99").instructions;
+    let mut program_instance = ProgramInstance::new(Program { instructions: image.clone() });
+    program_instance.push_input(104);
+    assert_eq!(program_instance.resume(), Interrupt::Output(7));
+    program_instance.reset(&image);
+    assert_eq!(program_instance.get_program().instructions, image);
+    program_instance.push_input(99);
+    assert_eq!(program_instance.resume(), Interrupt::Halted);
+  }
+
+  #[test]
+  fn restores_snapshots() {
+    // the input is written to 6, which is then run
+    let image = Program::from_string("3,6,1105,1,6,99,0,7,99").instructions;
+    let mut program_instance = ProgramInstance::new(Program { instructions: image });
+    let snapshot = program_instance.snapshot();
+    program_instance.push_input(104);
+    assert_eq!(program_instance.resume(), Interrupt::Output(7));
+    let printed = Snapshot::from_string(&program_instance.snapshot().to_string()).unwrap();
+    assert_eq!(printed, program_instance.snapshot());
+    program_instance.restore(&snapshot);
+    program_instance.push_input(99);
+    assert_eq!(program_instance.resume(), Interrupt::Halted);
+    program_instance.restore(&printed);
+    assert_eq!(program_instance.resume(), Interrupt::Halted);
+    assert_eq!(Snapshot::from_string("counter 1").unwrap_err(), "expected a 'memory' line in the snapshot");
+  }
 }
 
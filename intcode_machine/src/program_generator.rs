@@ -0,0 +1,317 @@
+use std::fmt;
+
+use super::instruction_type::InstructionType;
+use super::program::Program;
+use super::random::Random;
+
+/// An operand of a generated instruction, by what it refers to rather than by
+/// address, so instructions and data can be removed while shrinking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Operand {
+  /// position mode, the data value with this index, wrapped to the data
+  Data(usize),
+  Immediate(InstructionType),
+  /// immediate mode, the address of the instruction with this index, or of
+  /// the final HALT past the last one
+  Target(usize),
+  /// position mode, the address of the cell at the offset into the
+  /// instruction with this index, wrapped to its size, so the program reads
+  /// or rewrites its own code
+  Code(usize, usize),
+  /// position mode, the address of a cell after the data which holds the
+  /// address of the instruction with this index, for jumps through memory
+  Indirect(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct GeneratedInstruction {
+  pub opcode: InstructionType,
+  pub operands: Vec<Operand>,
+}
+
+/// A random program, with its inputs.
+///
+/// Jumps only go forward to an instruction, so a program which only writes
+/// to its data after the final HALT always halts, unless arithmetic
+/// overflows. Writes into the code may rewrite instructions and jump
+/// targets though, so those programs can fault or never halt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct GeneratedProgram {
+  pub instructions: Vec<GeneratedInstruction>,
+  /// values after the final HALT, never empty, which are followed in memory
+  /// by the cells of the indirect jump targets
+  pub data: Vec<InstructionType>,
+  pub inputs: Vec<InstructionType>,
+}
+
+fn small_value(random: &mut Random) -> InstructionType {
+  random.below(21) as InstructionType - 10
+}
+
+/// Generate a program with the given number of instructions, before the
+/// final HALT.
+pub
+fn generate_program(seed: u64, size: usize) -> GeneratedProgram {
+  // spread nearby seeds apart, since xorshift starts out close to its seed
+  let mut random = Random::new(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+  let data: Vec<InstructionType> = (0..1 + random.below(8)).map(|_| small_value(&mut random)).collect();
+  let mut inputs = Vec::new();
+  let mut instructions = Vec::new();
+  for index in 0..size {
+    let data_size = data.len();
+    // a cell of the data, or now and then of the code
+    let cell = |random: &mut Random| {
+      if random.below(4) == 0 {
+        Operand::Code(random.below(size), random.below(4))
+      } else {
+        Operand::Data(random.below(data_size))
+      }
+    };
+    let value = |random: &mut Random| {
+      if random.below(2) == 0 {
+        cell(random)
+      } else {
+        Operand::Immediate(small_value(random))
+      }
+    };
+    let opcode = [1, 2, 3, 4, 5, 6, 7, 8][random.below(8)];
+    let operands = match opcode {
+      1 | 2 | 7 | 8 => vec![value(&mut random), value(&mut random), cell(&mut random)],
+      3 => {
+        inputs.push(small_value(&mut random));
+        vec![cell(&mut random)]
+      },
+      4 => vec![value(&mut random)],
+      _ => {
+        let target = index + 1 + random.below(size - index);
+        let target = if random.below(2) == 0 { Operand::Target(target) } else { Operand::Indirect(target) };
+        vec![value(&mut random), target]
+      },
+    };
+    instructions.push(GeneratedInstruction { opcode, operands });
+  }
+  GeneratedProgram { instructions, data, inputs }
+}
+
+impl GeneratedProgram {
+  pub
+  fn to_program(&self) -> Program {
+    let mut addresses = vec![0];
+    for instruction in &self.instructions {
+      addresses.push(addresses.last().unwrap() + 1 + instruction.operands.len());
+    }
+    // the final HALT is at the last address
+    let data_start = addresses.last().unwrap() + 1;
+    let address_of = |index: usize| addresses[index.min(self.instructions.len())];
+    // the cells of the indirect targets, in the order they are first used
+    let mut indirect = Vec::new();
+    for operand in self.instructions.iter().flat_map(|x| x.operands.iter()) {
+      if let Operand::Indirect(index) = operand {
+        if !indirect.contains(&address_of(*index)) {
+          indirect.push(address_of(*index));
+        }
+      }
+    }
+    let indirect_start = data_start + self.data.len();
+    let mut memory = Vec::new();
+    for instruction in &self.instructions {
+      let mut intcode = instruction.opcode;
+      let mut values = Vec::new();
+      for (index, operand) in instruction.operands.iter().enumerate() {
+        let (immediate, value) = match *operand {
+          Operand::Data(index) => (false, (data_start + index % self.data.len()) as InstructionType),
+          Operand::Immediate(value) => (true, value),
+          Operand::Target(index) => (true, address_of(index) as InstructionType),
+          Operand::Code(index, offset) => {
+            let size = self.instructions.get(index).map_or(1, |x| 1 + x.operands.len());
+            (false, (address_of(index) + offset % size) as InstructionType)
+          },
+          Operand::Indirect(index) => {
+            let cell = indirect.iter().position(|x| *x == address_of(index)).unwrap();
+            (false, (indirect_start + cell) as InstructionType)
+          },
+        };
+        if immediate {
+          intcode += 10i32.pow(index as u32 + 2);
+        }
+        values.push(value);
+      }
+      memory.push(intcode);
+      memory.extend(values);
+    }
+    memory.push(99);
+    memory.extend(&self.data);
+    memory.extend(indirect.iter().map(|x| *x as InstructionType));
+    Program { instructions: memory }
+  }
+
+  /// Programs which are each a little smaller or simpler than this one, the
+  /// largest reductions first.
+  pub
+  fn shrink(&self) -> Vec<GeneratedProgram> {
+    let mut candidates = Vec::new();
+    for removed in 0..self.instructions.len() {
+      let mut candidate = self.clone();
+      candidate.instructions.remove(removed);
+      for operand in candidate.instructions.iter_mut().flat_map(|x| x.operands.iter_mut()) {
+        if let Operand::Target(index) | Operand::Code(index, _) | Operand::Indirect(index) = operand {
+          if *index > removed {
+            *index -= 1;
+          }
+        }
+      }
+      candidates.push(candidate);
+    }
+    for removed in 0..self.inputs.len() {
+      let mut candidate = self.clone();
+      candidate.inputs.remove(removed);
+      candidates.push(candidate);
+    }
+    for removed in (0..self.data.len()).skip(1) {
+      let mut candidate = self.clone();
+      candidate.data.remove(removed);
+      for operand in candidate.instructions.iter_mut().flat_map(|x| x.operands.iter_mut()) {
+        if let Operand::Data(index) = operand {
+          if *index >= removed {
+            *index -= 1;
+          }
+        }
+      }
+      candidates.push(candidate);
+    }
+    for (instruction, operand) in self.operand_indices() {
+      let simpler = match self.instructions[instruction].operands[operand] {
+        Operand::Data(index) if index != 0 => vec![Operand::Data(0)],
+        Operand::Immediate(value) if value != 0 => vec![Operand::Immediate(0), Operand::Immediate(value / 2)],
+        Operand::Code(..) => vec![Operand::Data(0)],
+        Operand::Indirect(index) => vec![Operand::Target(index)],
+        _ => vec![],
+      };
+      for replacement in simpler {
+        let mut candidate = self.clone();
+        candidate.instructions[instruction].operands[operand] = replacement;
+        candidates.push(candidate);
+      }
+    }
+    let value_count = self.data.len() + self.inputs.len();
+    for index in 0..value_count {
+      let value = if index < self.data.len() { self.data[index] } else { self.inputs[index - self.data.len()] };
+      if value == 0 {
+        continue;
+      }
+      for replacement in [0, value / 2].iter() {
+        let mut candidate = self.clone();
+        if index < self.data.len() {
+          candidate.data[index] = *replacement;
+        } else {
+          candidate.inputs[index - self.data.len()] = *replacement;
+        }
+        candidates.push(candidate);
+      }
+    }
+    candidates.dedup();
+    candidates
+  }
+
+  fn operand_indices(&self) -> Vec<(usize, usize)> {
+    self.instructions.iter()
+      .enumerate()
+      .flat_map(|(index, instruction)| (0..instruction.operands.len()).map(move |operand| (index, operand)))
+      .collect()
+  }
+}
+
+impl fmt::Display for GeneratedProgram {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let memory: Vec<String> = self.to_program().instructions.iter().map(|x| x.to_string()).collect();
+    write!(f, "{} with inputs {:?}", memory.join(","), self.inputs)
+  }
+}
+
+#[cfg(test)]
+mod program_generator_tests {
+  use super::*;
+  use std::panic;
+  use crate::run_intcode_program::run_intcode_program;
+
+  #[test]
+  fn renders_operands_as_addresses() {
+    let generated = GeneratedProgram {
+      instructions: vec![
+        GeneratedInstruction { opcode: 3, operands: vec![Operand::Data(1)] },
+        GeneratedInstruction { opcode: 6, operands: vec![Operand::Data(1), Operand::Target(3)] },
+        GeneratedInstruction { opcode: 1, operands: vec![Operand::Data(3), Operand::Immediate(-2), Operand::Data(0)] },
+      ],
+      data: vec![5, 0],
+      inputs: vec![7],
+    };
+    assert_eq!(generated.to_program().instructions, vec![3, 11, 1006, 11, 9, 1001, 11, -2, 10, 99, 5, 0]);
+  }
+
+  #[test]
+  fn shrinking_retargets_jumps() {
+    let generated = GeneratedProgram {
+      instructions: vec![
+        GeneratedInstruction { opcode: 4, operands: vec![Operand::Immediate(1)] },
+        GeneratedInstruction { opcode: 5, operands: vec![Operand::Immediate(1), Operand::Target(2)] },
+      ],
+      data: vec![0],
+      inputs: vec![],
+    };
+    let shrunk = &generated.shrink()[0];
+    assert_eq!(shrunk.instructions[0].operands[1], Operand::Target(1));
+    assert_eq!(shrunk.to_program().instructions, vec![1105, 1, 3, 99, 0]);
+  }
+
+  #[test]
+  fn renders_code_cells_and_indirect_targets() {
+    let generated = GeneratedProgram {
+      instructions: vec![
+        GeneratedInstruction { opcode: 1, operands: vec![Operand::Immediate(3), Operand::Immediate(4), Operand::Code(1, 5)] },
+        GeneratedInstruction { opcode: 4, operands: vec![Operand::Immediate(1)] },
+        GeneratedInstruction { opcode: 5, operands: vec![Operand::Immediate(1), Operand::Indirect(3)] },
+      ],
+      data: vec![0],
+      inputs: vec![],
+    };
+    // the ADD rewrites the PRINT's parameter, and the jump goes through 11
+    assert_eq!(generated.to_program().instructions, vec![1101, 3, 4, 5, 104, 1, 105, 1, 11, 99, 0, 9]);
+    let shrunk = generated.shrink();
+    assert!(shrunk.iter().any(|x| x.instructions[0].operands.get(2) == Some(&Operand::Data(0))));
+    assert!(shrunk.iter().any(|x| x.instructions.len() == 3 && x.instructions[2].operands[1] == Operand::Target(3)));
+  }
+
+  #[test]
+  fn generated_programs_rewrite_their_code() {
+    let operands: Vec<Operand> = (0..50)
+      .flat_map(|seed| generate_program(seed, 10).instructions)
+      .flat_map(|x| x.operands)
+      .collect();
+    assert!(operands.iter().any(|x| matches!(x, Operand::Code(..))));
+    assert!(operands.iter().any(|x| matches!(x, Operand::Indirect(_))));
+  }
+
+  #[test]
+  fn generated_programs_without_code_writes_halt() {
+    let mut checked = 0;
+    for seed in 0..1000 {
+      let generated = generate_program(seed, 5);
+      let operands = || generated.instructions.iter().flat_map(|x| x.operands.iter());
+      if operands().any(|x| matches!(x, Operand::Code(..))) {
+        continue;
+      }
+      checked += 1;
+      let mut program = generated.to_program();
+      let inputs = Some(generated.inputs.clone());
+      let result = panic::catch_unwind(panic::AssertUnwindSafe(|| run_intcode_program(&mut program, &inputs)));
+      if let Err(payload) = result {
+        let message = crate::fuzzer::panic_message(&*payload);
+        assert!(message.contains("overflow"), "seed {} faulted: {}", seed, message);
+      }
+    }
+    assert!(checked > 100, "only {} programs without code writes", checked);
+  }
+}
@@ -0,0 +1,32 @@
+/// A xorshift generator, so runs can be repeated from their seed.
+#[derive(Debug, Clone)]
+pub
+struct Random {
+  state: u64,
+}
+
+impl Random {
+  pub
+  fn new(seed: u64) -> Random {
+    Random { state: seed.max(1) }
+  }
+
+  /// The current state, which as a seed repeats the values from here on.
+  pub
+  fn state(&self) -> u64 {
+    self.state
+  }
+
+  pub
+  fn next(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  pub
+  fn below(&mut self, bound: usize) -> usize {
+    (self.next() % bound.max(1) as u64) as usize
+  }
+}
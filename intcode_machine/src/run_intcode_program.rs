@@ -1,55 +1,148 @@
+use std::collections::VecDeque;
+use std::panic;
+use std::rc::Rc;
+
+use super::coverage::Coverage;
 use super::instruction_type::InstructionType;
+use super::limits::ExecutionLimits;
+use super::limits::LimitExceeded;
+use super::operation_registry::OperationRegistry;
 use super::program::Program;
 use super::program::ProgramInstance;
 use super::program::StepError;
+use super::trace::TextTracer;
+use super::trace::Tracer;
 
 pub
 fn run_intcode_program(
-  mut program: &mut Program,
+  program: &mut Program,
   inputs: &Option<Vec<InstructionType>>,
 ) -> Vec<InstructionType>
 {
-  run_intcode_program_optional_trace(&mut program, inputs, false)
+  let mut program_instance = ProgramInstance::from_ref(program);
+  *program_instance.context.borrow_mut().inputs.borrow_mut() = inputs.clone().map(VecDeque::from);
+  run_program_instance(&mut program_instance)
 }
 
 pub
 fn trace_intcode_program(
-  mut program: &mut Program,
+  program: &mut Program,
+  inputs: &Option<Vec<InstructionType>>,
+) -> Vec<InstructionType>
+{
+  run_intcode_program_with_tracer(program, inputs, Box::new(TextTracer::stdout()))
+}
+
+pub
+fn run_intcode_program_with_tracer(
+  program: &mut Program,
   inputs: &Option<Vec<InstructionType>>,
+  tracer: Box<dyn Tracer>,
 ) -> Vec<InstructionType>
 {
-  run_intcode_program_optional_trace(&mut program, inputs, true)
+  let mut program_instance = ProgramInstance::from_ref(program);
+  *program_instance.context.borrow_mut().inputs.borrow_mut() = inputs.clone().map(VecDeque::from);
+  *program_instance.context.borrow_mut().tracer.borrow_mut() = Some(tracer);
+  run_program_instance(&mut program_instance)
 }
 
-fn run_intcode_program_optional_trace(
-  mut program: &mut Program,
+/// Run the program and add the addresses and branch directions it exercised
+/// to the given coverage, so coverage can be accumulated over many runs. A
+/// run which faults still adds what it exercised before panicking, but is
+/// not counted in the runs.
+pub
+fn run_intcode_program_with_coverage(
+  program: &mut Program,
   inputs: &Option<Vec<InstructionType>>,
-  trace: bool,
+  coverage: &mut Coverage,
 ) -> Vec<InstructionType>
 {
-  let mut program_instance = ProgramInstance::from_ref(&mut program);
-  *program_instance.context.borrow_mut().inputs.borrow_mut() = inputs.clone();
-  program_instance.context.borrow_mut().trace = trace;
+  let mut program_instance = ProgramInstance::from_ref(program);
+  *program_instance.context.borrow_mut().inputs.borrow_mut() = inputs.clone().map(VecDeque::from);
+  *program_instance.context.borrow_mut().coverage.borrow_mut() = Some(std::mem::take(coverage));
+  let result = panic::catch_unwind(panic::AssertUnwindSafe(|| run_program_instance(&mut program_instance)));
+  *coverage = program_instance.context.borrow_mut().coverage.borrow_mut().take().unwrap();
+  match result {
+    Ok(outputs) => {
+      coverage.runs += 1;
+      outputs
+    },
+    Err(payload) => panic::resume_unwind(payload),
+  }
+}
+
+/// Run the program until it halts or exceeds one of the limits, in which
+/// case the state of the program at that point is returned as the error.
+pub
+fn run_intcode_program_with_limits(
+  program: &mut Program,
+  inputs: &Option<Vec<InstructionType>>,
+  limits: &ExecutionLimits,
+) -> Result<Vec<InstructionType>, LimitExceeded>
+{
+  if let Some(limit) = limits.check_memory(&program.instructions) {
+    return Err(LimitExceeded { limit, counter: 0, steps: 0, memory: program.instructions.clone(), outputs: Vec::new() })
+  }
+  let mut program_instance = ProgramInstance::from_ref(program);
+  *program_instance.context.borrow_mut().inputs.borrow_mut() = inputs.clone().map(VecDeque::from);
+  program_instance.context.borrow_mut().limits = *limits;
+  run_program_instance_with_limits(&mut program_instance)
+}
+
+/// Run the program with the given operations instead of the standard ones.
+pub
+fn run_intcode_program_with_operations(
+  program: &mut Program,
+  inputs: &Option<Vec<InstructionType>>,
+  operations: Rc<OperationRegistry>,
+) -> Vec<InstructionType>
+{
+  let mut program_instance = ProgramInstance::from_ref(program);
+  *program_instance.context.borrow_mut().inputs.borrow_mut() = inputs.clone().map(VecDeque::from);
+  program_instance.set_operations(operations);
+  run_program_instance(&mut program_instance)
+}
+
+fn run_program_instance(program_instance: &mut ProgramInstance) -> Vec<InstructionType> {
+  match run_program_instance_with_limits(program_instance) {
+    Ok(outputs) => outputs,
+    Err(stopped) => panic!("unexpected stop: {}", stopped),
+  }
+}
+
+fn run_program_instance_with_limits(
+  program_instance: &mut ProgramInstance,
+) -> Result<Vec<InstructionType>, LimitExceeded>
+{
   let mut outputs = Vec::new();
   loop {
-    let mut next_counter;
+    let next_counter;
     match program_instance.step() {
       Ok((counter, x)) => {
         next_counter = counter;
-        match x {
-          Some(output) => outputs.push(output),
-          None => continue,
+        if let Some(output) = x {
+          outputs.push(output);
         }
       },
       Err(x) => match x {
         StepError::NeedInput => panic!("unexpected request for input and given inputs exhausted"),
         StepError::EndOfProgram => break,
         StepError::Error(msg) => panic!("unexpected error: {}", msg),
+        StepError::LimitExceeded(limit) => {
+          let context = program_instance.context.borrow();
+          return Err(LimitExceeded {
+            limit,
+            counter: context.counter.get(),
+            steps: context.steps.get(),
+            memory: program_instance.get_program().instructions.clone(),
+            outputs,
+          })
+        },
       }
     };
     program_instance.context.borrow_mut().counter.set(next_counter);
   }
-  outputs
+  Ok(outputs)
 }
 
 #[cfg(test)]
@@ -59,14 +152,14 @@ mod run_intcode_program_tests {
   #[test]
   fn empty_program() {
     let program = Program { instructions: vec![] };
-    let outputs = run_intcode_program(&mut program.clone(), &mut Some(Vec::new()));
+    let outputs = run_intcode_program(&mut program.clone(), &Some(Vec::new()));
     assert!(outputs.is_empty());
   }
 
   #[test]
   fn simple_halting_program() {
     let program = Program { instructions: vec![99] };
-    let outputs = run_intcode_program(&mut program.clone(), &mut Some(Vec::new()));
+    let outputs = run_intcode_program(&mut program.clone(), &Some(Vec::new()));
     assert!(outputs.is_empty());
   }
 
@@ -74,22 +167,22 @@ mod run_intcode_program_tests {
   fn aoc_day02_examples() {
     {
       let mut program = Program::from_string("1,0,0,0,99");
-      run_intcode_program(&mut program, &mut Some(Vec::new()));
+      run_intcode_program(&mut program, &Some(Vec::new()));
       assert!(program.to_string().starts_with("2,"));
     }
     {
       let mut program = Program::from_string("2,3,0,3,99");
-      run_intcode_program(&mut program, &mut Some(Vec::new()));
+      run_intcode_program(&mut program, &Some(Vec::new()));
       assert!(program.to_string().starts_with("2,3,0,6,"));
     }
     {
       let mut program = Program::from_string("2,4,4,5,99,0");
-      run_intcode_program(&mut program, &mut Some(Vec::new()));
+      run_intcode_program(&mut program, &Some(Vec::new()));
       assert!(program.to_string().starts_with("2,4,4,5,99,9801"));
     }
     {
       let mut program = Program::from_string("1,1,1,4,99,5,6,0,99");
-      run_intcode_program(&mut program, &mut Some(Vec::new()));
+      run_intcode_program(&mut program, &Some(Vec::new()));
       assert!(program.to_string().starts_with("30,"));
     }
     {
@@ -99,7 +192,7 @@ mod run_intcode_program_tests {
 63,1,63,10,67,2,10,67,71,1,6,71,75,1,10,75,79,1,79,9,83,2,83,6,87,2,87,9,91,1,
 5,91,95,1,6,95,99,1,99,9,103,2,10,103,107,1,107,6,111,2,9,111,115,1,5,115,119,
 1,10,119,123,1,2,123,127,1,127,6,0,99,2,14,0,0");
-      run_intcode_program(&mut program, &mut Some(Vec::new()));
+      run_intcode_program(&mut program, &Some(Vec::new()));
       assert!(program.to_string().starts_with("12490719,"));
     }
   }
@@ -144,19 +237,19 @@ mod run_intcode_program_tests {
 ");
     {
       let mut program = problem_input_program.clone();
-      let outputs = run_intcode_program(&mut program, &mut Some(vec![1]));
-      assert!(outputs.len() > 0);
+      let outputs = run_intcode_program(&mut program, &Some(vec![1]));
+      assert!(!outputs.is_empty());
       assert_eq!(*(outputs.last().unwrap()), 12896948_i32);
     }
     {
       let program = Program::from_string("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
       {
-        let outputs = run_intcode_program(&mut program.clone(), &mut Some(vec![0]));
+        let outputs = run_intcode_program(&mut program.clone(), &Some(vec![0]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 0_i32);
       }
       {
-        let outputs = run_intcode_program(&mut program.clone(), &mut Some(vec![1]));
+        let outputs = run_intcode_program(&mut program.clone(), &Some(vec![1]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 1_i32);
       }
@@ -164,12 +257,12 @@ mod run_intcode_program_tests {
     {
       let program = Program::from_string("3,3,1105,-1,9,1101,0,0,12,4,12,99,1");
       {
-        let outputs = run_intcode_program(&mut program.clone(), &mut Some(vec![0]));
+        let outputs = run_intcode_program(&mut program.clone(), &Some(vec![0]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 0_i32);
       }
       {
-        let outputs = run_intcode_program(&mut program.clone(), &mut Some(vec![1]));
+        let outputs = run_intcode_program(&mut program.clone(), &Some(vec![1]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 1_i32);
       }
@@ -181,24 +274,24 @@ mod run_intcode_program_tests {
 999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99
 ");
       {
-        let outputs = trace_intcode_program(&mut program.clone(), &mut Some(vec![7]));
+        let outputs = trace_intcode_program(&mut program.clone(), &Some(vec![7]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 999_i32);
       }
       {
-        let outputs = trace_intcode_program(&mut program.clone(), &mut Some(vec![8]));
+        let outputs = trace_intcode_program(&mut program.clone(), &Some(vec![8]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 1000_i32);
       }
       {
-        let outputs = trace_intcode_program(&mut program.clone(), &mut Some(vec![9]));
+        let outputs = trace_intcode_program(&mut program.clone(), &Some(vec![9]));
         assert!(outputs.len() == 1);
         assert_eq!(outputs[0], 1001_i32);
       }
     }
     {
       let program = problem_input_program.clone();
-      let outputs = trace_intcode_program(&mut program.clone(), &mut Some(vec![5]));
+      let outputs = trace_intcode_program(&mut program.clone(), &Some(vec![5]));
       assert!(outputs.len() == 1);
       assert_eq!(outputs[0], 7704130);
     }
@@ -0,0 +1,320 @@
+use std::fmt;
+
+use super::ascii::AsciiMachine;
+use super::ascii::AsciiOutput;
+use super::ascii::AsciiStatus;
+use super::hull_robot::Direction;
+use super::program::Program;
+
+/// The most movement functions the vacuum robot accepts.
+pub
+const MAX_FUNCTIONS: usize = 3;
+
+/// The most characters the robot accepts in a routine, without the newline.
+pub
+const MAX_ROUTINE_LENGTH: usize = 20;
+
+/// The view of a camera program, where '#' is scaffold and the robot is
+/// shown as '^', '>', 'v' or '<' on the scaffold it faces along.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct ScaffoldMap {
+  pub rows: Vec<Vec<char>>,
+  pub robot: Option<((i32, i32), Direction)>,
+}
+
+fn ahead((x, y): (i32, i32), direction: Direction) -> (i32, i32) {
+  match direction {
+    Direction::Up => (x, y - 1),
+    Direction::Right => (x + 1, y),
+    Direction::Down => (x, y + 1),
+    Direction::Left => (x - 1, y),
+  }
+}
+
+impl ScaffoldMap {
+  /// Parse the camera view, ignoring blank lines.
+  pub
+  fn parse(text: &str) -> ScaffoldMap {
+    let rows: Vec<Vec<char>> = text.lines()
+      .map(|line| line.trim_end())
+      .filter(|line| !line.is_empty())
+      .map(|line| line.chars().collect())
+      .collect();
+    let mut robot = None;
+    for (y, row) in rows.iter().enumerate() {
+      for (x, cell) in row.iter().enumerate() {
+        let direction = match cell {
+          '^' => Direction::Up,
+          '>' => Direction::Right,
+          'v' => Direction::Down,
+          '<' => Direction::Left,
+          _ => continue,
+        };
+        robot = Some(((x as i32, y as i32), direction));
+      }
+    }
+    ScaffoldMap { rows, robot }
+  }
+
+  pub
+  fn is_scaffold(&self, (x, y): (i32, i32)) -> bool {
+    if x < 0 || y < 0 {
+      return false
+    }
+    let cell = self.rows.get(y as usize).and_then(|row| row.get(x as usize));
+    matches!(cell, Some('#') | Some('^') | Some('>') | Some('v') | Some('<'))
+  }
+
+  /// The scaffold with scaffold on all four sides, by row then column.
+  pub
+  fn intersections(&self) -> Vec<(i32, i32)> {
+    let mut intersections = Vec::new();
+    for (y, row) in self.rows.iter().enumerate() {
+      for x in 0..row.len() {
+        let position = (x as i32, y as i32);
+        let directions = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+        if self.is_scaffold(position) && directions.iter().all(|x| self.is_scaffold(ahead(position, *x))) {
+          intersections.push(position);
+        }
+      }
+    }
+    intersections
+  }
+
+  /// The sum of the alignment parameters, x times y, of the intersections.
+  pub
+  fn alignment_sum(&self) -> i32 {
+    self.intersections().iter().map(|(x, y)| x * y).sum()
+  }
+
+  /// The moves of the robot along the scaffold, straight on through every
+  /// intersection, until it reaches an end.
+  pub
+  fn trace_path(&self) -> Vec<Move> {
+    let (mut position, mut direction) = match self.robot {
+      Some(robot) => robot,
+      None => return Vec::new(),
+    };
+    let mut moves = Vec::new();
+    loop {
+      let mut steps = 0;
+      while self.is_scaffold(ahead(position, direction)) {
+        position = ahead(position, direction);
+        steps += 1;
+      }
+      if steps > 0 {
+        moves.push(Move::Forward(steps));
+      }
+      if self.is_scaffold(ahead(position, direction.turned(false))) {
+        direction = direction.turned(false);
+        moves.push(Move::Left);
+      } else if self.is_scaffold(ahead(position, direction.turned(true))) {
+        direction = direction.turned(true);
+        moves.push(Move::Right);
+      } else {
+        return moves
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Move {
+  Left,
+  Right,
+  Forward(usize),
+}
+
+impl fmt::Display for Move {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Move::Left => write!(f, "L"),
+      Move::Right => write!(f, "R"),
+      Move::Forward(steps) => write!(f, "{}", steps),
+    }
+  }
+}
+
+/// The moves as the robot reads them, e.g. 'R,8,L,10'.
+pub
+fn join_moves(moves: &[Move]) -> String {
+  moves.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(",")
+}
+
+/// A main routine calling movement functions A, B and C, by index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub
+struct Routines {
+  pub main: Vec<usize>,
+  pub functions: Vec<Vec<Move>>,
+}
+
+impl Routines {
+  pub
+  fn main_text(&self) -> String {
+    let names: Vec<String> = self.main.iter().map(|x| ((b'A' + *x as u8) as char).to_string()).collect();
+    names.join(",")
+  }
+
+  /// The moves the main routine makes.
+  pub
+  fn expand(&self) -> Vec<Move> {
+    self.main.iter().flat_map(|x| self.functions[*x].iter().copied()).collect()
+  }
+}
+
+impl fmt::Display for Routines {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "main: {}", self.main_text())?;
+    for (index, function) in self.functions.iter().enumerate() {
+      writeln!(f, "{}: {}", (b'A' + index as u8) as char, join_moves(function))?;
+    }
+    Ok(())
+  }
+}
+
+fn compress_from(path: &[Move], routines: &mut Routines, max_functions: usize, max_length: usize) -> bool {
+  if path.is_empty() {
+    return true
+  }
+  // each call is a letter and a comma
+  if (routines.main.len() + 1) * 2 - 1 > max_length {
+    return false
+  }
+  for index in 0..routines.functions.len() {
+    let length = routines.functions[index].len();
+    if path.starts_with(&routines.functions[index]) {
+      routines.main.push(index);
+      if compress_from(&path[length..], routines, max_functions, max_length) {
+        return true
+      }
+      routines.main.pop();
+    }
+  }
+  if routines.functions.len() < max_functions {
+    for length in (1..=path.len()).rev() {
+      if join_moves(&path[..length]).len() > max_length {
+        continue;
+      }
+      routines.main.push(routines.functions.len());
+      routines.functions.push(path[..length].to_vec());
+      if compress_from(&path[length..], routines, max_functions, max_length) {
+        return true
+      }
+      routines.functions.pop();
+      routines.main.pop();
+    }
+  }
+  false
+}
+
+/// Split the path into a main routine and at most `max_functions`
+/// functions, each no longer than `max_length` characters, preferring
+/// longer functions.
+pub
+fn compress_path(path: &[Move], max_functions: usize, max_length: usize) -> Option<Routines> {
+  let mut routines = Routines::default();
+  if compress_from(path, &mut routines, max_functions, max_length) {
+    Some(routines)
+  } else {
+    None
+  }
+}
+
+/// What the camera program prints when run as is.
+pub
+fn camera_view(program: Program) -> String {
+  let (status, output) = AsciiMachine::new(program).run();
+  if status != AsciiStatus::Halted {
+    panic!("expected the camera program to halt, it stopped with '{:?}'", status);
+  }
+  output.text
+}
+
+/// Wake the robot up, by setting address 0 to 2, and send it the routines
+/// and whether to show a video feed. The dust collected is the last value
+/// outside of ASCII in the output, if the routines were accepted.
+pub
+fn run_scaffold(mut program: Program, routines: &Routines, video: bool) -> AsciiOutput {
+  program.instructions[0] = 2;
+  let mut machine = AsciiMachine::new(program);
+  machine.send_line(&routines.main_text());
+  for index in 0..MAX_FUNCTIONS {
+    machine.send_line(&routines.functions.get(index).map_or(String::new(), |x| join_moves(x)));
+  }
+  machine.send_line(if video { "y" } else { "n" });
+  let (status, output) = machine.run();
+  if status != AsciiStatus::Halted {
+    panic!("expected the vacuum robot program to halt after the routines, it stopped with '{:?}'", status);
+  }
+  output
+}
+
+#[cfg(test)]
+mod scaffold_tests {
+  use super::*;
+  use crate::ascii::encode_line;
+  use crate::instruction_type::InstructionType;
+
+  const CAMERA: &str = "\
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..
+";
+
+  const PATH: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+";
+
+  #[test]
+  fn aligns_intersections() {
+    let map = ScaffoldMap::parse(CAMERA);
+    assert_eq!(map.intersections(), vec![(2, 2), (2, 4), (6, 4), (10, 4)]);
+    assert_eq!(map.alignment_sum(), 76);
+    assert_eq!(map.robot, Some(((10, 6), Direction::Up)));
+  }
+
+  #[test]
+  fn traces_and_compresses_the_path() {
+    let path = ScaffoldMap::parse(PATH).trace_path();
+    assert_eq!(join_moves(&path), "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+    let routines = compress_path(&path, MAX_FUNCTIONS, MAX_ROUTINE_LENGTH).unwrap();
+    assert_eq!(routines.expand(), path);
+    assert!(routines.functions.len() <= MAX_FUNCTIONS);
+    assert!(routines.main_text().len() <= MAX_ROUTINE_LENGTH);
+    assert!(routines.functions.iter().all(|x| join_moves(x).len() <= MAX_ROUTINE_LENGTH));
+    // too short to fit even one move per call
+    assert_eq!(compress_path(&path, 1, 3), None);
+  }
+
+  #[test]
+  fn sends_the_routines() {
+    // sums every input until it reads 'n', then prints the sum
+    let mut program = Program::from_string("1,100,100,100,3,101,1,101,102,102,1008,101,110,103,1006,103,4,4,102,99");
+    program.instructions.resize(104, 0);
+    let routines = Routines { main: vec![0, 0], functions: vec![vec![Move::Right, Move::Forward(8)]] };
+    let sent: InstructionType = ["A,A", "R,8", "", "", "n"].iter().flat_map(|x| encode_line(x)).sum();
+    // the last newline is never read
+    assert_eq!(run_scaffold(program, &routines, false).values, vec![sent - 10]);
+  }
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+
+use super::instruction_type::InstructionType;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+use super::run_intcode_program::run_intcode_program_with_tracer;
+use super::trace::BufferTracer;
+use super::trace::TraceEvent;
+use super::trace::Tracer;
+
+/// An input or output of an interactive session, with the step it happened in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub
+enum SessionEntry {
+  Input { step: usize, value: InstructionType },
+  Output { step: usize, value: InstructionType },
+}
+
+impl fmt::Display for SessionEntry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SessionEntry::Input { step, value } => write!(f, "{} input {}", step, value),
+      SessionEntry::Output { step, value } => write!(f, "{} output {}", step, value),
+    }
+  }
+}
+
+impl SessionEntry {
+  fn from_event(step: usize, event: &TraceEvent) -> Option<SessionEntry> {
+    match event {
+      TraceEvent::Input { value } => Some(SessionEntry::Input { step, value: *value }),
+      TraceEvent::Output { value } => Some(SessionEntry::Output { step, value: *value }),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub
+struct Session {
+  pub entries: Vec<SessionEntry>,
+}
+
+impl Session {
+  /// Parse a session with one entry per line, e.g. '12 input 5', where blank
+  /// lines and lines starting with '#' are ignored.
+  pub
+  fn from_string(input_string: &str) -> Session {
+    let mut entries = Vec::new();
+    for mut line in input_string.lines() {
+      line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let fields: Vec<&str> = line.split_whitespace().collect();
+      if fields.len() != 3 {
+        panic!("expected session entry of the form 'step kind value', got '{}'", line);
+      }
+      let step = fields[0].parse::<usize>().unwrap();
+      let value = fields[2].parse::<InstructionType>().unwrap();
+      entries.push(match fields[1] {
+        "input" => SessionEntry::Input { step, value },
+        "output" => SessionEntry::Output { step, value },
+        kind => panic!("expected session entry kind 'input' or 'output', got '{}'", kind),
+      });
+    }
+    Session { entries }
+  }
+
+  pub
+  fn from_file(input_file: &str) -> Session {
+    let mut file = File::open(input_file).unwrap();
+    let mut content = String::new();
+    file.read_to_string(&mut content).unwrap();
+    Session::from_string(&content)
+  }
+
+  pub
+  fn inputs(&self) -> Vec<InstructionType> {
+    self.entries.iter().filter_map(|entry| match entry {
+      SessionEntry::Input { value, .. } => Some(*value),
+      _ => None,
+    }).collect()
+  }
+
+  pub
+  fn outputs(&self) -> Vec<InstructionType> {
+    self.entries.iter().filter_map(|entry| match entry {
+      SessionEntry::Output { value, .. } => Some(*value),
+      _ => None,
+    }).collect()
+  }
+}
+
+impl fmt::Display for Session {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for entry in &self.entries {
+      writeln!(f, "{}", entry)?;
+    }
+    Ok(())
+  }
+}
+
+/// A tracer which writes each input and output to the writer as it happens,
+/// so the session is kept even if the program or the user aborts it.
+#[derive(Debug)]
+pub
+struct SessionRecorder<W: Write + fmt::Debug> {
+  pub writer: W,
+  /// also print each output to stdout as it happens, for interactive use
+  pub echo: bool,
+}
+
+impl<W: Write + fmt::Debug> Tracer for SessionRecorder<W> {
+  fn event(&mut self, step: usize, event: &TraceEvent) {
+    if let Some(entry) = SessionEntry::from_event(step, event) {
+      writeln!(self.writer, "{}", entry).unwrap();
+      self.writer.flush().unwrap();
+      if let (true, SessionEntry::Output { value, .. }) = (self.echo, entry) {
+        println!("{}", value);
+      }
+    }
+  }
+}
+
+/// Run the program and record every input and output to the session file,
+/// printing the outputs as they happen if echo is set. When inputs is None
+/// the program reads its inputs from stdin.
+pub
+fn record_intcode_program(
+  program: &mut Program,
+  inputs: &Option<Vec<InstructionType>>,
+  session_file: &str,
+  echo: bool,
+) -> Vec<InstructionType>
+{
+  let file = File::create(session_file).unwrap();
+  run_intcode_program_with_tracer(program, inputs, Box::new(SessionRecorder { writer: file, echo }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct ReplayMismatch {
+  /// index of the first session entry which does not match
+  pub index: usize,
+  /// the recorded entry, or None if the replay produced more entries
+  pub expected: Option<SessionEntry>,
+  /// the replayed entry, or None if the replay stopped early
+  pub actual: Option<SessionEntry>,
+}
+
+impl fmt::Display for ReplayMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let show = |entry: &Option<SessionEntry>| match entry {
+      Some(entry) => format!("'{}'", entry),
+      None => "nothing".to_string(),
+    };
+    write!(
+      f,
+      "replay does not match session at entry {}, expected {}, got {}",
+      self.index,
+      show(&self.expected),
+      show(&self.actual))
+  }
+}
+
+/// Run the program, feeding it the recorded inputs, and check that it
+/// produces the same inputs and outputs at the same steps as the session.
+pub
+fn replay_intcode_program(
+  program: &mut Program,
+  session: &Session,
+) -> Result<Vec<InstructionType>, ReplayMismatch>
+{
+  let buffer = BufferTracer::new();
+  {
+    let mut program_instance = ProgramInstance::from_ref(program);
+    *program_instance.context.borrow_mut().inputs.borrow_mut() = Some(session.inputs().into());
+    *program_instance.context.borrow_mut().tracer.borrow_mut() = Some(Box::new(buffer.clone()));
+    loop {
+      match program_instance.step() {
+        Ok((next_counter, _)) => program_instance.context.borrow_mut().counter.set(next_counter),
+        // running out of recorded inputs is reported as a mismatch below
+        Err(StepError::NeedInput) | Err(StepError::EndOfProgram) => break,
+        Err(StepError::Error(msg)) => panic!("unexpected error: {}", msg),
+        Err(StepError::LimitExceeded(limit)) => panic!("unexpected stop by {}", limit),
+      }
+    }
+  }
+  let replayed: Vec<SessionEntry> = buffer.events().iter()
+    .filter_map(|(step, event)| SessionEntry::from_event(*step, event))
+    .collect();
+  for index in 0..replayed.len().max(session.entries.len()) {
+    let expected = session.entries.get(index).cloned();
+    let actual = replayed.get(index).cloned();
+    if expected != actual {
+      return Err(ReplayMismatch { index, expected, actual })
+    }
+  }
+  Ok(Session { entries: replayed }.outputs())
+}
+
+#[cfg(test)]
+mod session_tests {
+  use super::*;
+
+  #[test]
+  fn session_to_and_from_string() {
+    let session = Session::from_string("# comment\n0 input 8\n\n2 output 1\n");
+    assert_eq!(session.entries, vec![
+      SessionEntry::Input { step: 0, value: 8 },
+      SessionEntry::Output { step: 2, value: 1 },
+    ]);
+    assert_eq!(session.to_string(), "0 input 8\n2 output 1\n");
+  }
+
+  #[test]
+  fn recorder_writes_inputs_and_outputs() {
+    let mut recorder = SessionRecorder { writer: Vec::new(), echo: false };
+    recorder.event(0, &TraceEvent::Input { value: 8 });
+    recorder.event(1, &TraceEvent::Write { address: 9, value: 1 });
+    recorder.event(2, &TraceEvent::Output { value: 1 });
+    assert_eq!(String::from_utf8(recorder.writer).unwrap(), "0 input 8\n2 output 1\n");
+  }
+
+  #[test]
+  fn record_then_replay() {
+    let program = Program::from_string("3,11,3,12,1,11,12,13,4,13,99,0,0,0");
+    let session_file = std::env::temp_dir().join("intcode_machine_record_then_replay.txt");
+    let session_file = session_file.to_str().unwrap();
+    let outputs = record_intcode_program(&mut program.clone(), &Some(vec![20, 22]), session_file, false);
+    assert_eq!(outputs, vec![42]);
+    let session = Session::from_file(session_file);
+    assert_eq!(session.to_string(), "0 input 20\n1 input 22\n3 output 42\n");
+    assert_eq!(replay_intcode_program(&mut program.clone(), &session), Ok(vec![42]));
+  }
+
+  #[test]
+  fn replay_matches_session() {
+    let program = Program::from_string("3,9,8,9,10,9,4,9,99,-1,8");
+    let session = Session::from_string("0 input 8\n2 output 1\n");
+    assert_eq!(replay_intcode_program(&mut program.clone(), &session), Ok(vec![1]));
+  }
+
+  #[test]
+  fn replay_reports_mismatch() {
+    let program = Program::from_string("3,9,8,9,10,9,4,9,99,-1,8");
+    let session = Session::from_string("0 input 7\n2 output 1\n");
+    assert_eq!(replay_intcode_program(&mut program.clone(), &session), Err(ReplayMismatch {
+      index: 1,
+      expected: Some(SessionEntry::Output { step: 2, value: 1 }),
+      actual: Some(SessionEntry::Output { step: 2, value: 0 }),
+    }));
+  }
+
+  #[test]
+  fn replay_runs_out_of_input() {
+    let program = Program::from_string("3,11,3,12,1,11,12,13,4,13,99,0,0,0");
+    let session = Session::from_string("0 input 1\n1 input 2\n3 output 3\n");
+    assert_eq!(replay_intcode_program(&mut program.clone(), &session), Ok(vec![3]));
+    let truncated = Session::from_string("0 input 1\n");
+    assert_eq!(replay_intcode_program(&mut program.clone(), &truncated), Ok(vec![]));
+    let expected_more = Session::from_string("0 input 1\n3 output 3\n");
+    assert_eq!(replay_intcode_program(&mut program.clone(), &expected_more), Err(ReplayMismatch {
+      index: 1,
+      expected: Some(SessionEntry::Output { step: 3, value: 3 }),
+      actual: None,
+    }));
+  }
+}
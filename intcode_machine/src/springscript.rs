@@ -0,0 +1,471 @@
+use std::fmt;
+
+use super::ascii::AsciiMachine;
+use super::ascii::AsciiStatus;
+use super::instruction_type::InstructionType;
+use super::program::Program;
+
+/// The most instructions the springdroid accepts.
+pub
+const MAX_INSTRUCTIONS: usize = 15;
+
+/// The sensors A to I, each true if there is ground that many tiles ahead,
+/// and the writable registers T and J.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Register {
+  Sensor(u8),
+  T,
+  J,
+}
+
+impl Register {
+  fn parse(name: &str) -> Option<Register> {
+    match name {
+      "T" => Some(Register::T),
+      "J" => Some(Register::J),
+      x if x.len() == 1 && (b'A'..=b'I').contains(&x.as_bytes()[0]) => Some(Register::Sensor(x.as_bytes()[0] - b'A' + 1)),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for Register {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Register::Sensor(distance) => write!(f, "{}", (b'A' + distance - 1) as char),
+      Register::T => write!(f, "T"),
+      Register::J => write!(f, "J"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum Gate {
+  And,
+  Or,
+  Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+struct SpringInstruction {
+  pub gate: Gate,
+  pub input: Register,
+  pub output: Register,
+}
+
+impl fmt::Display for SpringInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let gate = match self.gate {
+      Gate::And => "AND",
+      Gate::Or => "OR",
+      Gate::Not => "NOT",
+    };
+    write!(f, "{} {} {}", gate, self.input, self.output)
+  }
+}
+
+/// WALK lets the droid read the sensors A to D, RUN all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub
+enum SpringMode {
+  Walk,
+  Run,
+}
+
+impl SpringMode {
+  pub
+  fn sensors(self) -> u8 {
+    match self {
+      SpringMode::Walk => 4,
+      SpringMode::Run => 9,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+enum SpringscriptError {
+  /// with the line number, from 1
+  Syntax(usize, String),
+  MissingMode,
+  TooManyInstructions(usize),
+  /// a sensor the mode cannot read, with the line number
+  UnavailableSensor(usize, Register),
+}
+
+impl fmt::Display for SpringscriptError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SpringscriptError::Syntax(line, message) => write!(f, "line {}: {}", line, message),
+      SpringscriptError::MissingMode => write!(f, "expected the script to end with WALK or RUN"),
+      SpringscriptError::TooManyInstructions(count) => write!(
+        f, "expected at most {} instructions, found {}", MAX_INSTRUCTIONS, count),
+      SpringscriptError::UnavailableSensor(line, register) => write!(
+        f, "line {}: sensor {} is only available with RUN", line, register),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct Springscript {
+  pub instructions: Vec<SpringInstruction>,
+  pub mode: SpringMode,
+}
+
+impl Springscript {
+  /// Parse and check a script, one instruction per line, ending with WALK
+  /// or RUN. Blank lines are ignored.
+  pub
+  fn parse(source: &str) -> Result<Springscript, SpringscriptError> {
+    let mut instructions = Vec::new();
+    let mut mode = None;
+    for (index, line) in source.lines().enumerate() {
+      let words: Vec<&str> = line.split_whitespace().collect();
+      let syntax_error = |message: String| Err(SpringscriptError::Syntax(index + 1, message));
+      if words.is_empty() {
+        continue;
+      }
+      if mode.is_some() {
+        return syntax_error(format!("unexpected '{}' after WALK or RUN", line.trim()))
+      }
+      let gate = match words[0] {
+        "WALK" | "RUN" if words.len() == 1 => {
+          mode = Some(if words[0] == "WALK" { SpringMode::Walk } else { SpringMode::Run });
+          continue;
+        },
+        "AND" => Gate::And,
+        "OR" => Gate::Or,
+        "NOT" => Gate::Not,
+        x => return syntax_error(format!("unknown instruction '{}'", x)),
+      };
+      if words.len() != 3 {
+        return syntax_error(format!("expected two registers, found '{}'", line.trim()))
+      }
+      let input = match Register::parse(words[1]) {
+        Some(input) => input,
+        None => return syntax_error(format!("unknown register '{}'", words[1])),
+      };
+      let output = match Register::parse(words[2]) {
+        Some(output @ Register::T) | Some(output @ Register::J) => output,
+        _ => return syntax_error(format!("expected T or J to be written, found '{}'", words[2])),
+      };
+      instructions.push(SpringInstruction { gate, input, output });
+    }
+    let script = Springscript { instructions, mode: mode.ok_or(SpringscriptError::MissingMode)? };
+    script.check()?;
+    Ok(script)
+  }
+
+  /// Check the instruction limit and that only the sensors of the mode are
+  /// read.
+  pub
+  fn check(&self) -> Result<(), SpringscriptError> {
+    if self.instructions.len() > MAX_INSTRUCTIONS {
+      return Err(SpringscriptError::TooManyInstructions(self.instructions.len()))
+    }
+    for (index, instruction) in self.instructions.iter().enumerate() {
+      if let Register::Sensor(distance) = instruction.input {
+        if distance > self.mode.sensors() {
+          return Err(SpringscriptError::UnavailableSensor(index + 1, instruction.input))
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Whether the droid jumps, given whether there is ground at each distance
+  /// ahead, from one.
+  pub
+  fn evaluate(&self, ground: &[bool]) -> bool {
+    let (mut t, mut j) = (false, false);
+    for instruction in &self.instructions {
+      let input = match instruction.input {
+        Register::Sensor(distance) => ground[distance as usize - 1],
+        Register::T => t,
+        Register::J => j,
+      };
+      let output = if instruction.output == Register::T { &mut t } else { &mut j };
+      *output = match instruction.gate {
+        Gate::And => input && *output,
+        Gate::Or => input || *output,
+        Gate::Not => !input,
+      };
+    }
+    j
+  }
+
+  /// Whether the droid makes it across the hull, starting at the position.
+  /// Past the end of the hull there is always ground.
+  pub
+  fn survives(&self, hull: &[bool], start: usize) -> bool {
+    let mut position = start;
+    while position < hull.len() {
+      if !hull[position] {
+        return false
+      }
+      let ground: Vec<bool> = (1..=self.mode.sensors() as usize)
+        .map(|distance| hull.get(position + distance).cloned().unwrap_or(true))
+        .collect();
+      position += if self.evaluate(&ground) { 4 } else { 1 };
+    }
+    true
+  }
+}
+
+impl fmt::Display for Springscript {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for instruction in &self.instructions {
+      writeln!(f, "{}", instruction)?;
+    }
+    writeln!(f, "{}", if self.mode == SpringMode::Walk { "WALK" } else { "RUN" })
+  }
+}
+
+/// One frame of the animation the program prints when the droid falls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+struct SpringFrame {
+  pub rows: Vec<String>,
+  /// the column and row of the droid, if it is in view
+  pub droid: Option<(usize, usize)>,
+  /// whether each column of the bottom row is ground
+  pub ground: Vec<bool>,
+}
+
+impl SpringFrame {
+  /// The hull from the column of the droid, if it is in view.
+  pub
+  fn hull_ahead(&self) -> Option<Vec<bool>> {
+    self.droid.map(|(column, _)| self.ground[column..].to_vec())
+  }
+}
+
+/// Parse the frames from the text printed when the droid falls, which are
+/// blocks of rows of '.', '#' and '@' separated by blank lines.
+pub
+fn parse_failure_frames(text: &str) -> Vec<SpringFrame> {
+  let mut frames = Vec::new();
+  for block in text.split("\n\n") {
+    let rows: Vec<String> = block.lines().map(|x| x.to_string()).filter(|x| !x.is_empty()).collect();
+    let is_frame = !rows.is_empty()
+      && rows.iter().all(|row| row.len() == rows[0].len() && row.chars().all(|x| ".#@".contains(x)))
+      && rows.last().unwrap().contains('#');
+    if !is_frame {
+      continue;
+    }
+    let droid = rows.iter().enumerate().find_map(|(y, row)| row.find('@').map(|x| (x, y)));
+    let ground = rows.last().unwrap().chars().map(|x| x == '#').collect();
+    frames.push(SpringFrame { rows, droid, ground });
+  }
+  frames
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub
+enum SpringdroidOutcome {
+  /// the droid made it across, and reported the amount of hull damage
+  HullDamage(InstructionType),
+  /// the droid fell, with the frames of its last moments and all the text
+  Fell(Vec<SpringFrame>, String),
+}
+
+/// Check the script and send it to the springdroid program, after its
+/// prompt.
+pub
+fn run_springscript(program: Program, script: &Springscript) -> Result<SpringdroidOutcome, SpringscriptError> {
+  script.check()?;
+  let mut machine = AsciiMachine::new(program);
+  let (mut status, mut output) = machine.run();
+  for line in script.to_string().lines() {
+    if status == AsciiStatus::Halted {
+      break;
+    }
+    machine.send_line(line);
+    let (next_status, next_output) = machine.run();
+    status = next_status;
+    output.text += &next_output.text;
+    output.values.extend(next_output.values);
+  }
+  if status == AsciiStatus::NeedInput {
+    panic!("expected the springdroid program to halt after the script, it asked for more input");
+  }
+  Ok(match output.values.first() {
+    Some(damage) => SpringdroidOutcome::HullDamage(*damage),
+    None => SpringdroidOutcome::Fell(parse_failure_frames(&output.text), output.text),
+  })
+}
+
+// every instruction which can be written in the mode
+fn instruction_choices(mode: SpringMode) -> Vec<SpringInstruction> {
+  let mut inputs: Vec<Register> = (1..=mode.sensors()).map(Register::Sensor).collect();
+  inputs.push(Register::T);
+  inputs.push(Register::J);
+  let mut choices = Vec::new();
+  for gate in &[Gate::Not, Gate::And, Gate::Or] {
+    for input in &inputs {
+      for output in &[Register::J, Register::T] {
+        choices.push(SpringInstruction { gate: *gate, input: *input, output: *output });
+      }
+    }
+  }
+  choices
+}
+
+/// Search for a script of at most `max_instructions` which gets the droid
+/// across, shortest first.
+///
+/// Each candidate is first checked against the hulls the droid has already
+/// fallen on, and only tried with `attempt` if it would survive them all,
+/// so few runs of the program are needed. Returns the script and the hull
+/// damage it reported.
+pub
+fn synthesize_springscript<F: FnMut(&Springscript) -> SpringdroidOutcome>(
+  mode: SpringMode,
+  max_instructions: usize,
+  mut attempt: F,
+) -> Option<(Springscript, InstructionType)>
+{
+  let choices = instruction_choices(mode);
+  let mut hulls: Vec<Vec<bool>> = Vec::new();
+  for length in 1..=max_instructions.min(MAX_INSTRUCTIONS) {
+    let mut indices = vec![0; length];
+    loop {
+      let script = Springscript { instructions: indices.iter().map(|x| choices[*x]).collect(), mode };
+      // the droid never jumps unless J is written
+      let writes_j = script.instructions.iter().any(|x| x.output == Register::J);
+      if writes_j && hulls.iter().all(|hull| script.survives(hull, 0)) {
+        match attempt(&script) {
+          SpringdroidOutcome::HullDamage(damage) => return Some((script, damage)),
+          SpringdroidOutcome::Fell(frames, _) => {
+            match frames.first().and_then(|x| x.hull_ahead()) {
+              Some(hull) if !hulls.contains(&hull) => hulls.push(hull),
+              // nothing new was learned, so the search cannot progress
+              _ => return None,
+            }
+          },
+        }
+      }
+      // the next combination, counting with the first instruction fastest
+      let mut position = 0;
+      while position < length && indices[position] == choices.len() - 1 {
+        indices[position] = 0;
+        position += 1;
+      }
+      if position == length {
+        break;
+      }
+      indices[position] += 1;
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod springscript_tests {
+  use super::*;
+  use crate::ascii::encode_line;
+
+  fn hull(text: &str) -> Vec<bool> {
+    text.chars().map(|x| x == '#').collect()
+  }
+
+  const FAILURE: &str = "\
+Input instructions:
+
+Walking...
+
+
+Didn't make it across:
+
+.................
+.................
+@................
+#####.###########
+
+.................
+.................
+.@...............
+#####.###########
+
+";
+
+  #[test]
+  fn parses_and_prints_scripts() {
+    let source = "NOT A J\nNOT B T\nOR T J\n\nAND D J\nWALK\n";
+    let script = Springscript::parse(source).unwrap();
+    assert_eq!(script.instructions.len(), 4);
+    assert_eq!(script.instructions[1], SpringInstruction { gate: Gate::Not, input: Register::Sensor(2), output: Register::T });
+    assert_eq!(script.to_string(), "NOT A J\nNOT B T\nOR T J\nAND D J\nWALK\n");
+  }
+
+  #[test]
+  fn rejects_invalid_scripts() {
+    assert_eq!(Springscript::parse("NOT A J\n"), Err(SpringscriptError::MissingMode));
+    assert_eq!(Springscript::parse("NOT A B\nWALK"), Err(SpringscriptError::Syntax(1, String::from(
+      "expected T or J to be written, found 'B'"))));
+    assert_eq!(Springscript::parse("OR J T\nNOT E J\nWALK"), Err(SpringscriptError::UnavailableSensor(2, Register::Sensor(5))));
+    assert!(Springscript::parse("NOT E J\nRUN").is_ok());
+    let too_long = "NOT A J\n".repeat(16) + "WALK";
+    assert_eq!(Springscript::parse(&too_long), Err(SpringscriptError::TooManyInstructions(16)));
+  }
+
+  #[test]
+  fn simulates_the_droid() {
+    let script = Springscript::parse("NOT A J\nWALK").unwrap();
+    assert!(script.survives(&hull("#####.###########"), 0));
+    assert!(!script.survives(&hull("#####..#.########"), 0));
+  }
+
+  #[test]
+  fn parses_failure_frames() {
+    let frames = parse_failure_frames(FAILURE);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].droid, Some((1, 2)));
+    assert_eq!(frames[0].hull_ahead(), Some(hull("#####.###########")));
+  }
+
+  #[test]
+  fn runs_script_after_prompt() {
+    // prints a prompt, reads until the K of WALK, then prints the failure
+    let mut program: Vec<String> = encode_line("Input instructions:").iter().map(|x| format!("104,{}", x)).collect();
+    let read = program.len() * 2;
+    program.push(format!("3,0,1008,0,75,1,1006,1,{}", read));
+    for x in FAILURE.split("Input instructions:\n").nth(1).unwrap().chars() {
+      program.push(format!("104,{}", x as u32));
+    }
+    program.push(String::from("99"));
+    let script = Springscript::parse("NOT A J\nWALK").unwrap();
+    match run_springscript(Program::from_string(&program.join(",")), &script).unwrap() {
+      SpringdroidOutcome::Fell(frames, text) => {
+        assert_eq!(frames.len(), 2);
+        assert!(text.starts_with("Input instructions:\n"));
+      },
+      outcome => panic!("expected the droid to fall, got {:?}", outcome),
+    }
+  }
+
+  #[test]
+  fn synthesizes_script_from_failures() {
+    let hidden = [hull("#####.###########"), hull("#####...#########"), hull("#####..#.########")];
+    let mut attempts = 0;
+    let found = synthesize_springscript(SpringMode::Walk, 4, |script| {
+      attempts += 1;
+      match hidden.iter().find(|x| !script.survives(x, 0)) {
+        Some(hull) => SpringdroidOutcome::Fell(vec![SpringFrame {
+          rows: vec![],
+          droid: Some((0, 0)),
+          ground: hull.clone(),
+        }], String::new()),
+        None => SpringdroidOutcome::HullDamage(19_352_638),
+      }
+    });
+    let (script, damage) = found.unwrap();
+    assert_eq!(damage, 19_352_638);
+    assert!(hidden.iter().all(|x| script.survives(x, 0)));
+    assert!(attempts <= hidden.len() + 1);
+  }
+}
@@ -0,0 +1,888 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::panic;
+use std::rc::Rc;
+
+use super::instruction_type::InstructionType;
+use super::operation_instance::try_parse_operation_intcode;
+use super::operation_registry::standard_registry;
+use super::parameter_mode::ParameterMode;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+
+/// A value which is unknown when the program starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub
+enum Symbol {
+  /// the initial value at a memory address, e.g. the noun and verb of day 2
+  Memory(usize),
+  /// the nth value read by INPUT
+  Input(usize),
+}
+
+impl fmt::Display for Symbol {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Symbol::Memory(address) => write!(f, "mem{}", address),
+      Symbol::Input(index) => write!(f, "input{}", index),
+    }
+  }
+}
+
+/// The inclusive range of values a symbol may take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub
+struct SymbolDomain {
+  pub symbol: Symbol,
+  pub min: InstructionType,
+  pub max: InstructionType,
+}
+
+pub
+type Assignment = BTreeMap<Symbol, InstructionType>;
+
+/// The most values of a symbol the solver will try one by one. For wider
+/// domains it only tries the bounds and the values around the constants in
+/// the constraints, and if none of those satisfy them, it can't decide.
+pub
+const MAX_ENUMERATED_VALUES: i64 = 1 << 24;
+
+/// The result of solving constraints.
+#[derive(Debug, Clone, PartialEq)]
+pub
+enum SolverResult {
+  Solved(Assignment),
+  /// no values satisfy the constraints
+  Unsatisfiable,
+  /// a domain was too wide to rule out every value
+  Unsupported,
+}
+
+impl SolverResult {
+  pub
+  fn assignment(self) -> Option<Assignment> {
+    match self {
+      SolverResult::Solved(assignment) => Some(assignment),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+enum Expression {
+  Constant(InstructionType),
+  Symbol(Symbol),
+  Add(Rc<Expression>, Rc<Expression>),
+  Multiply(Rc<Expression>, Rc<Expression>),
+  LessThan(Rc<Expression>, Rc<Expression>),
+  Equals(Rc<Expression>, Rc<Expression>),
+  /// the value at a symbolic address, in memory as it was when it was read
+  Load(Rc<Vec<Expression>>, Rc<Expression>),
+}
+
+impl fmt::Display for Expression {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Expression::Constant(x) => write!(f, "{}", x),
+      Expression::Symbol(x) => write!(f, "{}", x),
+      Expression::Add(x, y) => write!(f, "({} + {})", x, y),
+      Expression::Multiply(x, y) => write!(f, "({} * {})", x, y),
+      Expression::LessThan(x, y) => write!(f, "({} < {})", x, y),
+      Expression::Equals(x, y) => write!(f, "({} == {})", x, y),
+      Expression::Load(_, address) => write!(f, "[{}]", address),
+    }
+  }
+}
+
+impl Expression {
+  /// Combine two expressions with ADD, MULTIPLY, LESS-THAN or EQUALS, folding
+  /// constants, or None if folding overflows.
+  pub
+  fn binary(opcode: InstructionType, left: Expression, right: Expression) -> Option<Expression> {
+    if let (Some(x), Some(y)) = (left.constant(), right.constant()) {
+      return Some(Expression::Constant(apply(opcode, x, y)?))
+    }
+    let (left, right) = (Rc::new(left), Rc::new(right));
+    Some(match opcode {
+      1 => Expression::Add(left, right),
+      2 => Expression::Multiply(left, right),
+      7 => Expression::LessThan(left, right),
+      8 => Expression::Equals(left, right),
+      _ => panic!("expected the opcode of a binary operation, got '{}'", opcode),
+    })
+  }
+
+  pub
+  fn constant(&self) -> Option<InstructionType> {
+    match self {
+      Expression::Constant(x) => Some(*x),
+      _ => None,
+    }
+  }
+
+  /// The value of the expression, or None if a symbol is unassigned, the
+  /// arithmetic overflows or a load is out of range, all of which would
+  /// stop the real program.
+  pub
+  fn evaluate(&self, assignment: &Assignment) -> Option<InstructionType> {
+    match self {
+      Expression::Constant(x) => Some(*x),
+      Expression::Symbol(x) => assignment.get(x).cloned(),
+      Expression::Add(x, y) => apply(1, x.evaluate(assignment)?, y.evaluate(assignment)?),
+      Expression::Multiply(x, y) => apply(2, x.evaluate(assignment)?, y.evaluate(assignment)?),
+      Expression::LessThan(x, y) => apply(7, x.evaluate(assignment)?, y.evaluate(assignment)?),
+      Expression::Equals(x, y) => apply(8, x.evaluate(assignment)?, y.evaluate(assignment)?),
+      Expression::Load(memory, address) => {
+        let address = address.evaluate(assignment)?;
+        if address < 0 {
+          return None
+        }
+        memory.get(address as usize)?.evaluate(assignment)
+      },
+    }
+  }
+
+  fn collect_symbols(&self, symbols: &mut BTreeSet<Symbol>) {
+    match self {
+      Expression::Constant(_) => (),
+      Expression::Symbol(x) => {
+        symbols.insert(*x);
+      },
+      Expression::Add(x, y) | Expression::Multiply(x, y) | Expression::LessThan(x, y) | Expression::Equals(x, y) => {
+        x.collect_symbols(symbols);
+        y.collect_symbols(symbols);
+      },
+      Expression::Load(memory, address) => {
+        address.collect_symbols(symbols);
+        for value in memory.iter() {
+          value.collect_symbols(symbols);
+        }
+      },
+    }
+  }
+
+  // the constants the expression computes with, leaving out the memory of loads
+  fn collect_constants(&self, constants: &mut BTreeSet<InstructionType>) {
+    match self {
+      Expression::Constant(x) => {
+        constants.insert(*x);
+      },
+      Expression::Symbol(_) => (),
+      Expression::Add(x, y) | Expression::Multiply(x, y) | Expression::LessThan(x, y) | Expression::Equals(x, y) => {
+        x.collect_constants(constants);
+        y.collect_constants(constants);
+      },
+      Expression::Load(_, address) => address.collect_constants(constants),
+    }
+  }
+
+  /// The symbols the value of the expression depends on.
+  pub
+  fn symbols(&self) -> BTreeSet<Symbol> {
+    let mut symbols = BTreeSet::new();
+    self.collect_symbols(&mut symbols);
+    symbols
+  }
+
+  // the degree of the expression as a polynomial in the symbol, or None if it
+  // is not a polynomial in it
+  fn degree(&self, symbol: Symbol) -> Option<u32> {
+    match self {
+      Expression::Constant(_) => Some(0),
+      Expression::Symbol(x) => Some(if *x == symbol { 1 } else { 0 }),
+      Expression::Add(x, y) => Some(x.degree(symbol)?.max(y.degree(symbol)?)),
+      Expression::Multiply(x, y) => Some(x.degree(symbol)? + y.degree(symbol)?),
+      _ if self.symbols().contains(&symbol) => None,
+      _ => Some(0),
+    }
+  }
+}
+
+fn apply(opcode: InstructionType, x: InstructionType, y: InstructionType) -> Option<InstructionType> {
+  match opcode {
+    1 => x.checked_add(y),
+    2 => x.checked_mul(y),
+    7 => Some((x < y) as InstructionType),
+    _ => Some((x == y) as InstructionType),
+  }
+}
+
+/// A condition which must be non-zero, if expected is true, or zero, for a
+/// path to be followed.
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct Constraint {
+  pub condition: Expression,
+  pub expected: bool,
+}
+
+impl Constraint {
+  fn holds(&self, assignment: &Assignment) -> bool {
+    match self.condition.evaluate(assignment) {
+      Some(value) => (value != 0) == self.expected,
+      None => false,
+    }
+  }
+}
+
+impl fmt::Display for Constraint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.expected {
+      write!(f, "{} != 0", self.condition)
+    } else {
+      write!(f, "{} == 0", self.condition)
+    }
+  }
+}
+
+/// Find values for the symbols, within their domains, which satisfy all the
+/// constraints.
+///
+/// The search assigns one symbol at a time and checks each constraint as
+/// soon as its symbols are assigned. The last symbol of an equality which is
+/// linear in it is solved for directly rather than enumerated, and domains
+/// wider than `MAX_ENUMERATED_VALUES` are only partly tried.
+pub
+fn solve_constraints(constraints: &[Constraint], domains: &[SymbolDomain]) -> SolverResult {
+  let mut symbols = BTreeSet::new();
+  for constraint in constraints {
+    constraint.condition.collect_symbols(&mut symbols);
+  }
+  let symbols: Vec<Symbol> = symbols.into_iter().collect();
+  let domains = symbols.iter()
+    .map(|symbol| domains.iter().find(|x| x.symbol == *symbol).cloned())
+    .collect::<Option<Vec<SymbolDomain>>>();
+  let domains = match domains {
+    Some(domains) => domains,
+    None => return SolverResult::Unsatisfiable,
+  };
+  // each constraint is checked once the last of its symbols is assigned
+  let mut checks: Vec<Vec<&Constraint>> = vec![vec![]; symbols.len() + 1];
+  for constraint in constraints {
+    let last = constraint.condition.symbols().iter()
+      .map(|x| symbols.iter().position(|y| y == x).unwrap() + 1)
+      .max()
+      .unwrap_or(0);
+    checks[last].push(constraint);
+  }
+  let mut assignment = Assignment::new();
+  if !checks[0].iter().all(|x| x.holds(&assignment)) {
+    return SolverResult::Unsatisfiable
+  }
+  match search(&symbols, &domains, &checks, &mut assignment) {
+    Some(true) => SolverResult::Solved(assignment),
+    Some(false) => SolverResult::Unsatisfiable,
+    None => SolverResult::Unsupported,
+  }
+}
+
+// the values to try for a symbol whose domain is too wide to enumerate: the
+// bounds, zero and the values around the constants it is compared with
+fn boundary_values(domain: &SymbolDomain, constraints: &[&Constraint]) -> Vec<InstructionType> {
+  let mut constants = BTreeSet::new();
+  for constraint in constraints {
+    constraint.condition.collect_constants(&mut constants);
+  }
+  let mut values: BTreeSet<i64> = BTreeSet::new();
+  values.extend(&[i64::from(domain.min), i64::from(domain.max), 0]);
+  for constant in constants {
+    values.extend(&[i64::from(constant) - 1, i64::from(constant), i64::from(constant) + 1]);
+  }
+  values.into_iter()
+    .filter(|x| *x >= i64::from(domain.min) && *x <= i64::from(domain.max))
+    .map(|x| x as InstructionType)
+    .collect()
+}
+
+// true if the remaining symbols can be assigned, false if they can't, or
+// None if that is unknown as a domain was too wide to try every value
+fn search(
+  symbols: &[Symbol],
+  domains: &[SymbolDomain],
+  checks: &[Vec<&Constraint>],
+  assignment: &mut Assignment,
+) -> Option<bool>
+{
+  let index = assignment.len();
+  if index == symbols.len() {
+    return Some(true)
+  }
+  let symbol = symbols[index];
+  let domain = domains[index];
+  let mut complete = true;
+  let candidates: Box<dyn Iterator<Item = InstructionType>> = match solve_linear(symbol, &checks[index + 1], assignment) {
+    Some(value) => Box::new(value.into_iter().filter(|x| *x >= domain.min && *x <= domain.max)),
+    None if i64::from(domain.max) - i64::from(domain.min) + 1 > MAX_ENUMERATED_VALUES => {
+      complete = false;
+      Box::new(boundary_values(&domain, &checks[index + 1]).into_iter())
+    },
+    None => Box::new(domain.min..=domain.max),
+  };
+  for value in candidates {
+    assignment.insert(symbol, value);
+    if checks[index + 1].iter().all(|x| x.holds(assignment)) {
+      match search(symbols, domains, checks, assignment) {
+        Some(true) => return Some(true),
+        Some(false) => (),
+        None => complete = false,
+      }
+    }
+  }
+  assignment.remove(&symbol);
+  if complete { Some(false) } else { None }
+}
+
+// the only value of the symbol, if any, which can satisfy an equality of the
+// form a * symbol + b == c * symbol + d, or None if there is no such equality
+fn solve_linear(
+  symbol: Symbol,
+  constraints: &[&Constraint],
+  assignment: &mut Assignment,
+) -> Option<Option<InstructionType>>
+{
+  for constraint in constraints.iter().filter(|x| x.expected) {
+    let (left, right) = match &constraint.condition {
+      Expression::Equals(left, right) => (left, right),
+      _ => continue,
+    };
+    if left.degree(symbol).is_none_or(|x| x > 1) || right.degree(symbol).is_none_or(|x| x > 1) {
+      continue;
+    }
+    let mut difference_at = |value: InstructionType| -> Option<i64> {
+      assignment.insert(symbol, value);
+      let difference = i64::from(left.evaluate(assignment)?) - i64::from(right.evaluate(assignment)?);
+      Some(difference)
+    };
+    let (at_zero, at_one) = match (difference_at(0), difference_at(1)) {
+      (Some(x), Some(y)) => (x, y),
+      _ => continue,
+    };
+    assignment.remove(&symbol);
+    let slope = at_one - at_zero;
+    if slope == 0 {
+      continue;
+    }
+    if at_zero % slope != 0 {
+      return Some(None)
+    }
+    return Some(InstructionType::try_from(-at_zero / slope).ok())
+  }
+  assignment.remove(&symbol);
+  None
+}
+
+/// What a symbolic run should find values for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub
+enum SymbolicGoal {
+  /// any output equal to the value
+  Output(InstructionType),
+  /// execution of the instruction at the address
+  Address(usize),
+  /// the value at the address when the program halts, as in day 2
+  Memory { address: usize, value: InstructionType },
+}
+
+/// How a path through the program ended.
+#[derive(Debug, Clone, PartialEq)]
+pub
+enum PathEnd {
+  Halted,
+  /// input was needed beyond the concrete inputs and input symbols
+  NeedInput,
+  StepLimit,
+  Fault(&'static str),
+  /// the path depends on a symbol in a way the executor does not follow,
+  /// e.g. a symbolic jump destination
+  Unsupported(&'static str),
+}
+
+/// A feasible path through the program, and the state at its end.
+#[derive(Debug, Clone)]
+pub
+struct SymbolicPath {
+  pub end: PathEnd,
+  pub counter: usize,
+  /// the number of inputs read, concrete and symbolic
+  pub inputs_read: usize,
+  pub constraints: Vec<Constraint>,
+  pub outputs: Vec<Expression>,
+  pub memory: Vec<Expression>,
+}
+
+enum Step {
+  Continue,
+  End(PathEnd),
+  /// the other side of a branch, already ended if other_end is set, and the
+  /// end of this path, if it ends at the branch
+  Fork { other: SymbolicPath, other_end: Option<PathEnd>, end: Option<PathEnd> },
+}
+
+const UNDECIDED: PathEnd = PathEnd::Unsupported("constraints too wide to decide");
+
+struct Explorer<'a> {
+  inputs: &'a [InstructionType],
+  domains: &'a [SymbolDomain],
+}
+
+impl<'a> Explorer<'a> {
+  fn solve(&self, constraints: &[Constraint]) -> SolverResult {
+    solve_constraints(constraints, self.domains)
+  }
+
+  fn parameter(&self, path: &mut SymbolicPath, mode: ParameterMode, index: usize) -> Result<Expression, PathEnd> {
+    let raw = path.memory[path.counter + index].clone();
+    if mode == ParameterMode::ImmediateMode {
+      return Ok(raw)
+    }
+    match raw.constant() {
+      Some(address) if address >= 0 && (address as usize) < path.memory.len() =>
+        Ok(path.memory[address as usize].clone()),
+      Some(_) => Err(PathEnd::Fault("address out of range")),
+      None => {
+        // the load is only valid for addresses in the program
+        let length = Expression::Constant(path.memory.len() as InstructionType);
+        path.constraints.push(Constraint {
+          condition: Expression::binary(7, raw.clone(), length).unwrap(),
+          expected: true,
+        });
+        path.constraints.push(Constraint {
+          condition: Expression::binary(7, raw.clone(), Expression::Constant(0)).unwrap(),
+          expected: false,
+        });
+        Ok(Expression::Load(Rc::new(path.memory.clone()), Rc::new(raw)))
+      },
+    }
+  }
+
+  fn destination(&self, path: &SymbolicPath, index: usize) -> Result<usize, PathEnd> {
+    match path.memory[path.counter + index].constant() {
+      Some(address) if address >= 0 && (address as usize) < path.memory.len() => Ok(address as usize),
+      Some(_) => Err(PathEnd::Fault("address out of range")),
+      None => Err(PathEnd::Unsupported("symbolic write address")),
+    }
+  }
+
+  fn step(&self, path: &mut SymbolicPath) -> Result<Step, PathEnd> {
+    if path.counter >= path.memory.len() {
+      return Ok(Step::End(PathEnd::Halted))
+    }
+    let counter = path.counter;
+    let intcode = path.memory[counter].constant().ok_or(PathEnd::Unsupported("symbolic instruction"))?;
+    let instance = try_parse_operation_intcode(intcode).ok_or(PathEnd::Fault("invalid instruction"))?;
+    let operation = standard_registry().get(instance.opcode).ok_or(PathEnd::Fault("unknown operation"))?;
+    if counter + operation.size > path.memory.len() {
+      return Err(PathEnd::Fault("truncated instruction"))
+    }
+    let next = counter + operation.size;
+    match instance.opcode {
+      1 | 2 | 7 | 8 => {
+        let left = self.parameter(path, instance.parameter1_mode, 1)?;
+        let right = self.parameter(path, instance.parameter2_mode, 2)?;
+        let destination = self.destination(path, 3)?;
+        path.memory[destination] = Expression::binary(instance.opcode, left, right)
+          .ok_or(PathEnd::Fault("overflow"))?;
+      },
+      3 => {
+        let destination = self.destination(path, 1)?;
+        let index = path.inputs_read;
+        let value = match self.inputs.get(index) {
+          Some(value) => Expression::Constant(*value),
+          None if self.domains.iter().any(|x| x.symbol == Symbol::Input(index)) =>
+            Expression::Symbol(Symbol::Input(index)),
+          None => return Ok(Step::End(PathEnd::NeedInput)),
+        };
+        path.inputs_read += 1;
+        path.memory[destination] = value;
+      },
+      4 => {
+        let value = self.parameter(path, instance.parameter1_mode, 1)?;
+        path.outputs.push(value);
+      },
+      5 | 6 => {
+        let test = self.parameter(path, instance.parameter1_mode, 1)?;
+        let jump_if_true = instance.opcode == 5;
+        let taken = match test.constant() {
+          Some(value) => (value != 0) == jump_if_true,
+          None => {
+            let mut other = path.clone();
+            other.constraints.push(Constraint { condition: test.clone(), expected: !jump_if_true });
+            other.counter = next;
+            path.constraints.push(Constraint { condition: test, expected: jump_if_true });
+            let fork = self.solve(&other.constraints);
+            let end = match self.solve(&path.constraints) {
+              SolverResult::Unsatisfiable => {
+                // only the fall through is possible
+                *path = other;
+                return Ok(if fork == SolverResult::Unsupported { Step::End(UNDECIDED) } else { Step::Continue })
+              },
+              SolverResult::Unsupported => Some(UNDECIDED),
+              SolverResult::Solved(_) => match self.jump_destination(path) {
+                Ok(destination) => {
+                  path.counter = destination;
+                  None
+                },
+                Err(end) => Some(end),
+              },
+            };
+            return Ok(match (fork, end) {
+              (SolverResult::Unsatisfiable, None) => Step::Continue,
+              (SolverResult::Unsatisfiable, Some(end)) => Step::End(end),
+              (SolverResult::Unsupported, end) => Step::Fork { other, other_end: Some(UNDECIDED), end },
+              (SolverResult::Solved(_), end) => Step::Fork { other, other_end: None, end },
+            })
+          },
+        };
+        path.counter = if taken { self.jump_destination(path)? } else { next };
+        return Ok(Step::Continue)
+      },
+      _ => return Ok(Step::End(PathEnd::Halted)),
+    }
+    path.counter = next;
+    Ok(Step::Continue)
+  }
+
+  fn jump_destination(&self, path: &mut SymbolicPath) -> Result<usize, PathEnd> {
+    let instance = try_parse_operation_intcode(path.memory[path.counter].constant().unwrap()).unwrap();
+    let destination = self.parameter(path, instance.parameter2_mode, 2)?;
+    match destination.constant() {
+      Some(x) if x >= 0 && (x as usize) < path.memory.len() => Ok(x as usize),
+      Some(_) => Err(PathEnd::Fault("jump out of program")),
+      None => Err(PathEnd::Unsupported("symbolic jump destination")),
+    }
+  }
+}
+
+// the constraint under which the goal is met at this point of the path, if
+// it can be met here at all
+fn goal_condition(goal: &SymbolicGoal, path: &SymbolicPath, end: Option<&PathEnd>) -> Option<Expression> {
+  match (goal, end) {
+    (SymbolicGoal::Address(address), None) if path.counter == *address => Some(Expression::Constant(1)),
+    (SymbolicGoal::Memory { address, value }, Some(PathEnd::Halted)) => {
+      Expression::binary(8, path.memory.get(*address)?.clone(), Expression::Constant(*value))
+    },
+    _ => None,
+  }
+}
+
+/// Explore the paths through the program, forking at each jump whose test
+/// depends on a symbol, and stopping early if the goal is met.
+///
+/// The memory symbols replace the initial values at their addresses, which
+/// have to be within the program, and input symbols are used for the inputs
+/// after the concrete ones. At most `max_steps` instructions are executed
+/// over all paths.
+pub
+fn explore_intcode_program(
+  program: &Program,
+  inputs: &[InstructionType],
+  domains: &[SymbolDomain],
+  max_steps: usize,
+) -> Vec<SymbolicPath>
+{
+  explore(program, inputs, domains, max_steps, None).0
+}
+
+fn explore(
+  program: &Program,
+  inputs: &[InstructionType],
+  domains: &[SymbolDomain],
+  max_steps: usize,
+  goal: Option<&SymbolicGoal>,
+) -> (Vec<SymbolicPath>, Option<Assignment>)
+{
+  let mut memory: Vec<Expression> = program.instructions.iter().map(|x| Expression::Constant(*x)).collect();
+  for domain in domains {
+    if let Symbol::Memory(address) = domain.symbol {
+      if address >= memory.len() {
+        panic!("expected the address of {} to be within the program of length {}", domain.symbol, memory.len());
+      }
+      memory[address] = Expression::Symbol(domain.symbol);
+    }
+  }
+  let initial = SymbolicPath {
+    end: PathEnd::StepLimit,
+    counter: 0,
+    inputs_read: 0,
+    constraints: vec![],
+    outputs: vec![],
+    memory,
+  };
+  let explorer = Explorer { inputs, domains };
+  let mut pending = vec![initial];
+  let mut finished = Vec::new();
+  let mut steps = 0;
+  // check whether the goal can be met under the path's constraints
+  let check_goal = |path: &SymbolicPath, condition: Option<Expression>| -> Option<Assignment> {
+    let condition = condition?;
+    let mut constraints = path.constraints.clone();
+    constraints.push(Constraint { condition, expected: true });
+    let assignment = solve_constraints(&constraints, domains).assignment()?;
+    if verify_assignment(program, inputs, domains, &assignment, goal?, max_steps) {
+      Some(assignment)
+    } else {
+      None
+    }
+  };
+  while let Some(mut path) = pending.pop() {
+    let end = loop {
+      if let Some(goal) = goal {
+        if let Some(assignment) = check_goal(&path, goal_condition(goal, &path, None)) {
+          return (finished, Some(assignment))
+        }
+      }
+      if steps >= max_steps {
+        break PathEnd::StepLimit
+      }
+      steps += 1;
+      let outputs = path.outputs.len();
+      match explorer.step(&mut path) {
+        Ok(Step::Continue) => (),
+        Ok(Step::Fork { mut other, other_end, end }) => {
+          match other_end {
+            Some(other_end) => {
+              other.end = other_end;
+              finished.push(other);
+            },
+            None => pending.push(other),
+          }
+          if let Some(end) = end {
+            break end
+          }
+        },
+        Ok(Step::End(end)) | Err(end) => break end,
+      }
+      if let (Some(SymbolicGoal::Output(value)), true) = (goal, path.outputs.len() > outputs) {
+        let output = path.outputs.last().unwrap().clone();
+        if let Some(assignment) = check_goal(&path, Expression::binary(8, output, Expression::Constant(*value))) {
+          return (finished, Some(assignment))
+        }
+      }
+    };
+    if let Some(goal) = goal {
+      if let Some(assignment) = check_goal(&path, goal_condition(goal, &path, Some(&end))) {
+        return (finished, Some(assignment))
+      }
+    }
+    path.end = end;
+    finished.push(path);
+  }
+  (finished, None)
+}
+
+/// Find values for the symbols which make the program meet the goal, or None
+/// if no explored path meets it.
+///
+/// Solutions are checked by running the program concretely before they are
+/// returned.
+pub
+fn solve_intcode_program(
+  program: &Program,
+  inputs: &[InstructionType],
+  domains: &[SymbolDomain],
+  goal: &SymbolicGoal,
+  max_steps: usize,
+) -> Option<Assignment>
+{
+  explore(program, inputs, domains, max_steps, Some(goal)).1
+}
+
+// run the program with the symbols replaced by their values
+fn verify_assignment(
+  program: &Program,
+  inputs: &[InstructionType],
+  domains: &[SymbolDomain],
+  assignment: &Assignment,
+  goal: &SymbolicGoal,
+  max_steps: usize,
+) -> bool
+{
+  let value_of = |symbol: Symbol| -> InstructionType {
+    match assignment.get(&symbol) {
+      Some(value) => *value,
+      None => domains.iter().find(|x| x.symbol == symbol).map_or(0, |x| x.min),
+    }
+  };
+  let mut program = program.clone();
+  let mut inputs = inputs.to_vec();
+  for domain in domains {
+    match domain.symbol {
+      Symbol::Memory(address) => program.instructions[address] = value_of(domain.symbol),
+      Symbol::Input(_) => (),
+    }
+  }
+  let symbolic_inputs = domains.iter().filter_map(|x| match x.symbol {
+    Symbol::Input(index) => Some(index),
+    Symbol::Memory(_) => None,
+  }).max();
+  if let Some(last) = symbolic_inputs {
+    for index in inputs.len()..=last {
+      inputs.push(value_of(Symbol::Input(index)));
+    }
+  }
+  let goal = *goal;
+  // a fault panics in the interpreter, which simply means no solution
+  let result = panic::catch_unwind(move || {
+    let mut program_instance = ProgramInstance::new(program);
+    *program_instance.context.borrow_mut().inputs.borrow_mut() = Some(inputs.into());
+    for _ in 0..max_steps {
+      let counter = program_instance.context.borrow().counter.get();
+      if goal == SymbolicGoal::Address(counter) {
+        return true
+      }
+      match program_instance.step() {
+        Ok((next_counter, output)) => {
+          if output.map(SymbolicGoal::Output) == Some(goal) {
+            return true
+          }
+          program_instance.context.borrow_mut().counter.set(next_counter);
+        },
+        Err(StepError::EndOfProgram) => {
+          let memory = &program_instance.get_program().instructions;
+          return match goal {
+            SymbolicGoal::Memory { address, value } => memory.get(address) == Some(&value),
+            _ => false,
+          }
+        },
+        Err(_) => return false,
+      }
+    }
+    false
+  });
+  result.unwrap_or(false)
+}
+
+#[cfg(test)]
+mod symbolic_tests {
+  use super::*;
+  use crate::run_intcode_program::run_intcode_program;
+
+  fn noun_and_verb() -> Vec<SymbolDomain> {
+    vec![
+      SymbolDomain { symbol: Symbol::Memory(1), min: 0, max: 99 },
+      SymbolDomain { symbol: Symbol::Memory(2), min: 0, max: 99 },
+    ]
+  }
+
+  #[test]
+  fn solves_day02_noun_and_verb() {
+    let program = Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day02.csv"));
+    let goal = SymbolicGoal::Memory { address: 0, value: 19690720 };
+    let assignment = solve_intcode_program(&program, &[], &noun_and_verb(), &goal, 10_000).unwrap();
+    let mut patched = program.clone();
+    patched.instructions[1] = assignment[&Symbol::Memory(1)];
+    patched.instructions[2] = assignment[&Symbol::Memory(2)];
+    run_intcode_program(&mut patched, &Some(vec![]));
+    assert_eq!(patched.instructions[0], 19690720);
+  }
+
+  #[test]
+  fn day02_result_is_linear() {
+    let program = Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day02.csv"));
+    let paths = explore_intcode_program(&program, &[], &noun_and_verb(), 10_000);
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].end, PathEnd::Halted);
+    let result = &paths[0].memory[0];
+    assert_eq!(result.symbols(), noun_and_verb().iter().map(|x| x.symbol).collect());
+    assert_eq!(result.degree(Symbol::Memory(1)), Some(1));
+    assert_eq!(result.degree(Symbol::Memory(2)), Some(1));
+  }
+
+  #[test]
+  fn forks_at_symbolic_jumps() {
+    // outputs 999 below 8, 1000 for 8 and 1001 above
+    let program = Program::from_string("
+3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,
+1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,
+999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99
+");
+    let domains = [SymbolDomain { symbol: Symbol::Input(0), min: -100, max: 100 }];
+    let paths = explore_intcode_program(&program, &[], &domains, 1_000);
+    assert_eq!(paths.len(), 3);
+    assert!(paths.iter().all(|x| x.end == PathEnd::Halted));
+    let solve = |goal| solve_intcode_program(&program, &[], &domains, &goal, 1_000);
+    assert_eq!(solve(SymbolicGoal::Output(1000)).unwrap()[&Symbol::Input(0)], 8);
+    assert!(solve(SymbolicGoal::Output(999)).unwrap()[&Symbol::Input(0)] < 8);
+    assert_eq!(solve(SymbolicGoal::Output(1002)), None);
+  }
+
+  #[test]
+  fn keeps_the_fall_through_of_a_faulting_jump() {
+    // prints 1 for 0, and jumps out of the program otherwise
+    let program = Program::from_string("3,9,1005,9,100,104,1,99,0,0");
+    let domains = [SymbolDomain { symbol: Symbol::Input(0), min: 0, max: 5 }];
+    let paths = explore_intcode_program(&program, &[], &domains, 1_000);
+    let ends: Vec<&PathEnd> = paths.iter().map(|x| &x.end).collect();
+    assert_eq!(ends, vec![&PathEnd::Fault("jump out of program"), &PathEnd::Halted]);
+    assert_eq!(paths[1].outputs, vec![Expression::Constant(1)]);
+  }
+
+  #[test]
+  #[should_panic(expected = "expected the address of mem20 to be within the program of length 5")]
+  fn rejects_memory_symbols_outside_the_program() {
+    let domains = [SymbolDomain { symbol: Symbol::Memory(20), min: 0, max: 1 }];
+    explore_intcode_program(&Program::from_string("1,0,0,0,99"), &[], &domains, 100);
+  }
+
+  #[test]
+  fn tries_boundary_values_of_wide_domains() {
+    let x = Expression::Symbol(Symbol::Input(0));
+    let domains = [SymbolDomain { symbol: Symbol::Input(0), min: InstructionType::MIN, max: InstructionType::MAX }];
+    let below = Constraint { condition: Expression::binary(7, x.clone(), Expression::Constant(-5)).unwrap(), expected: true };
+    assert_eq!(solve_constraints(std::slice::from_ref(&below), &domains).assignment().unwrap()[&Symbol::Input(0)], InstructionType::MIN);
+    let above = Constraint { expected: false, ..below };
+    assert!(solve_constraints(&[above], &domains).assignment().is_some());
+    // no value next to a constant squares to 49
+    let square = Expression::binary(2, x.clone(), x).unwrap();
+    let square = Constraint { condition: Expression::binary(8, square, Expression::Constant(49)).unwrap(), expected: true };
+    assert_eq!(solve_constraints(&[square], &domains), SolverResult::Unsupported);
+  }
+
+  #[test]
+  fn explores_comparisons_over_every_value() {
+    // prints 1 if the input is below 5, otherwise 0
+    let program = Program::from_string("3,15,1007,15,5,16,1005,16,12,104,0,99,104,1,99,0,0");
+    let domains = [SymbolDomain { symbol: Symbol::Input(0), min: InstructionType::MIN, max: InstructionType::MAX }];
+    let paths = explore_intcode_program(&program, &[], &domains, 1_000);
+    assert_eq!(paths.len(), 2);
+    assert!(paths.iter().all(|x| x.end == PathEnd::Halted));
+    let solve = |goal| solve_intcode_program(&program, &[], &domains, &goal, 1_000).unwrap()[&Symbol::Input(0)];
+    assert!(solve(SymbolicGoal::Output(1)) < 5);
+    assert!(solve(SymbolicGoal::Output(0)) >= 5);
+  }
+
+  #[test]
+  fn reaches_address() {
+    // spins at 15 unless the input is 5
+    let program = Program::from_string("3,13,1008,13,5,14,1006,14,15,104,1,99,0,0,0,1105,1,15");
+    let domains = [SymbolDomain { symbol: Symbol::Input(0), min: 0, max: 9 }];
+    let assignment = solve_intcode_program(&program, &[], &domains, &SymbolicGoal::Address(15), 1_000).unwrap();
+    assert_ne!(assignment[&Symbol::Input(0)], 5);
+    let assignment = solve_intcode_program(&program, &[], &domains, &SymbolicGoal::Address(9), 1_000).unwrap();
+    assert_eq!(assignment[&Symbol::Input(0)], 5);
+  }
+
+  #[test]
+  fn solves_linear_equalities_directly() {
+    let x = Expression::Symbol(Symbol::Input(0));
+    let y = Expression::Symbol(Symbol::Input(1));
+    let sum = Expression::binary(2, x, Expression::Constant(1000)).unwrap();
+    let sum = Expression::binary(1, sum, y).unwrap();
+    assert_eq!(sum.to_string(), "((input0 * 1000) + input1)");
+    let constraint = Constraint {
+      condition: Expression::binary(8, sum, Expression::Constant(123_456)).unwrap(),
+      expected: true,
+    };
+    let domains = [
+      SymbolDomain { symbol: Symbol::Input(0), min: 0, max: 999 },
+      SymbolDomain { symbol: Symbol::Input(1), min: 0, max: 999 },
+    ];
+    let assignment = solve_constraints(&[constraint], &domains).assignment().unwrap();
+    assert_eq!(assignment[&Symbol::Input(0)], 123);
+    assert_eq!(assignment[&Symbol::Input(1)], 456);
+  }
+}
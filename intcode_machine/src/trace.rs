@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+use super::instruction_type::InstructionType;
+use super::parameter_mode::ParameterMode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+enum TraceEvent {
+  Fetch { address: usize, value: InstructionType },
+  Decode { address: usize, name: &'static str, parameters: Vec<(ParameterMode, InstructionType)> },
+  Read { address: usize, value: InstructionType },
+  Write { address: usize, value: InstructionType },
+  Input { value: InstructionType },
+  Output { value: InstructionType },
+  Jump { address: usize, destination: usize, taken: bool },
+  Halt { address: usize },
+}
+
+impl TraceEvent {
+  pub
+  fn kind(&self) -> &'static str {
+    match self {
+      TraceEvent::Fetch { .. } => "fetch",
+      TraceEvent::Decode { .. } => "decode",
+      TraceEvent::Read { .. } => "read",
+      TraceEvent::Write { .. } => "write",
+      TraceEvent::Input { .. } => "input",
+      TraceEvent::Output { .. } => "output",
+      TraceEvent::Jump { .. } => "jump",
+      TraceEvent::Halt { .. } => "halt",
+    }
+  }
+
+  pub
+  fn to_json(&self, step: usize) -> String {
+    let fields = match self {
+      TraceEvent::Fetch { address, value } => format!("\"address\":{},\"value\":{}", address, value),
+      TraceEvent::Decode { address, name, parameters } => {
+        let parameters: Vec<String> = parameters.iter()
+          .map(|(mode, value)| format!("{{\"mode\":\"{}\",\"value\":{}}}", mode_name(mode), value))
+          .collect();
+        format!("\"address\":{},\"name\":\"{}\",\"parameters\":[{}]", address, name, parameters.join(","))
+      },
+      TraceEvent::Read { address, value } => format!("\"address\":{},\"value\":{}", address, value),
+      TraceEvent::Write { address, value } => format!("\"address\":{},\"value\":{}", address, value),
+      TraceEvent::Input { value } => format!("\"value\":{}", value),
+      TraceEvent::Output { value } => format!("\"value\":{}", value),
+      TraceEvent::Jump { address, destination, taken } =>
+        format!("\"address\":{},\"destination\":{},\"taken\":{}", address, destination, taken),
+      TraceEvent::Halt { address } => format!("\"address\":{}", address),
+    };
+    format!("{{\"step\":{},\"event\":\"{}\",{}}}", step, self.kind(), fields)
+  }
+}
+
+impl fmt::Display for TraceEvent {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TraceEvent::Fetch { address, value } => write!(f, "fetch '{}' from '{}'", value, address),
+      TraceEvent::Decode { address, name, parameters } => {
+        let parameters: Vec<String> = parameters.iter()
+          .map(|(mode, value)| match mode {
+            ParameterMode::PositionMode => format!("[{}]", value),
+            ParameterMode::ImmediateMode => format!("{}", value),
+          })
+          .collect();
+        write!(f, "decode '{}' at '{}': {}", name, address, parameters.join(", "))
+      },
+      TraceEvent::Read { address, value } => write!(f, "read '{}' from '{}'", value, address),
+      TraceEvent::Write { address, value } => write!(f, "write '{}' to '{}'", value, address),
+      TraceEvent::Input { value } => write!(f, "input '{}'", value),
+      TraceEvent::Output { value } => write!(f, "output '{}'", value),
+      TraceEvent::Jump { address, destination, taken } =>
+        write!(f, "jump from '{}' to '{}': {}", address, destination, taken),
+      TraceEvent::Halt { address } => write!(f, "halt at '{}'", address),
+    }
+  }
+}
+
+fn mode_name(mode: &ParameterMode) -> &'static str {
+  match mode {
+    ParameterMode::PositionMode => "position",
+    ParameterMode::ImmediateMode => "immediate",
+  }
+}
+
+/// Receives the events of a running program, tagged with the index of the
+/// step (instruction) which produced them.
+pub
+trait Tracer: fmt::Debug {
+  fn event(&mut self, step: usize, event: &TraceEvent);
+}
+
+/// Writes each event as a line of human-readable text.
+#[derive(Debug)]
+pub
+struct TextTracer<W: Write + fmt::Debug> {
+  pub writer: W,
+}
+
+impl TextTracer<io::Stdout> {
+  pub
+  fn stdout() -> TextTracer<io::Stdout> {
+    TextTracer { writer: io::stdout() }
+  }
+}
+
+impl<W: Write + fmt::Debug> Tracer for TextTracer<W> {
+  fn event(&mut self, step: usize, event: &TraceEvent) {
+    match event {
+      TraceEvent::Fetch { .. } => writeln!(self.writer, "step {}: {}", step, event),
+      _ => writeln!(self.writer, "  {}", event),
+    }.unwrap();
+  }
+}
+
+/// Writes each event as a JSON object on its own line.
+#[derive(Debug)]
+pub
+struct JsonLinesTracer<W: Write + fmt::Debug> {
+  pub writer: W,
+}
+
+impl<W: Write + fmt::Debug> Tracer for JsonLinesTracer<W> {
+  fn event(&mut self, step: usize, event: &TraceEvent) {
+    writeln!(self.writer, "{}", event.to_json(step)).unwrap();
+  }
+}
+
+/// Keeps the events in memory, the buffer can be cloned before it is given
+/// to a program and the clone used to inspect the events afterwards.
+#[derive(Debug, Default, Clone)]
+pub
+struct BufferTracer {
+  events: Rc<RefCell<Vec<(usize, TraceEvent)>>>,
+}
+
+impl BufferTracer {
+  pub
+  fn new() -> BufferTracer {
+    BufferTracer::default()
+  }
+
+  pub
+  fn events(&self) -> Vec<(usize, TraceEvent)> {
+    self.events.borrow().clone()
+  }
+}
+
+impl Tracer for BufferTracer {
+  fn event(&mut self, step: usize, event: &TraceEvent) {
+    self.events.borrow_mut().push((step, event.clone()));
+  }
+}
+
+#[cfg(test)]
+mod trace_tests {
+  use super::*;
+  use crate::program::Program;
+  use crate::run_intcode_program::run_intcode_program_with_tracer;
+
+  #[test]
+  fn buffer_records_execution() {
+    let buffer = BufferTracer::new();
+    let mut program = Program::from_string("3,9,8,9,10,9,4,9,99,-1,8");
+    let outputs = run_intcode_program_with_tracer(&mut program, &Some(vec![8]), Box::new(buffer.clone()));
+    assert_eq!(outputs, vec![1]);
+    let events = buffer.events();
+    assert_eq!(events[0], (0, TraceEvent::Fetch { address: 0, value: 3 }));
+    assert_eq!(events[1], (0, TraceEvent::Decode {
+      address: 0,
+      name: "INPUT",
+      parameters: vec![(ParameterMode::PositionMode, 9)],
+    }));
+    assert_eq!(events[2], (0, TraceEvent::Input { value: 8 }));
+    assert_eq!(events[3], (0, TraceEvent::Write { address: 9, value: 8 }));
+    assert!(events.contains(&(1, TraceEvent::Read { address: 10, value: 8 })));
+    assert!(events.contains(&(1, TraceEvent::Write { address: 9, value: 1 })));
+    assert!(events.contains(&(2, TraceEvent::Output { value: 1 })));
+    assert_eq!(*events.last().unwrap(), (3, TraceEvent::Halt { address: 8 }));
+  }
+
+  #[test]
+  fn jumps_are_traced() {
+    let buffer = BufferTracer::new();
+    let mut program = Program::from_string("3,3,1105,-1,9,1101,0,0,12,4,12,99,1");
+    run_intcode_program_with_tracer(&mut program, &Some(vec![0]), Box::new(buffer.clone()));
+    assert!(buffer.events().contains(&(1, TraceEvent::Jump { address: 2, destination: 5, taken: false })));
+  }
+
+  #[test]
+  fn decode_near_end_of_memory() {
+    // the trailing JUMP-IF-TRUE parameter is missing, decoding must not panic
+    let buffer = BufferTracer::new();
+    let tracer = buffer.clone();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      run_intcode_program_with_tracer(&mut Program::from_string("1105,1"), &Some(vec![]), Box::new(tracer));
+    }));
+    // the operation itself still rejects the truncated instruction
+    assert!(result.is_err());
+    assert_eq!(buffer.events()[1], (0, TraceEvent::Decode {
+      address: 0,
+      name: "JUMP-IF-TRUE",
+      parameters: vec![(ParameterMode::ImmediateMode, 1)],
+    }));
+  }
+
+  #[test]
+  fn json_lines_format() {
+    let mut tracer = JsonLinesTracer { writer: Vec::new() };
+    tracer.event(4, &TraceEvent::Decode {
+      address: 2,
+      name: "ADD",
+      parameters: vec![(ParameterMode::PositionMode, 5), (ParameterMode::ImmediateMode, -1)],
+    });
+    tracer.event(4, &TraceEvent::Jump { address: 2, destination: 7, taken: true });
+    assert_eq!(
+      String::from_utf8(tracer.writer).unwrap(),
+      "{\"step\":4,\"event\":\"decode\",\"address\":2,\"name\":\"ADD\",\"parameters\":\
+[{\"mode\":\"position\",\"value\":5},{\"mode\":\"immediate\",\"value\":-1}]}\n\
+{\"step\":4,\"event\":\"jump\",\"address\":2,\"destination\":7,\"taken\":true}\n");
+  }
+
+  #[test]
+  fn text_format() {
+    let mut tracer = TextTracer { writer: Vec::new() };
+    tracer.event(0, &TraceEvent::Fetch { address: 0, value: 1001 });
+    tracer.event(0, &TraceEvent::Write { address: 3, value: 7 });
+    assert_eq!(
+      String::from_utf8(tracer.writer).unwrap(),
+      "step 0: fetch '1001' from '0'\n  write '7' to '3'\n");
+  }
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+use std::panic;
+
+use super::instruction_type::InstructionType;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+use super::trace::BufferTracer;
+use super::trace::TraceEvent;
+
+/// All of the events produced by a single step of a program.
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct TraceStep {
+  pub step: usize,
+  pub events: Vec<TraceEvent>,
+}
+
+impl TraceStep {
+  pub
+  fn counter(&self) -> Option<usize> {
+    self.events.iter().find_map(|event| match event {
+      TraceEvent::Fetch { address, .. } => Some(*address),
+      _ => None,
+    })
+  }
+
+  fn decoded(&self) -> Option<&TraceEvent> {
+    self.events.iter().find(|event| matches!(event, TraceEvent::Decode { .. }))
+  }
+
+  // values consumed by the instruction, either read from memory or input
+  fn operand_values(&self) -> Vec<InstructionType> {
+    self.events.iter().filter_map(|event| match event {
+      TraceEvent::Read { value, .. } => Some(*value),
+      TraceEvent::Input { value } => Some(*value),
+      _ => None,
+    }).collect()
+  }
+
+  fn writes(&self) -> Vec<&TraceEvent> {
+    self.events.iter().filter(|event| matches!(event, TraceEvent::Write { .. })).collect()
+  }
+
+  fn outputs(&self) -> Vec<&TraceEvent> {
+    self.events.iter().filter(|event| matches!(event, TraceEvent::Output { .. })).collect()
+  }
+}
+
+impl fmt::Display for TraceStep {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let events: Vec<String> = self.events.iter()
+      .filter(|event| !matches!(event, TraceEvent::Fetch { .. }))
+      .map(|event| event.to_string())
+      .collect();
+    match self.counter() {
+      Some(counter) => write!(f, "step {} at '{}': {}", self.step, counter, events.join("; ")),
+      None => write!(f, "step {}: {}", self.step, events.join("; ")),
+    }
+  }
+}
+
+/// Group a flat list of events, as recorded by a BufferTracer, into steps.
+pub
+fn group_trace_steps(events: &[(usize, TraceEvent)]) -> Vec<TraceStep> {
+  let mut steps: Vec<TraceStep> = Vec::new();
+  for (step, event) in events {
+    match steps.last_mut() {
+      Some(last) if last.step == *step => {
+        // a step which was retried after waiting for input fetches again
+        if let TraceEvent::Fetch { .. } = event {
+          last.events.clear();
+        }
+        last.events.push(event.clone());
+      },
+      _ => steps.push(TraceStep { step: *step, events: vec![event.clone()] }),
+    }
+  }
+  steps
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub
+struct Divergence {
+  /// index of the first step which differs
+  pub step: usize,
+  /// what differs, any of "counter", "instruction", "operands", "writes", "outputs" or "length"
+  pub differences: Vec<&'static str>,
+  /// the steps of both runs around the divergence, None once a run has ended
+  pub context: Vec<(Option<TraceStep>, Option<TraceStep>)>,
+}
+
+impl fmt::Display for Divergence {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "first divergence at step {}: {}", self.step, self.differences.join(", "))?;
+    for (left, right) in &self.context {
+      let step = left.as_ref().or(right.as_ref()).unwrap().step;
+      let marker = if step == self.step { ">>" } else { "  " };
+      let show = |x: &Option<TraceStep>| match x {
+        Some(x) => x.to_string(),
+        None => "<ended>".to_string(),
+      };
+      writeln!(f, "{} left:  {}", marker, show(left))?;
+      writeln!(f, "{} right: {}", marker, show(right))?;
+    }
+    Ok(())
+  }
+}
+
+fn step_differences(left: &TraceStep, right: &TraceStep) -> Vec<&'static str> {
+  let mut differences = Vec::new();
+  if left.counter() != right.counter() {
+    differences.push("counter");
+  }
+  if left.decoded() != right.decoded() {
+    differences.push("instruction");
+  }
+  if left.operand_values() != right.operand_values() {
+    differences.push("operands");
+  }
+  if left.writes() != right.writes() {
+    differences.push("writes");
+  }
+  if left.outputs() != right.outputs() {
+    differences.push("outputs");
+  }
+  differences
+}
+
+/// Find the first step where two traces differ, with up to `context` steps
+/// before and after it.
+pub
+fn diff_traces(left: &[TraceStep], right: &[TraceStep], context: usize) -> Option<Divergence> {
+  let mut divergence = None;
+  for index in 0..left.len().max(right.len()) {
+    let differences = match (left.get(index), right.get(index)) {
+      (Some(l), Some(r)) => step_differences(l, r),
+      _ => vec!["length"],
+    };
+    if !differences.is_empty() {
+      divergence = Some((index, differences));
+      break;
+    }
+  }
+  let (index, differences) = divergence?;
+  let first = index.saturating_sub(context);
+  let last = (index + context).min(left.len().max(right.len()) - 1);
+  Some(Divergence {
+    step: index,
+    differences,
+    context: (first..=last).map(|i| (left.get(i).cloned(), right.get(i).cloned())).collect(),
+  })
+}
+
+/// Run the program with the given inputs, and return its trace grouped into
+/// steps. The run ends at halt, when input runs out, on a fault or after
+/// `max_steps` steps.
+pub
+fn trace_run(program: &Program, inputs: &[InstructionType], max_steps: usize) -> Vec<TraceStep> {
+  let buffer = BufferTracer::new();
+  let tracer = buffer.clone();
+  let program = program.clone();
+  let inputs = inputs.to_vec();
+  // a fault panics, but the steps up to it are still worth comparing
+  let result = panic::catch_unwind(panic::AssertUnwindSafe(move || {
+    let mut program_instance = ProgramInstance::new(program);
+    *program_instance.context.borrow_mut().inputs.borrow_mut() = Some(inputs.into());
+    *program_instance.context.borrow_mut().tracer.borrow_mut() = Some(Box::new(tracer));
+    for _ in 0..max_steps {
+      let next_counter = match program_instance.step() {
+        Ok((next_counter, _)) => next_counter,
+        Err(StepError::NeedInput) => return true,
+        Err(StepError::EndOfProgram) | Err(StepError::Error(_)) | Err(StepError::LimitExceeded(_)) => break,
+      };
+      program_instance.context.borrow_mut().counter.set(next_counter);
+    }
+    false
+  }));
+  let mut steps = group_trace_steps(&buffer.events());
+  if let Ok(true) = result {
+    // the input instruction which ran out of input did not complete
+    steps.pop();
+  }
+  steps
+}
+
+/// Run two programs, or the same program with two sets of inputs, and find
+/// the first step where their execution differs.
+pub
+fn diff_runs(
+  left: (&Program, &[InstructionType]),
+  right: (&Program, &[InstructionType]),
+  context: usize,
+  max_steps: usize,
+) -> Option<Divergence>
+{
+  let left = trace_run(left.0, left.1, max_steps);
+  let right = trace_run(right.0, right.1, max_steps);
+  diff_traces(&left, &right, context)
+}
+
+#[cfg(test)]
+mod trace_diff_tests {
+  use super::*;
+
+  #[test]
+  fn identical_runs_do_not_diverge() {
+    let program = Program::from_string("3,9,8,9,10,9,4,9,99,-1,8");
+    assert_eq!(diff_runs((&program, &[8]), (&program, &[8]), 2, 1000), None);
+  }
+
+  #[test]
+  fn different_inputs() {
+    let program = Program::from_string("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+    let divergence = diff_runs((&program, &[0]), (&program, &[1]), 1, 1000).unwrap();
+    // the input itself is the first difference
+    assert_eq!(divergence.step, 0);
+    assert_eq!(divergence.differences, vec!["operands", "writes"]);
+    assert_eq!(divergence.context.len(), 2);
+    let report = divergence.to_string();
+    assert!(report.starts_with("first divergence at step 0: operands, writes\n"));
+    assert!(report.contains(">> left:  step 0 at '0': decode 'INPUT' at '0': [12]; input '0'; write '0' to '12'"));
+    assert!(report.contains(">> right: step 0 at '0': decode 'INPUT' at '0': [12]; input '1'; write '1' to '12'"));
+  }
+
+  #[test]
+  fn patched_program() {
+    let original = Program::from_string("1,0,0,0,2,0,0,0,99");
+    let patched = Program::from_string("1,0,0,0,1,0,4,0,99");
+    let divergence = diff_runs((&original, &[]), (&patched, &[]), 1, 1000).unwrap();
+    assert_eq!(divergence.step, 1);
+    assert_eq!(divergence.differences, vec!["instruction", "operands", "writes"]);
+    // one step before and the halt after
+    assert_eq!(divergence.context.len(), 3);
+    assert_eq!(divergence.context[0].0, divergence.context[0].1);
+  }
+
+  #[test]
+  fn one_run_ends_early() {
+    let program = Program::from_string("3,5,4,5,99,0");
+    let left = trace_run(&program, &[1], 1000);
+    let right = trace_run(&program, &[], 1000);
+    assert_eq!(right.len(), 0);
+    let divergence = diff_traces(&left, &right, 0).unwrap();
+    assert_eq!(divergence.differences, vec!["length"]);
+    assert_eq!(divergence.context, vec![(Some(left[0].clone()), None)]);
+  }
+}
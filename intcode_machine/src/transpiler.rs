@@ -0,0 +1,269 @@
+use std::collections::VecDeque;
+
+use super::disassembler::Instruction;
+use super::disassembler::decode_instruction;
+use super::instruction_type::InstructionType;
+use super::parameter_mode::ParameterMode;
+use super::program::Program;
+use super::program::ProgramInstance;
+use super::program::StepError;
+
+// whether the compiled form of the instruction would behave exactly like the
+// interpreter, otherwise it is left to the interpreter, which also reports
+// any fault
+fn can_compile(instruction: &Instruction, length: usize) -> bool {
+  let in_range = |value: InstructionType| value >= 0 && (value as usize) < length;
+  let parameters_valid = instruction.parameters.iter().all(|(mode, value)| match mode {
+    ParameterMode::PositionMode => in_range(*value),
+    ParameterMode::ImmediateMode => true,
+  });
+  let destination_valid = match instruction.opcode {
+    1 | 2 | 7 | 8 => instruction.parameters[2].0 == ParameterMode::PositionMode,
+    3 => instruction.parameters[0].0 == ParameterMode::PositionMode,
+    5 | 6 => instruction.parameters[1].0 == ParameterMode::PositionMode || in_range(instruction.parameters[1].1),
+    _ => true,
+  };
+  parameters_valid && destination_valid && operation(instruction).is_some()
+}
+
+fn operand(parameter: &(ParameterMode, InstructionType)) -> String {
+  match parameter {
+    (ParameterMode::PositionMode, address) => format!("m[{}]", address),
+    (ParameterMode::ImmediateMode, value) => format!("{}", value),
+  }
+}
+
+// the expression computed by an ADD, MULTIPLY, LESS-THAN or EQUALS, with
+// immediate operands folded, or None if folding would overflow
+fn operation(instruction: &Instruction) -> Option<String> {
+  let (left, right) = match instruction.opcode {
+    1 | 2 | 7 | 8 => (instruction.parameters[0], instruction.parameters[1]),
+    _ => return Some(String::new()),
+  };
+  if let ((ParameterMode::ImmediateMode, x), (ParameterMode::ImmediateMode, y)) = (left, right) {
+    let value = match instruction.opcode {
+      1 => x.checked_add(y)?,
+      2 => x.checked_mul(y)?,
+      7 => (x < y) as InstructionType,
+      _ => (x == y) as InstructionType,
+    };
+    return Some(value.to_string())
+  }
+  Some(match instruction.opcode {
+    1 => format!("{} + {}", operand(&left), operand(&right)),
+    2 => format!("{} * {}", operand(&left), operand(&right)),
+    7 => format!("({} < {}) as i32", operand(&left), operand(&right)),
+    _ => format!("({} == {}) as i32", operand(&left), operand(&right)),
+  })
+}
+
+fn compile_instruction(instruction: &Instruction, compiled: &[Instruction]) -> String {
+  let address = instruction.address;
+  let next = instruction.next_address();
+  // writes into compiled instructions make them fall back to the interpreter
+  let write = |destination: InstructionType, value: String| {
+    let mut code = format!("m[{}] = {};", destination, value);
+    for other in compiled {
+      if other.address <= destination as usize && (destination as usize) < other.next_address() {
+        code += &format!(" clean[{}] = false;", other.address);
+      }
+    }
+    code
+  };
+  match instruction.opcode {
+    1 | 2 | 7 | 8 => format!(
+      "{{ {} counter = {}; }},",
+      write(instruction.parameters[2].1, operation(instruction).unwrap()),
+      next),
+    // without input, the interpreter reports the error
+    3 => format!(
+      "match inputs.pop_front() {{ Some(x) => {{ {} counter = {}; }}, None => clean[{}] = false }},",
+      write(instruction.parameters[0].1, String::from("x")),
+      next,
+      address),
+    4 => format!("{{ outputs.push({}); counter = {}; }},", operand(&instruction.parameters[0]), next),
+    5 | 6 => {
+      let taken = match instruction.parameters[1] {
+        (ParameterMode::ImmediateMode, destination) => format!("counter = {}", destination),
+        // the interpreter reports a jump out of the program
+        (ParameterMode::PositionMode, destination) => format!(
+          "if m[{}] < 0 || m[{}] as usize >= LENGTH {{ clean[{}] = false }} else {{ counter = m[{}] as usize }}",
+          destination, destination, address, destination),
+      };
+      let not_taken = format!("counter = {}", next);
+      let jump_if_true = instruction.opcode == 5;
+      match instruction.parameters[0] {
+        (ParameterMode::ImmediateMode, test) if (test != 0) == jump_if_true => format!("{},", taken),
+        (ParameterMode::ImmediateMode, _) => format!("{},", not_taken),
+        (ParameterMode::PositionMode, test) => format!(
+          "if m[{}] {} 0 {{ {} }} else {{ {} }},",
+          test,
+          if jump_if_true { "!=" } else { "==" },
+          taken,
+          not_taken),
+      }
+    },
+    _ => String::from("break,"),
+  }
+}
+
+/// Translate the program into the source of a Rust module, whose
+/// `run(program: &mut Program, inputs: &[i32]) -> Vec<i32>` behaves like
+/// `run_intcode_program`.
+///
+/// Every address which decodes to a valid instruction gets a match arm, since
+/// control may reach any of them through a position mode jump. An instruction
+/// whose values have been overwritten, in advance or by the program itself,
+/// falls back to the interpreter, as do the addresses without an arm.
+pub
+fn transpile(program: &Program) -> String {
+  let memory = &program.instructions;
+  let compiled: Vec<Instruction> = (0..memory.len())
+    .filter_map(|address| decode_instruction(memory, address))
+    .filter(|instruction| can_compile(instruction, memory.len()))
+    .collect();
+  let mut output = format!("\
+// Generated from an intcode program of {} values, by intcode_machine::transpile.
+#![allow(clippy::all)]
+
+use std::collections::VecDeque;
+
+use intcode_machine::Program;
+use intcode_machine::interpret_step;
+use intcode_machine::run_intcode_program;
+
+const LENGTH: usize = {};
+
+// the address of each compiled instruction, and the values it was compiled from
+const INSTRUCTIONS: &[(usize, &[i32])] = &[
+", memory.len(), memory.len());
+  for instruction in &compiled {
+    let values: Vec<String> = memory[instruction.address..instruction.next_address()]
+      .iter()
+      .map(|x| x.to_string())
+      .collect();
+    output += &format!("  ({}, &[{}]),\n", instruction.address, values.join(", "));
+  }
+  output += "\
+];
+
+pub
+fn run(program: &mut Program, inputs: &[i32]) -> Vec<i32> {
+  if program.instructions.len() != LENGTH {
+    return run_intcode_program(program, &Some(inputs.to_vec()))
+  }
+  // an instruction only runs compiled while memory still matches it
+  let mut clean = [false; LENGTH];
+  for (address, values) in INSTRUCTIONS {
+    clean[*address] = program.instructions[*address..*address + values.len()] == **values;
+  }
+  let mut inputs: VecDeque<i32> = inputs.iter().cloned().collect();
+  let mut outputs = Vec::new();
+  let mut counter = 0;
+  while counter < LENGTH {
+    if !clean[counter] {
+      match interpret_step(program, counter, &mut inputs, &mut outputs, &mut clean) {
+        Some(next_counter) => counter = next_counter,
+        None => break,
+      }
+      continue;
+    }
+    let m = &mut program.instructions;
+    match counter {
+";
+  for instruction in &compiled {
+    output += &format!(
+      "      {} => {}  // {}\n",
+      instruction.address,
+      compile_instruction(instruction, &compiled),
+      instruction);
+  }
+  output += "      _ => unreachable!(),
+    }
+  }
+  outputs
+}
+";
+  output
+}
+
+/// Run the single instruction at the counter in the interpreter, on behalf of
+/// transpiled code, and return the next counter, or None once the program
+/// halts.
+///
+/// The compiled instructions which the write of the instruction may
+/// overlap are marked as no longer clean. Faults panic, as they do in
+/// `run_intcode_program`.
+pub
+fn interpret_step(
+  program: &mut Program,
+  counter: usize,
+  inputs: &mut VecDeque<InstructionType>,
+  outputs: &mut Vec<InstructionType>,
+  clean: &mut [bool],
+) -> Option<usize>
+{
+  let destination = decode_instruction(&program.instructions, counter).and_then(|x| x.destination());
+  if let Some(destination) = destination.filter(|x| *x >= 0) {
+    let destination = destination as usize;
+    // instructions are at most four values long
+    let end = (destination + 1).min(clean.len());
+    for x in clean.iter_mut().take(end).skip(destination.saturating_sub(3)) {
+      *x = false;
+    }
+  }
+  let mut program_instance = ProgramInstance::from_ref(program);
+  program_instance.context.borrow().counter.set(counter);
+  *program_instance.context.borrow().inputs.borrow_mut() = Some(std::mem::take(inputs));
+  let result = program_instance.step();
+  *inputs = program_instance.context.borrow().inputs.borrow_mut().take().unwrap();
+  match result {
+    Ok((next_counter, output)) => {
+      outputs.extend(output);
+      Some(next_counter)
+    },
+    Err(StepError::EndOfProgram) => None,
+    Err(StepError::NeedInput) => panic!("unexpected request for input and given inputs exhausted"),
+    Err(StepError::Error(msg)) => panic!("unexpected error: {}", msg),
+    Err(StepError::LimitExceeded(limit)) => panic!("unexpected stop by {}", limit),
+  }
+}
+
+#[cfg(test)]
+mod transpiler_tests {
+  use super::*;
+
+  #[test]
+  fn compiles_each_instruction() {
+    let source = transpile(&Program::from_string("3,9,1001,9,-1,9,4,9,99,0"));
+    assert!(source.contains("const LENGTH: usize = 10;\n"));
+    assert!(source.contains("  (2, &[1001, 9, -1, 9]),\n"));
+    assert!(source.contains(
+      "      0 => match inputs.pop_front() { Some(x) => { m[9] = x; counter = 2; }, None => clean[0] = false },"));
+    assert!(source.contains("      2 => { m[9] = m[9] + -1; counter = 6; },  // ADD [9], -1, [9]\n"));
+    assert!(source.contains("      6 => { outputs.push(m[9]); counter = 8; },  // PRINT [9]\n"));
+    assert!(source.contains("      8 => break,  // HALT\n"));
+  }
+
+  #[test]
+  fn writes_into_code_invalidate_it() {
+    // the add at 0 overwrites the opcode of the add at 4, and the values it
+    // overlaps from 1 to 3 also decode, the jump at 8 is out of range, so it
+    // is left to the interpreter
+    let source = transpile(&Program::from_string("1101,1,1,4,1,0,0,0,1105,1,100"));
+    assert!(source.contains(
+      "      0 => { m[4] = 2; clean[1] = false; clean[2] = false; clean[3] = false; clean[4] = false; counter = 4; },"));
+    assert!(!source.contains("      8 =>"));
+  }
+
+  #[test]
+  fn interpret_step_invalidates_destination() {
+    let mut program = Program::from_string("1101,1,1,4,99");
+    let mut inputs = VecDeque::new();
+    let mut outputs = Vec::new();
+    let mut clean = [true; 5];
+    assert_eq!(interpret_step(&mut program, 0, &mut inputs, &mut outputs, &mut clean), Some(4));
+    assert_eq!(clean, [true, false, false, false, false]);
+    assert_eq!(program.instructions[4], 2);
+  }
+}
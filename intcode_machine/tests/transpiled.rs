@@ -0,0 +1,77 @@
+extern crate intcode_machine;
+
+use intcode_machine::Program;
+use intcode_machine::run_intcode_program;
+use intcode_machine::transpile;
+
+// regenerate with intcode_transpile when the transpiler changes
+#[path = "transpiled/day02.rs"]
+mod day02;
+#[path = "transpiled/day05.rs"]
+mod day05;
+#[path = "transpiled/day05_compare_to_8.rs"]
+mod day05_compare_to_8;
+
+const COMPARE_TO_8_PROGRAM: &str = "\
+3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+// the transpiled run must leave memory and outputs exactly as the
+// interpreter does
+fn assert_matches_interpreter(
+  run: fn(&mut Program, &[i32]) -> Vec<i32>,
+  program: &Program,
+  inputs: &[i32],
+) -> Vec<i32>
+{
+  let mut interpreted = program.clone();
+  let expected = run_intcode_program(&mut interpreted, &Some(inputs.to_vec()));
+  let mut transpiled = program.clone();
+  let outputs = run(&mut transpiled, inputs);
+  assert_eq!(outputs, expected);
+  assert_eq!(transpiled.instructions, interpreted.instructions);
+  outputs
+}
+
+#[test]
+fn generated_modules_are_up_to_date() {
+  assert_eq!(
+    transpile(&Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day02.csv"))),
+    include_str!("transpiled/day02.rs"));
+  assert_eq!(
+    transpile(&Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day05.csv"))),
+    include_str!("transpiled/day05.rs"));
+  assert_eq!(
+    transpile(&Program::from_string(COMPARE_TO_8_PROGRAM)),
+    include_str!("transpiled/day05_compare_to_8.rs"));
+}
+
+#[test]
+fn day02_matches_interpreter() {
+  let program = Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day02.csv"));
+  // patching the noun and verb makes the first instruction fall back to the
+  // interpreter
+  for (noun, verb) in &[(0, 0), (12, 2), (53, 98), (99, 99)] {
+    let mut patched = program.clone();
+    patched.instructions[1] = *noun;
+    patched.instructions[2] = *verb;
+    assert_matches_interpreter(day02::run, &patched, &[]);
+  }
+  let mut patched = program.clone();
+  patched.instructions[1] = 12;
+  patched.instructions[2] = 2;
+  day02::run(&mut patched, &[]);
+  assert_eq!(patched.instructions[0], 12490719);
+}
+
+#[test]
+fn day05_matches_interpreter() {
+  // the diagnostic program modifies the instruction at 6 before running it
+  let program = Program::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/day05.csv"));
+  assert_eq!(*assert_matches_interpreter(day05::run, &program, &[1]).last().unwrap(), 12896948);
+  assert_matches_interpreter(day05::run, &program, &[5]);
+  let program = Program::from_string(COMPARE_TO_8_PROGRAM);
+  for input in 6..11 {
+    assert_matches_interpreter(day05_compare_to_8::run, &program, &[input]);
+  }
+}
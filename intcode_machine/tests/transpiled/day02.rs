@@ -0,0 +1,165 @@
+// Generated from an intcode program of 137 values, by intcode_machine::transpile.
+#![allow(clippy::all)]
+
+use std::collections::VecDeque;
+
+use intcode_machine::Program;
+use intcode_machine::interpret_step;
+use intcode_machine::run_intcode_program;
+
+const LENGTH: usize = 137;
+
+// the address of each compiled instruction, and the values it was compiled from
+const INSTRUCTIONS: &[(usize, &[i32])] = &[
+  (0, &[1, 0, 0, 3]),
+  (3, &[3, 1]),
+  (4, &[1, 1, 2, 3]),
+  (5, &[1, 2, 3, 1]),
+  (6, &[2, 3, 1, 3]),
+  (7, &[3, 1]),
+  (8, &[1, 3, 4, 3]),
+  (9, &[3, 4]),
+  (10, &[4, 3]),
+  (11, &[3, 1]),
+  (12, &[1, 5, 0, 3]),
+  (13, &[5, 0, 3]),
+  (15, &[3, 2]),
+  (16, &[2, 13, 1, 19]),
+  (18, &[1, 19, 1, 19]),
+  (20, &[1, 19, 6, 23]),
+  (22, &[6, 23, 1]),
+  (24, &[1, 23, 6, 27]),
+  (26, &[6, 27, 1]),
+  (28, &[1, 13, 27, 31]),
+  (32, &[2, 13, 31, 35]),
+  (36, &[1, 5, 35, 39]),
+  (37, &[5, 35, 39]),
+  (40, &[2, 39, 13, 43]),
+  (44, &[1, 10, 43, 47]),
+  (48, &[2, 13, 47, 51]),
+  (52, &[1, 6, 51, 55]),
+  (53, &[6, 51, 55]),
+  (56, &[2, 55, 13, 59]),
+  (60, &[1, 59, 10, 63]),
+  (64, &[1, 63, 10, 67]),
+  (68, &[2, 10, 67, 71]),
+  (72, &[1, 6, 71, 75]),
+  (73, &[6, 71, 75]),
+  (76, &[1, 10, 75, 79]),
+  (80, &[1, 79, 9, 83]),
+  (84, &[2, 83, 6, 87]),
+  (86, &[6, 87, 2]),
+  (88, &[2, 87, 9, 91]),
+  (92, &[1, 5, 91, 95]),
+  (93, &[5, 91, 95]),
+  (96, &[1, 6, 95, 99]),
+  (97, &[6, 95, 99]),
+  (99, &[99]),
+  (100, &[1, 99, 9, 103]),
+  (101, &[99]),
+  (104, &[2, 10, 103, 107]),
+  (107, &[107, 1, 107, 6]),
+  (108, &[1, 107, 6, 111]),
+  (109, &[107, 6, 111, 2]),
+  (110, &[6, 111, 2]),
+  (112, &[2, 9, 111, 115]),
+  (116, &[1, 5, 115, 119]),
+  (117, &[5, 115, 119]),
+  (120, &[1, 10, 119, 123]),
+  (124, &[1, 2, 123, 127]),
+  (125, &[2, 123, 127, 1]),
+  (128, &[1, 127, 6, 0]),
+  (130, &[6, 0, 99]),
+  (132, &[99]),
+  (133, &[2, 14, 0, 0]),
+];
+
+pub
+fn run(program: &mut Program, inputs: &[i32]) -> Vec<i32> {
+  if program.instructions.len() != LENGTH {
+    return run_intcode_program(program, &Some(inputs.to_vec()))
+  }
+  // an instruction only runs compiled while memory still matches it
+  let mut clean = [false; LENGTH];
+  for (address, values) in INSTRUCTIONS {
+    clean[*address] = program.instructions[*address..*address + values.len()] == **values;
+  }
+  let mut inputs: VecDeque<i32> = inputs.iter().cloned().collect();
+  let mut outputs = Vec::new();
+  let mut counter = 0;
+  while counter < LENGTH {
+    if !clean[counter] {
+      match interpret_step(program, counter, &mut inputs, &mut outputs, &mut clean) {
+        Some(next_counter) => counter = next_counter,
+        None => break,
+      }
+      continue;
+    }
+    let m = &mut program.instructions;
+    match counter {
+      0 => { m[3] = m[0] + m[0]; clean[0] = false; clean[3] = false; counter = 4; },  // ADD [0], [0], [3]
+      3 => match inputs.pop_front() { Some(x) => { m[1] = x; clean[0] = false; counter = 5; }, None => clean[3] = false },  // INPUT [1]
+      4 => { m[3] = m[1] + m[2]; clean[0] = false; clean[3] = false; counter = 8; },  // ADD [1], [2], [3]
+      5 => { m[1] = m[2] + m[3]; clean[0] = false; counter = 9; },  // ADD [2], [3], [1]
+      6 => { m[3] = m[3] * m[1]; clean[0] = false; clean[3] = false; counter = 10; },  // MULTIPLY [3], [1], [3]
+      7 => match inputs.pop_front() { Some(x) => { m[1] = x; clean[0] = false; counter = 9; }, None => clean[7] = false },  // INPUT [1]
+      8 => { m[3] = m[3] + m[4]; clean[0] = false; clean[3] = false; counter = 12; },  // ADD [3], [4], [3]
+      9 => match inputs.pop_front() { Some(x) => { m[4] = x; clean[3] = false; clean[4] = false; counter = 11; }, None => clean[9] = false },  // INPUT [4]
+      10 => { outputs.push(m[3]); counter = 12; },  // PRINT [3]
+      11 => match inputs.pop_front() { Some(x) => { m[1] = x; clean[0] = false; counter = 13; }, None => clean[11] = false },  // INPUT [1]
+      12 => { m[3] = m[5] + m[0]; clean[0] = false; clean[3] = false; counter = 16; },  // ADD [5], [0], [3]
+      13 => if m[0] != 0 { if m[3] < 0 || m[3] as usize >= LENGTH { clean[13] = false } else { counter = m[3] as usize } } else { counter = 16 },  // JUMP-IF-TRUE [0], [3]
+      15 => match inputs.pop_front() { Some(x) => { m[2] = x; clean[0] = false; counter = 17; }, None => clean[15] = false },  // INPUT [2]
+      16 => { m[19] = m[13] * m[1]; clean[16] = false; clean[18] = false; counter = 20; },  // MULTIPLY [13], [1], [19]
+      18 => { m[19] = m[19] + m[1]; clean[16] = false; clean[18] = false; counter = 22; },  // ADD [19], [1], [19]
+      20 => { m[23] = m[19] + m[6]; clean[20] = false; clean[22] = false; counter = 24; },  // ADD [19], [6], [23]
+      22 => if m[23] == 0 { if m[1] < 0 || m[1] as usize >= LENGTH { clean[22] = false } else { counter = m[1] as usize } } else { counter = 25 },  // JUMP-IF-FALSE [23], [1]
+      24 => { m[27] = m[23] + m[6]; clean[24] = false; clean[26] = false; counter = 28; },  // ADD [23], [6], [27]
+      26 => if m[27] == 0 { if m[1] < 0 || m[1] as usize >= LENGTH { clean[26] = false } else { counter = m[1] as usize } } else { counter = 29 },  // JUMP-IF-FALSE [27], [1]
+      28 => { m[31] = m[13] + m[27]; clean[28] = false; counter = 32; },  // ADD [13], [27], [31]
+      32 => { m[35] = m[13] * m[31]; clean[32] = false; counter = 36; },  // MULTIPLY [13], [31], [35]
+      36 => { m[39] = m[5] + m[35]; clean[36] = false; clean[37] = false; counter = 40; },  // ADD [5], [35], [39]
+      37 => if m[35] != 0 { if m[39] < 0 || m[39] as usize >= LENGTH { clean[37] = false } else { counter = m[39] as usize } } else { counter = 40 },  // JUMP-IF-TRUE [35], [39]
+      40 => { m[43] = m[39] * m[13]; clean[40] = false; counter = 44; },  // MULTIPLY [39], [13], [43]
+      44 => { m[47] = m[10] + m[43]; clean[44] = false; counter = 48; },  // ADD [10], [43], [47]
+      48 => { m[51] = m[13] * m[47]; clean[48] = false; counter = 52; },  // MULTIPLY [13], [47], [51]
+      52 => { m[55] = m[6] + m[51]; clean[52] = false; clean[53] = false; counter = 56; },  // ADD [6], [51], [55]
+      53 => if m[51] == 0 { if m[55] < 0 || m[55] as usize >= LENGTH { clean[53] = false } else { counter = m[55] as usize } } else { counter = 56 },  // JUMP-IF-FALSE [51], [55]
+      56 => { m[59] = m[55] * m[13]; clean[56] = false; counter = 60; },  // MULTIPLY [55], [13], [59]
+      60 => { m[63] = m[59] + m[10]; clean[60] = false; counter = 64; },  // ADD [59], [10], [63]
+      64 => { m[67] = m[63] + m[10]; clean[64] = false; counter = 68; },  // ADD [63], [10], [67]
+      68 => { m[71] = m[10] * m[67]; clean[68] = false; counter = 72; },  // MULTIPLY [10], [67], [71]
+      72 => { m[75] = m[6] + m[71]; clean[72] = false; clean[73] = false; counter = 76; },  // ADD [6], [71], [75]
+      73 => if m[71] == 0 { if m[75] < 0 || m[75] as usize >= LENGTH { clean[73] = false } else { counter = m[75] as usize } } else { counter = 76 },  // JUMP-IF-FALSE [71], [75]
+      76 => { m[79] = m[10] + m[75]; clean[76] = false; counter = 80; },  // ADD [10], [75], [79]
+      80 => { m[83] = m[79] + m[9]; clean[80] = false; counter = 84; },  // ADD [79], [9], [83]
+      84 => { m[87] = m[83] * m[6]; clean[84] = false; clean[86] = false; counter = 88; },  // MULTIPLY [83], [6], [87]
+      86 => if m[87] == 0 { if m[2] < 0 || m[2] as usize >= LENGTH { clean[86] = false } else { counter = m[2] as usize } } else { counter = 89 },  // JUMP-IF-FALSE [87], [2]
+      88 => { m[91] = m[87] * m[9]; clean[88] = false; counter = 92; },  // MULTIPLY [87], [9], [91]
+      92 => { m[95] = m[5] + m[91]; clean[92] = false; clean[93] = false; counter = 96; },  // ADD [5], [91], [95]
+      93 => if m[91] != 0 { if m[95] < 0 || m[95] as usize >= LENGTH { clean[93] = false } else { counter = m[95] as usize } } else { counter = 96 },  // JUMP-IF-TRUE [91], [95]
+      96 => { m[99] = m[6] + m[95]; clean[96] = false; clean[97] = false; clean[99] = false; counter = 100; },  // ADD [6], [95], [99]
+      97 => if m[95] == 0 { if m[99] < 0 || m[99] as usize >= LENGTH { clean[97] = false } else { counter = m[99] as usize } } else { counter = 100 },  // JUMP-IF-FALSE [95], [99]
+      99 => break,  // HALT
+      100 => { m[103] = m[99] + m[9]; clean[100] = false; counter = 104; },  // ADD [99], [9], [103]
+      101 => break,  // HALT
+      104 => { m[107] = m[10] * m[103]; clean[104] = false; clean[107] = false; counter = 108; },  // MULTIPLY [10], [103], [107]
+      107 => { m[6] = (1 < m[107]) as i32; clean[4] = false; clean[5] = false; clean[6] = false; counter = 111; },  // LESS-THAN 1, [107], [6]
+      108 => { m[111] = m[107] + m[6]; clean[108] = false; clean[109] = false; clean[110] = false; counter = 112; },  // ADD [107], [6], [111]
+      109 => { m[2] = (6 < m[111]) as i32; clean[0] = false; counter = 113; },  // LESS-THAN 6, [111], [2]
+      110 => if m[111] == 0 { if m[2] < 0 || m[2] as usize >= LENGTH { clean[110] = false } else { counter = m[2] as usize } } else { counter = 113 },  // JUMP-IF-FALSE [111], [2]
+      112 => { m[115] = m[9] * m[111]; clean[112] = false; counter = 116; },  // MULTIPLY [9], [111], [115]
+      116 => { m[119] = m[5] + m[115]; clean[116] = false; clean[117] = false; counter = 120; },  // ADD [5], [115], [119]
+      117 => if m[115] != 0 { if m[119] < 0 || m[119] as usize >= LENGTH { clean[117] = false } else { counter = m[119] as usize } } else { counter = 120 },  // JUMP-IF-TRUE [115], [119]
+      120 => { m[123] = m[10] + m[119]; clean[120] = false; counter = 124; },  // ADD [10], [119], [123]
+      124 => { m[127] = m[2] + m[123]; clean[124] = false; clean[125] = false; counter = 128; },  // ADD [2], [123], [127]
+      125 => { m[1] = m[123] * m[127]; clean[0] = false; counter = 129; },  // MULTIPLY [123], [127], [1]
+      128 => { m[0] = m[127] + m[6]; clean[0] = false; counter = 132; },  // ADD [127], [6], [0]
+      130 => if m[0] == 0 { if m[99] < 0 || m[99] as usize >= LENGTH { clean[130] = false } else { counter = m[99] as usize } } else { counter = 133 },  // JUMP-IF-FALSE [0], [99]
+      132 => break,  // HALT
+      133 => { m[0] = m[14] * m[0]; clean[0] = false; counter = 137; },  // MULTIPLY [14], [0], [0]
+      _ => unreachable!(),
+    }
+  }
+  outputs
+}
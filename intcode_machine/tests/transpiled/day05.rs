@@ -0,0 +1,437 @@
+// Generated from an intcode program of 678 values, by intcode_machine::transpile.
+#![allow(clippy::all)]
+
+use std::collections::VecDeque;
+
+use intcode_machine::Program;
+use intcode_machine::interpret_step;
+use intcode_machine::run_intcode_program;
+
+const LENGTH: usize = 678;
+
+// the address of each compiled instruction, and the values it was compiled from
+const INSTRUCTIONS: &[(usize, &[i32])] = &[
+  (0, &[3, 225]),
+  (2, &[1, 225, 6, 6]),
+  (7, &[1, 238, 225, 104]),
+  (10, &[104, 0]),
+  (12, &[1102, 46, 47, 225]),
+  (16, &[2, 122, 130, 224]),
+  (20, &[101, -1998, 224, 224]),
+  (24, &[4, 224]),
+  (26, &[1002, 223, 8, 223]),
+  (30, &[1001, 224, 6, 224]),
+  (32, &[6, 224, 1]),
+  (34, &[1, 224, 223, 223]),
+  (38, &[1102, 61, 51, 225]),
+  (42, &[102, 32, 92, 224]),
+  (46, &[101, -800, 224, 224]),
+  (50, &[4, 224]),
+  (52, &[1002, 223, 8, 223]),
+  (56, &[1001, 224, 1, 224]),
+  (58, &[1, 224, 1, 223]),
+  (60, &[1, 223, 224, 223]),
+  (64, &[1101, 61, 64, 225]),
+  (68, &[1001, 118, 25, 224]),
+  (72, &[101, -106, 224, 224]),
+  (76, &[4, 224]),
+  (78, &[1002, 223, 8, 223]),
+  (80, &[8, 223, 101, 1]),
+  (82, &[101, 1, 224, 224]),
+  (83, &[1, 224, 224, 1]),
+  (86, &[1, 224, 223, 223]),
+  (90, &[1102, 33, 25, 225]),
+  (94, &[1102, 73, 67, 224]),
+  (98, &[101, -4891, 224, 224]),
+  (102, &[4, 224]),
+  (104, &[1002, 223, 8, 223]),
+  (108, &[1001, 224, 4, 224]),
+  (110, &[4, 224]),
+  (112, &[1, 224, 223, 223]),
+  (116, &[1101, 14, 81, 225]),
+  (120, &[1102, 17, 74, 225]),
+  (124, &[1102, 52, 67, 225]),
+  (128, &[1101, 94, 27, 225]),
+  (132, &[101, 71, 39, 224]),
+  (136, &[101, -132, 224, 224]),
+  (140, &[4, 224]),
+  (142, &[1002, 223, 8, 223]),
+  (144, &[8, 223, 101, 5]),
+  (146, &[101, 5, 224, 224]),
+  (147, &[5, 224, 224]),
+  (150, &[1, 224, 223, 223]),
+  (154, &[1002, 14, 38, 224]),
+  (158, &[101, -1786, 224, 224]),
+  (162, &[4, 224]),
+  (164, &[102, 8, 223, 223]),
+  (168, &[1001, 224, 2, 224]),
+  (170, &[2, 224, 1, 223]),
+  (172, &[1, 223, 224, 223]),
+  (176, &[1, 65, 126, 224]),
+  (180, &[1001, 224, -128, 224]),
+  (184, &[4, 224]),
+  (186, &[1002, 223, 8, 223]),
+  (188, &[8, 223, 101, 6]),
+  (190, &[101, 6, 224, 224]),
+  (191, &[6, 224, 224]),
+  (194, &[1, 224, 223, 223]),
+  (198, &[1101, 81, 40, 224]),
+  (202, &[1001, 224, -121, 224]),
+  (206, &[4, 224]),
+  (208, &[102, 8, 223, 223]),
+  (209, &[8, 223, 223, 101]),
+  (212, &[101, 4, 224, 224]),
+  (213, &[4, 224]),
+  (216, &[1, 223, 224, 223]),
+  (220, &[4, 223]),
+  (222, &[99]),
+  (241, &[1105, 227, 247]),
+  (250, &[1005, 0, 256]),
+  (259, &[1106, 0, 265]),
+  (268, &[1006, 227, 274]),
+  (274, &[1105, 1, 280]),
+  (280, &[1, 225, 225, 225]),
+  (284, &[1101, 294, 0, 0]),
+  (288, &[105, 1, 0]),
+  (294, &[1106, 0, 300]),
+  (300, &[1, 225, 225, 225]),
+  (304, &[1101, 314, 0, 0]),
+  (308, &[106, 0, 0]),
+  (314, &[1008, 677, 226, 224]),
+  (318, &[1002, 223, 2, 223]),
+  (322, &[1005, 224, 329]),
+  (325, &[1001, 223, 1, 223]),
+  (327, &[1, 223, 107, 677]),
+  (329, &[107, 677, 677, 224]),
+  (333, &[102, 2, 223, 223]),
+  (337, &[1005, 224, 344]),
+  (340, &[101, 1, 223, 223]),
+  (344, &[1107, 677, 677, 224]),
+  (348, &[102, 2, 223, 223]),
+  (352, &[1005, 224, 359]),
+  (355, &[1001, 223, 1, 223]),
+  (359, &[1108, 226, 226, 224]),
+  (363, &[1002, 223, 2, 223]),
+  (367, &[1006, 224, 374]),
+  (370, &[101, 1, 223, 223]),
+  (371, &[1, 223, 223, 107]),
+  (374, &[107, 226, 226, 224]),
+  (378, &[1002, 223, 2, 223]),
+  (382, &[1005, 224, 389]),
+  (385, &[1001, 223, 1, 223]),
+  (387, &[1, 223, 108, 226]),
+  (389, &[108, 226, 226, 224]),
+  (393, &[1002, 223, 2, 223]),
+  (397, &[1005, 224, 404]),
+  (400, &[1001, 223, 1, 223]),
+  (404, &[1008, 677, 677, 224]),
+  (408, &[1002, 223, 2, 223]),
+  (412, &[1006, 224, 419]),
+  (415, &[1001, 223, 1, 223]),
+  (419, &[1107, 677, 226, 224]),
+  (423, &[102, 2, 223, 223]),
+  (427, &[1005, 224, 434]),
+  (430, &[1001, 223, 1, 223]),
+  (432, &[1, 223, 108, 226]),
+  (434, &[108, 226, 677, 224]),
+  (438, &[102, 2, 223, 223]),
+  (442, &[1006, 224, 449]),
+  (445, &[1001, 223, 1, 223]),
+  (447, &[1, 223, 8, 677]),
+  (449, &[8, 677, 226, 224]),
+  (453, &[102, 2, 223, 223]),
+  (457, &[1006, 224, 464]),
+  (460, &[1001, 223, 1, 223]),
+  (464, &[1007, 677, 226, 224]),
+  (468, &[1002, 223, 2, 223]),
+  (472, &[1006, 224, 479]),
+  (475, &[1001, 223, 1, 223]),
+  (479, &[1007, 677, 677, 224]),
+  (483, &[1002, 223, 2, 223]),
+  (487, &[1005, 224, 494]),
+  (490, &[1001, 223, 1, 223]),
+  (494, &[1107, 226, 677, 224]),
+  (498, &[1002, 223, 2, 223]),
+  (502, &[1006, 224, 509]),
+  (505, &[101, 1, 223, 223]),
+  (509, &[1108, 226, 677, 224]),
+  (513, &[102, 2, 223, 223]),
+  (517, &[1005, 224, 524]),
+  (520, &[1001, 223, 1, 223]),
+  (522, &[1, 223, 7, 226]),
+  (524, &[7, 226, 226, 224]),
+  (528, &[102, 2, 223, 223]),
+  (532, &[1005, 224, 539]),
+  (535, &[1001, 223, 1, 223]),
+  (537, &[1, 223, 8, 677]),
+  (539, &[8, 677, 677, 224]),
+  (543, &[1002, 223, 2, 223]),
+  (547, &[1005, 224, 554]),
+  (550, &[101, 1, 223, 223]),
+  (551, &[1, 223, 223, 107]),
+  (554, &[107, 677, 226, 224]),
+  (558, &[102, 2, 223, 223]),
+  (562, &[1006, 224, 569]),
+  (565, &[1001, 223, 1, 223]),
+  (567, &[1, 223, 7, 226]),
+  (569, &[7, 226, 677, 224]),
+  (573, &[1002, 223, 2, 223]),
+  (577, &[1005, 224, 584]),
+  (580, &[1001, 223, 1, 223]),
+  (584, &[1008, 226, 226, 224]),
+  (588, &[1002, 223, 2, 223]),
+  (592, &[1006, 224, 599]),
+  (595, &[101, 1, 223, 223]),
+  (599, &[1108, 677, 226, 224]),
+  (603, &[102, 2, 223, 223]),
+  (607, &[1006, 224, 614]),
+  (610, &[101, 1, 223, 223]),
+  (611, &[1, 223, 223, 7]),
+  (614, &[7, 677, 226, 224]),
+  (618, &[102, 2, 223, 223]),
+  (622, &[1005, 224, 629]),
+  (625, &[1001, 223, 1, 223]),
+  (627, &[1, 223, 8, 226]),
+  (629, &[8, 226, 677, 224]),
+  (633, &[1002, 223, 2, 223]),
+  (637, &[1006, 224, 644]),
+  (640, &[101, 1, 223, 223]),
+  (644, &[1007, 226, 226, 224]),
+  (648, &[102, 2, 223, 223]),
+  (652, &[1005, 224, 659]),
+  (655, &[101, 1, 223, 223]),
+  (656, &[1, 223, 223, 108]),
+  (659, &[108, 677, 677, 224]),
+  (663, &[1002, 223, 2, 223]),
+  (667, &[1006, 224, 674]),
+  (670, &[1001, 223, 1, 223]),
+  (672, &[1, 223, 4, 223]),
+  (674, &[4, 223]),
+  (676, &[99]),
+];
+
+pub
+fn run(program: &mut Program, inputs: &[i32]) -> Vec<i32> {
+  if program.instructions.len() != LENGTH {
+    return run_intcode_program(program, &Some(inputs.to_vec()))
+  }
+  // an instruction only runs compiled while memory still matches it
+  let mut clean = [false; LENGTH];
+  for (address, values) in INSTRUCTIONS {
+    clean[*address] = program.instructions[*address..*address + values.len()] == **values;
+  }
+  let mut inputs: VecDeque<i32> = inputs.iter().cloned().collect();
+  let mut outputs = Vec::new();
+  let mut counter = 0;
+  while counter < LENGTH {
+    if !clean[counter] {
+      match interpret_step(program, counter, &mut inputs, &mut outputs, &mut clean) {
+        Some(next_counter) => counter = next_counter,
+        None => break,
+      }
+      continue;
+    }
+    let m = &mut program.instructions;
+    match counter {
+      0 => match inputs.pop_front() { Some(x) => { m[225] = x; counter = 2; }, None => clean[0] = false },  // INPUT [225]
+      2 => { m[6] = m[225] + m[6]; counter = 6; },  // ADD [225], [6], [6]
+      7 => { m[104] = m[238] + m[225]; clean[104] = false; counter = 11; },  // ADD [238], [225], [104]
+      10 => { outputs.push(0); counter = 12; },  // PRINT 0
+      12 => { m[225] = 2162; counter = 16; },  // MULTIPLY 46, 47, [225]
+      16 => { m[224] = m[122] * m[130]; counter = 20; },  // MULTIPLY [122], [130], [224]
+      20 => { m[224] = -1998 + m[224]; counter = 24; },  // ADD -1998, [224], [224]
+      24 => { outputs.push(m[224]); counter = 26; },  // PRINT [224]
+      26 => { m[223] = m[223] * 8; counter = 30; },  // MULTIPLY [223], 8, [223]
+      30 => { m[224] = m[224] + 6; counter = 34; },  // ADD [224], 6, [224]
+      32 => if m[224] == 0 { if m[1] < 0 || m[1] as usize >= LENGTH { clean[32] = false } else { counter = m[1] as usize } } else { counter = 35 },  // JUMP-IF-FALSE [224], [1]
+      34 => { m[223] = m[224] + m[223]; counter = 38; },  // ADD [224], [223], [223]
+      38 => { m[225] = 3111; counter = 42; },  // MULTIPLY 61, 51, [225]
+      42 => { m[224] = 32 * m[92]; counter = 46; },  // MULTIPLY 32, [92], [224]
+      46 => { m[224] = -800 + m[224]; counter = 50; },  // ADD -800, [224], [224]
+      50 => { outputs.push(m[224]); counter = 52; },  // PRINT [224]
+      52 => { m[223] = m[223] * 8; counter = 56; },  // MULTIPLY [223], 8, [223]
+      56 => { m[224] = m[224] + 1; counter = 60; },  // ADD [224], 1, [224]
+      58 => { m[223] = m[224] + m[1]; counter = 62; },  // ADD [224], [1], [223]
+      60 => { m[223] = m[223] + m[224]; counter = 64; },  // ADD [223], [224], [223]
+      64 => { m[225] = 125; counter = 68; },  // ADD 61, 64, [225]
+      68 => { m[224] = m[118] + 25; counter = 72; },  // ADD [118], 25, [224]
+      72 => { m[224] = -106 + m[224]; counter = 76; },  // ADD -106, [224], [224]
+      76 => { outputs.push(m[224]); counter = 78; },  // PRINT [224]
+      78 => { m[223] = m[223] * 8; counter = 82; },  // MULTIPLY [223], 8, [223]
+      80 => { m[1] = (m[223] == m[101]) as i32; clean[0] = false; counter = 84; },  // EQUALS [223], [101], [1]
+      82 => { m[224] = 1 + m[224]; counter = 86; },  // ADD 1, [224], [224]
+      83 => { m[1] = m[224] + m[224]; clean[0] = false; counter = 87; },  // ADD [224], [224], [1]
+      86 => { m[223] = m[224] + m[223]; counter = 90; },  // ADD [224], [223], [223]
+      90 => { m[225] = 825; counter = 94; },  // MULTIPLY 33, 25, [225]
+      94 => { m[224] = 4891; counter = 98; },  // MULTIPLY 73, 67, [224]
+      98 => { m[224] = -4891 + m[224]; counter = 102; },  // ADD -4891, [224], [224]
+      102 => { outputs.push(m[224]); counter = 104; },  // PRINT [224]
+      104 => { m[223] = m[223] * 8; counter = 108; },  // MULTIPLY [223], 8, [223]
+      108 => { m[224] = m[224] + 4; counter = 112; },  // ADD [224], 4, [224]
+      110 => { outputs.push(m[224]); counter = 112; },  // PRINT [224]
+      112 => { m[223] = m[224] + m[223]; counter = 116; },  // ADD [224], [223], [223]
+      116 => { m[225] = 95; counter = 120; },  // ADD 14, 81, [225]
+      120 => { m[225] = 1258; counter = 124; },  // MULTIPLY 17, 74, [225]
+      124 => { m[225] = 3484; counter = 128; },  // MULTIPLY 52, 67, [225]
+      128 => { m[225] = 121; counter = 132; },  // ADD 94, 27, [225]
+      132 => { m[224] = 71 + m[39]; counter = 136; },  // ADD 71, [39], [224]
+      136 => { m[224] = -132 + m[224]; counter = 140; },  // ADD -132, [224], [224]
+      140 => { outputs.push(m[224]); counter = 142; },  // PRINT [224]
+      142 => { m[223] = m[223] * 8; counter = 146; },  // MULTIPLY [223], 8, [223]
+      144 => { m[5] = (m[223] == m[101]) as i32; clean[2] = false; counter = 148; },  // EQUALS [223], [101], [5]
+      146 => { m[224] = 5 + m[224]; counter = 150; },  // ADD 5, [224], [224]
+      147 => if m[224] != 0 { if m[224] < 0 || m[224] as usize >= LENGTH { clean[147] = false } else { counter = m[224] as usize } } else { counter = 150 },  // JUMP-IF-TRUE [224], [224]
+      150 => { m[223] = m[224] + m[223]; counter = 154; },  // ADD [224], [223], [223]
+      154 => { m[224] = m[14] * 38; counter = 158; },  // MULTIPLY [14], 38, [224]
+      158 => { m[224] = -1786 + m[224]; counter = 162; },  // ADD -1786, [224], [224]
+      162 => { outputs.push(m[224]); counter = 164; },  // PRINT [224]
+      164 => { m[223] = 8 * m[223]; counter = 168; },  // MULTIPLY 8, [223], [223]
+      168 => { m[224] = m[224] + 2; counter = 172; },  // ADD [224], 2, [224]
+      170 => { m[223] = m[224] * m[1]; counter = 174; },  // MULTIPLY [224], [1], [223]
+      172 => { m[223] = m[223] + m[224]; counter = 176; },  // ADD [223], [224], [223]
+      176 => { m[224] = m[65] + m[126]; counter = 180; },  // ADD [65], [126], [224]
+      180 => { m[224] = m[224] + -128; counter = 184; },  // ADD [224], -128, [224]
+      184 => { outputs.push(m[224]); counter = 186; },  // PRINT [224]
+      186 => { m[223] = m[223] * 8; counter = 190; },  // MULTIPLY [223], 8, [223]
+      188 => { m[6] = (m[223] == m[101]) as i32; counter = 192; },  // EQUALS [223], [101], [6]
+      190 => { m[224] = 6 + m[224]; counter = 194; },  // ADD 6, [224], [224]
+      191 => if m[224] == 0 { if m[224] < 0 || m[224] as usize >= LENGTH { clean[191] = false } else { counter = m[224] as usize } } else { counter = 194 },  // JUMP-IF-FALSE [224], [224]
+      194 => { m[223] = m[224] + m[223]; counter = 198; },  // ADD [224], [223], [223]
+      198 => { m[224] = 121; counter = 202; },  // ADD 81, 40, [224]
+      202 => { m[224] = m[224] + -121; counter = 206; },  // ADD [224], -121, [224]
+      206 => { outputs.push(m[224]); counter = 208; },  // PRINT [224]
+      208 => { m[223] = 8 * m[223]; counter = 212; },  // MULTIPLY 8, [223], [223]
+      209 => { m[101] = (m[223] == m[223]) as i32; clean[98] = false; counter = 213; },  // EQUALS [223], [223], [101]
+      212 => { m[224] = 4 + m[224]; counter = 216; },  // ADD 4, [224], [224]
+      213 => { outputs.push(m[224]); counter = 215; },  // PRINT [224]
+      216 => { m[223] = m[223] + m[224]; counter = 220; },  // ADD [223], [224], [223]
+      220 => { outputs.push(m[223]); counter = 222; },  // PRINT [223]
+      222 => break,  // HALT
+      241 => counter = 247,  // JUMP-IF-TRUE 227, 247
+      250 => if m[0] != 0 { counter = 256 } else { counter = 253 },  // JUMP-IF-TRUE [0], 256
+      259 => counter = 265,  // JUMP-IF-FALSE 0, 265
+      268 => if m[227] == 0 { counter = 274 } else { counter = 271 },  // JUMP-IF-FALSE [227], 274
+      274 => counter = 280,  // JUMP-IF-TRUE 1, 280
+      280 => { m[225] = m[225] + m[225]; counter = 284; },  // ADD [225], [225], [225]
+      284 => { m[0] = 294; clean[0] = false; counter = 288; },  // ADD 294, 0, [0]
+      288 => if m[0] < 0 || m[0] as usize >= LENGTH { clean[288] = false } else { counter = m[0] as usize },  // JUMP-IF-TRUE 1, [0]
+      294 => counter = 300,  // JUMP-IF-FALSE 0, 300
+      300 => { m[225] = m[225] + m[225]; counter = 304; },  // ADD [225], [225], [225]
+      304 => { m[0] = 314; clean[0] = false; counter = 308; },  // ADD 314, 0, [0]
+      308 => if m[0] < 0 || m[0] as usize >= LENGTH { clean[308] = false } else { counter = m[0] as usize },  // JUMP-IF-FALSE 0, [0]
+      314 => { m[224] = (m[677] == 226) as i32; counter = 318; },  // EQUALS [677], 226, [224]
+      318 => { m[223] = m[223] * 2; counter = 322; },  // MULTIPLY [223], 2, [223]
+      322 => if m[224] != 0 { counter = 329 } else { counter = 325 },  // JUMP-IF-TRUE [224], 329
+      325 => { m[223] = m[223] + 1; counter = 329; },  // ADD [223], 1, [223]
+      327 => { m[677] = m[223] + m[107]; counter = 331; },  // ADD [223], [107], [677]
+      329 => { m[224] = (677 < m[677]) as i32; counter = 333; },  // LESS-THAN 677, [677], [224]
+      333 => { m[223] = 2 * m[223]; counter = 337; },  // MULTIPLY 2, [223], [223]
+      337 => if m[224] != 0 { counter = 344 } else { counter = 340 },  // JUMP-IF-TRUE [224], 344
+      340 => { m[223] = 1 + m[223]; counter = 344; },  // ADD 1, [223], [223]
+      344 => { m[224] = 0; counter = 348; },  // LESS-THAN 677, 677, [224]
+      348 => { m[223] = 2 * m[223]; counter = 352; },  // MULTIPLY 2, [223], [223]
+      352 => if m[224] != 0 { counter = 359 } else { counter = 355 },  // JUMP-IF-TRUE [224], 359
+      355 => { m[223] = m[223] + 1; counter = 359; },  // ADD [223], 1, [223]
+      359 => { m[224] = 1; counter = 363; },  // EQUALS 226, 226, [224]
+      363 => { m[223] = m[223] * 2; counter = 367; },  // MULTIPLY [223], 2, [223]
+      367 => if m[224] == 0 { counter = 374 } else { counter = 370 },  // JUMP-IF-FALSE [224], 374
+      370 => { m[223] = 1 + m[223]; counter = 374; },  // ADD 1, [223], [223]
+      371 => { m[107] = m[223] + m[223]; clean[104] = false; counter = 375; },  // ADD [223], [223], [107]
+      374 => { m[224] = (226 < m[226]) as i32; counter = 378; },  // LESS-THAN 226, [226], [224]
+      378 => { m[223] = m[223] * 2; counter = 382; },  // MULTIPLY [223], 2, [223]
+      382 => if m[224] != 0 { counter = 389 } else { counter = 385 },  // JUMP-IF-TRUE [224], 389
+      385 => { m[223] = m[223] + 1; counter = 389; },  // ADD [223], 1, [223]
+      387 => { m[226] = m[223] + m[108]; counter = 391; },  // ADD [223], [108], [226]
+      389 => { m[224] = (226 == m[226]) as i32; counter = 393; },  // EQUALS 226, [226], [224]
+      393 => { m[223] = m[223] * 2; counter = 397; },  // MULTIPLY [223], 2, [223]
+      397 => if m[224] != 0 { counter = 404 } else { counter = 400 },  // JUMP-IF-TRUE [224], 404
+      400 => { m[223] = m[223] + 1; counter = 404; },  // ADD [223], 1, [223]
+      404 => { m[224] = (m[677] == 677) as i32; counter = 408; },  // EQUALS [677], 677, [224]
+      408 => { m[223] = m[223] * 2; counter = 412; },  // MULTIPLY [223], 2, [223]
+      412 => if m[224] == 0 { counter = 419 } else { counter = 415 },  // JUMP-IF-FALSE [224], 419
+      415 => { m[223] = m[223] + 1; counter = 419; },  // ADD [223], 1, [223]
+      419 => { m[224] = 0; counter = 423; },  // LESS-THAN 677, 226, [224]
+      423 => { m[223] = 2 * m[223]; counter = 427; },  // MULTIPLY 2, [223], [223]
+      427 => if m[224] != 0 { counter = 434 } else { counter = 430 },  // JUMP-IF-TRUE [224], 434
+      430 => { m[223] = m[223] + 1; counter = 434; },  // ADD [223], 1, [223]
+      432 => { m[226] = m[223] + m[108]; counter = 436; },  // ADD [223], [108], [226]
+      434 => { m[224] = (226 == m[677]) as i32; counter = 438; },  // EQUALS 226, [677], [224]
+      438 => { m[223] = 2 * m[223]; counter = 442; },  // MULTIPLY 2, [223], [223]
+      442 => if m[224] == 0 { counter = 449 } else { counter = 445 },  // JUMP-IF-FALSE [224], 449
+      445 => { m[223] = m[223] + 1; counter = 449; },  // ADD [223], 1, [223]
+      447 => { m[677] = m[223] + m[8]; counter = 451; },  // ADD [223], [8], [677]
+      449 => { m[224] = (m[677] == m[226]) as i32; counter = 453; },  // EQUALS [677], [226], [224]
+      453 => { m[223] = 2 * m[223]; counter = 457; },  // MULTIPLY 2, [223], [223]
+      457 => if m[224] == 0 { counter = 464 } else { counter = 460 },  // JUMP-IF-FALSE [224], 464
+      460 => { m[223] = m[223] + 1; counter = 464; },  // ADD [223], 1, [223]
+      464 => { m[224] = (m[677] < 226) as i32; counter = 468; },  // LESS-THAN [677], 226, [224]
+      468 => { m[223] = m[223] * 2; counter = 472; },  // MULTIPLY [223], 2, [223]
+      472 => if m[224] == 0 { counter = 479 } else { counter = 475 },  // JUMP-IF-FALSE [224], 479
+      475 => { m[223] = m[223] + 1; counter = 479; },  // ADD [223], 1, [223]
+      479 => { m[224] = (m[677] < 677) as i32; counter = 483; },  // LESS-THAN [677], 677, [224]
+      483 => { m[223] = m[223] * 2; counter = 487; },  // MULTIPLY [223], 2, [223]
+      487 => if m[224] != 0 { counter = 494 } else { counter = 490 },  // JUMP-IF-TRUE [224], 494
+      490 => { m[223] = m[223] + 1; counter = 494; },  // ADD [223], 1, [223]
+      494 => { m[224] = 1; counter = 498; },  // LESS-THAN 226, 677, [224]
+      498 => { m[223] = m[223] * 2; counter = 502; },  // MULTIPLY [223], 2, [223]
+      502 => if m[224] == 0 { counter = 509 } else { counter = 505 },  // JUMP-IF-FALSE [224], 509
+      505 => { m[223] = 1 + m[223]; counter = 509; },  // ADD 1, [223], [223]
+      509 => { m[224] = 0; counter = 513; },  // EQUALS 226, 677, [224]
+      513 => { m[223] = 2 * m[223]; counter = 517; },  // MULTIPLY 2, [223], [223]
+      517 => if m[224] != 0 { counter = 524 } else { counter = 520 },  // JUMP-IF-TRUE [224], 524
+      520 => { m[223] = m[223] + 1; counter = 524; },  // ADD [223], 1, [223]
+      522 => { m[226] = m[223] + m[7]; counter = 526; },  // ADD [223], [7], [226]
+      524 => { m[224] = (m[226] < m[226]) as i32; counter = 528; },  // LESS-THAN [226], [226], [224]
+      528 => { m[223] = 2 * m[223]; counter = 532; },  // MULTIPLY 2, [223], [223]
+      532 => if m[224] != 0 { counter = 539 } else { counter = 535 },  // JUMP-IF-TRUE [224], 539
+      535 => { m[223] = m[223] + 1; counter = 539; },  // ADD [223], 1, [223]
+      537 => { m[677] = m[223] + m[8]; counter = 541; },  // ADD [223], [8], [677]
+      539 => { m[224] = (m[677] == m[677]) as i32; counter = 543; },  // EQUALS [677], [677], [224]
+      543 => { m[223] = m[223] * 2; counter = 547; },  // MULTIPLY [223], 2, [223]
+      547 => if m[224] != 0 { counter = 554 } else { counter = 550 },  // JUMP-IF-TRUE [224], 554
+      550 => { m[223] = 1 + m[223]; counter = 554; },  // ADD 1, [223], [223]
+      551 => { m[107] = m[223] + m[223]; clean[104] = false; counter = 555; },  // ADD [223], [223], [107]
+      554 => { m[224] = (677 < m[226]) as i32; counter = 558; },  // LESS-THAN 677, [226], [224]
+      558 => { m[223] = 2 * m[223]; counter = 562; },  // MULTIPLY 2, [223], [223]
+      562 => if m[224] == 0 { counter = 569 } else { counter = 565 },  // JUMP-IF-FALSE [224], 569
+      565 => { m[223] = m[223] + 1; counter = 569; },  // ADD [223], 1, [223]
+      567 => { m[226] = m[223] + m[7]; counter = 571; },  // ADD [223], [7], [226]
+      569 => { m[224] = (m[226] < m[677]) as i32; counter = 573; },  // LESS-THAN [226], [677], [224]
+      573 => { m[223] = m[223] * 2; counter = 577; },  // MULTIPLY [223], 2, [223]
+      577 => if m[224] != 0 { counter = 584 } else { counter = 580 },  // JUMP-IF-TRUE [224], 584
+      580 => { m[223] = m[223] + 1; counter = 584; },  // ADD [223], 1, [223]
+      584 => { m[224] = (m[226] == 226) as i32; counter = 588; },  // EQUALS [226], 226, [224]
+      588 => { m[223] = m[223] * 2; counter = 592; },  // MULTIPLY [223], 2, [223]
+      592 => if m[224] == 0 { counter = 599 } else { counter = 595 },  // JUMP-IF-FALSE [224], 599
+      595 => { m[223] = 1 + m[223]; counter = 599; },  // ADD 1, [223], [223]
+      599 => { m[224] = 0; counter = 603; },  // EQUALS 677, 226, [224]
+      603 => { m[223] = 2 * m[223]; counter = 607; },  // MULTIPLY 2, [223], [223]
+      607 => if m[224] == 0 { counter = 614 } else { counter = 610 },  // JUMP-IF-FALSE [224], 614
+      610 => { m[223] = 1 + m[223]; counter = 614; },  // ADD 1, [223], [223]
+      611 => { m[7] = m[223] + m[223]; clean[7] = false; counter = 615; },  // ADD [223], [223], [7]
+      614 => { m[224] = (m[677] < m[226]) as i32; counter = 618; },  // LESS-THAN [677], [226], [224]
+      618 => { m[223] = 2 * m[223]; counter = 622; },  // MULTIPLY 2, [223], [223]
+      622 => if m[224] != 0 { counter = 629 } else { counter = 625 },  // JUMP-IF-TRUE [224], 629
+      625 => { m[223] = m[223] + 1; counter = 629; },  // ADD [223], 1, [223]
+      627 => { m[226] = m[223] + m[8]; counter = 631; },  // ADD [223], [8], [226]
+      629 => { m[224] = (m[226] == m[677]) as i32; counter = 633; },  // EQUALS [226], [677], [224]
+      633 => { m[223] = m[223] * 2; counter = 637; },  // MULTIPLY [223], 2, [223]
+      637 => if m[224] == 0 { counter = 644 } else { counter = 640 },  // JUMP-IF-FALSE [224], 644
+      640 => { m[223] = 1 + m[223]; counter = 644; },  // ADD 1, [223], [223]
+      644 => { m[224] = (m[226] < 226) as i32; counter = 648; },  // LESS-THAN [226], 226, [224]
+      648 => { m[223] = 2 * m[223]; counter = 652; },  // MULTIPLY 2, [223], [223]
+      652 => if m[224] != 0 { counter = 659 } else { counter = 655 },  // JUMP-IF-TRUE [224], 659
+      655 => { m[223] = 1 + m[223]; counter = 659; },  // ADD 1, [223], [223]
+      656 => { m[108] = m[223] + m[223]; clean[108] = false; counter = 660; },  // ADD [223], [223], [108]
+      659 => { m[224] = (677 == m[677]) as i32; counter = 663; },  // EQUALS 677, [677], [224]
+      663 => { m[223] = m[223] * 2; counter = 667; },  // MULTIPLY [223], 2, [223]
+      667 => if m[224] == 0 { counter = 674 } else { counter = 670 },  // JUMP-IF-FALSE [224], 674
+      670 => { m[223] = m[223] + 1; counter = 674; },  // ADD [223], 1, [223]
+      672 => { m[223] = m[223] + m[4]; counter = 676; },  // ADD [223], [4], [223]
+      674 => { outputs.push(m[223]); counter = 676; },  // PRINT [223]
+      676 => break,  // HALT
+      _ => unreachable!(),
+    }
+  }
+  outputs
+}
@@ -0,0 +1,75 @@
+// Generated from an intcode program of 47 values, by intcode_machine::transpile.
+#![allow(clippy::all)]
+
+use std::collections::VecDeque;
+
+use intcode_machine::Program;
+use intcode_machine::interpret_step;
+use intcode_machine::run_intcode_program;
+
+const LENGTH: usize = 47;
+
+// the address of each compiled instruction, and the values it was compiled from
+const INSTRUCTIONS: &[(usize, &[i32])] = &[
+  (0, &[3, 21]),
+  (2, &[1008, 21, 8, 20]),
+  (6, &[1005, 20, 22]),
+  (9, &[107, 8, 21, 20]),
+  (13, &[1006, 20, 31]),
+  (16, &[1106, 0, 36]),
+  (22, &[1002, 21, 125, 20]),
+  (26, &[4, 20]),
+  (28, &[1105, 1, 46]),
+  (31, &[104, 999]),
+  (33, &[1105, 1, 46]),
+  (36, &[1101, 1000, 1, 20]),
+  (38, &[1, 20, 4, 20]),
+  (40, &[4, 20]),
+  (42, &[1105, 1, 46]),
+  (46, &[99]),
+];
+
+pub
+fn run(program: &mut Program, inputs: &[i32]) -> Vec<i32> {
+  if program.instructions.len() != LENGTH {
+    return run_intcode_program(program, &Some(inputs.to_vec()))
+  }
+  // an instruction only runs compiled while memory still matches it
+  let mut clean = [false; LENGTH];
+  for (address, values) in INSTRUCTIONS {
+    clean[*address] = program.instructions[*address..*address + values.len()] == **values;
+  }
+  let mut inputs: VecDeque<i32> = inputs.iter().cloned().collect();
+  let mut outputs = Vec::new();
+  let mut counter = 0;
+  while counter < LENGTH {
+    if !clean[counter] {
+      match interpret_step(program, counter, &mut inputs, &mut outputs, &mut clean) {
+        Some(next_counter) => counter = next_counter,
+        None => break,
+      }
+      continue;
+    }
+    let m = &mut program.instructions;
+    match counter {
+      0 => match inputs.pop_front() { Some(x) => { m[21] = x; counter = 2; }, None => clean[0] = false },  // INPUT [21]
+      2 => { m[20] = (m[21] == 8) as i32; counter = 6; },  // EQUALS [21], 8, [20]
+      6 => if m[20] != 0 { counter = 22 } else { counter = 9 },  // JUMP-IF-TRUE [20], 22
+      9 => { m[20] = (8 < m[21]) as i32; counter = 13; },  // LESS-THAN 8, [21], [20]
+      13 => if m[20] == 0 { counter = 31 } else { counter = 16 },  // JUMP-IF-FALSE [20], 31
+      16 => counter = 36,  // JUMP-IF-FALSE 0, 36
+      22 => { m[20] = m[21] * 125; counter = 26; },  // MULTIPLY [21], 125, [20]
+      26 => { outputs.push(m[20]); counter = 28; },  // PRINT [20]
+      28 => counter = 46,  // JUMP-IF-TRUE 1, 46
+      31 => { outputs.push(999); counter = 33; },  // PRINT 999
+      33 => counter = 46,  // JUMP-IF-TRUE 1, 46
+      36 => { m[20] = 1001; counter = 40; },  // ADD 1000, 1, [20]
+      38 => { m[20] = m[20] + m[4]; counter = 42; },  // ADD [20], [4], [20]
+      40 => { outputs.push(m[20]); counter = 42; },  // PRINT [20]
+      42 => counter = 46,  // JUMP-IF-TRUE 1, 46
+      46 => break,  // HALT
+      _ => unreachable!(),
+    }
+  }
+  outputs
+}